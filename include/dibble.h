@@ -0,0 +1,62 @@
+#ifndef DIBBLE_H
+#define DIBBLE_H
+
+/* Generated by cbindgen from src/ffi.rs; don't edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// An open dictionary, from [`dibble_open`]
+typedef struct DibbleDictionary DibbleDictionary;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Opens the dictionary at `path`, a shard tree or `.dibble` archive, or the
+// installed dictionaries if `path` is null. Returns null on failure; see
+// [`dibble_last_error`]. Close it with [`dibble_close`].
+//
+// # Safety
+//
+// `path` must be null or a NUL-terminated UTF-8 string.
+DibbleDictionary *dibble_open(const char *path);
+
+// Looks up `word` in `dict`, returning its entry as JSON, shaped as in a
+// shard. Returns null if the word isn't there, with no error set, or on
+// failure, with one set; see [`dibble_last_error`]. Free the result with
+// [`dibble_free`].
+//
+// # Safety
+//
+// `dict` must come from [`dibble_open`] and not have been closed, and `word`
+// must be a NUL-terminated UTF-8 string.
+char *dibble_lookup_json(const DibbleDictionary *dict, const char *word);
+
+// Frees a string returned by dibble. Does nothing if `s` is null.
+//
+// # Safety
+//
+// `s` must be null or a string from [`dibble_lookup_json`] that hasn't been
+// freed already.
+void dibble_free(char *s);
+
+// Closes a dictionary from [`dibble_open`]. Does nothing if `dict` is null.
+//
+// # Safety
+//
+// `dict` must be null or come from [`dibble_open`] and not have been
+// closed already.
+void dibble_close(DibbleDictionary *dict);
+
+// Why the last call on this thread failed, or null if it didn't. The string
+// belongs to dibble and is only valid until the next call on the thread.
+const char *dibble_last_error(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* DIBBLE_H */
@@ -0,0 +1,161 @@
+use crate::{Dictionary, sharding::ShardScheme};
+use anyhow::{Context, Result};
+use ruzstd::{
+    decoding::StreamingDecoder,
+    encoding::{CompressionLevel, compress_to_vec},
+};
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+/// File extension that marks a dictionary root as an archive rather than a directory
+pub const EXTENSION: &str = "dibble";
+
+const MAGIC: &[u8; 8] = b"DIBBLEAR";
+const VERSION: u32 = 1;
+
+/// A whole shard tree in one file: a header, a table of shard names with the
+/// offset and length of each, then every shard as its own zstd frame. Only the
+/// table and the requested shard are read on lookup.
+#[derive(Debug)]
+pub struct Archive {
+    file: File,
+    data_start: u64,
+    shards: BTreeMap<String, (u64, u64)>,
+}
+
+/// Whether `root` names an archive rather than a shard directory
+pub fn is_archive(root: &Path) -> bool {
+    root.extension().is_some_and(|ext| ext == EXTENSION)
+}
+
+impl Archive {
+    pub fn open(path: &Path) -> Result<Self> {
+        let file =
+            File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+        let mut reader = BufReader::new(&file);
+
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            anyhow::bail!("{} is not a dibble archive", path.display());
+        }
+        let version = read_u32(&mut reader)?;
+        if version != VERSION {
+            anyhow::bail!(
+                "{} has archive version {}, expected {}",
+                path.display(),
+                version,
+                VERSION
+            );
+        }
+
+        let count = read_u32(&mut reader)?;
+        let mut data_start = 16;
+        let mut shards = BTreeMap::new();
+        for _ in 0..count {
+            let mut len = [0u8; 2];
+            reader.read_exact(&mut len)?;
+            let mut name = vec![0u8; u16::from_le_bytes(len) as usize];
+            reader.read_exact(&mut name)?;
+            let offset = read_u64(&mut reader)?;
+            let size = read_u64(&mut reader)?;
+
+            data_start += 2 + name.len() as u64 + 16;
+            shards.insert(String::from_utf8(name)?, (offset, size));
+        }
+
+        Ok(Self {
+            file,
+            data_start,
+            shards,
+        })
+    }
+
+    /// Every shard in the archive, relative and without extension
+    pub fn shards(&self) -> impl Iterator<Item = PathBuf> + '_ {
+        self.shards.keys().map(PathBuf::from)
+    }
+
+    /// Decompresses a single shard, or returns `None` if the archive lacks it
+    pub fn read_shard(&self, shard: &Path) -> Result<Option<String>> {
+        let Some(&(offset, size)) = self.shards.get(&shard_key(shard)) else {
+            return Ok(None);
+        };
+
+        let mut file = &self.file;
+        file.seek(SeekFrom::Start(self.data_start + offset))?;
+        let mut decoder = StreamingDecoder::new(file.take(size))?;
+
+        let mut contents = String::new();
+        decoder.read_to_string(&mut contents)?;
+        Ok(Some(contents))
+    }
+
+    /// Packs every shard of `dict` into a new archive at `path`. Returns the
+    /// number of shards written. Archives have no manifest, so only a tree
+    /// sharded by the default scheme can be packed.
+    pub fn build(dict: &Dictionary, path: &Path) -> Result<usize> {
+        if dict.scheme() != ShardScheme::default() {
+            anyhow::bail!(
+                "Only dictionaries sharded by {} can be packed; this one is sharded by {}",
+                ShardScheme::default(),
+                dict.scheme()
+            );
+        }
+        let mut table = Vec::new();
+        let mut data = Vec::new();
+
+        for shard in dict.shards()? {
+            let contents = dict.read_shard(&shard)?;
+            let compressed = compress_to_vec(contents.as_bytes(), CompressionLevel::Fastest);
+            table.push((
+                shard_key(&shard),
+                data.len() as u64,
+                compressed.len() as u64,
+            ));
+            data.extend_from_slice(&compressed);
+        }
+
+        let partial = path.with_extension("partial");
+        let mut out = BufWriter::new(File::create(&partial)?);
+        out.write_all(MAGIC)?;
+        out.write_all(&VERSION.to_le_bytes())?;
+        out.write_all(&(table.len() as u32).to_le_bytes())?;
+        for (name, offset, size) in &table {
+            out.write_all(&(name.len() as u16).to_le_bytes())?;
+            out.write_all(name.as_bytes())?;
+            out.write_all(&offset.to_le_bytes())?;
+            out.write_all(&size.to_le_bytes())?;
+        }
+        out.write_all(&data)?;
+        out.into_inner()?.sync_all()?;
+
+        fs::rename(&partial, path)?;
+        Ok(table.len())
+    }
+}
+
+/// Shard names are stored with `/` separators whatever the platform
+fn shard_key(shard: &Path) -> String {
+    shard
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn read_u32(reader: &mut impl Read) -> Result<u32> {
+    let mut buf = [0u8; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64(reader: &mut impl Read) -> Result<u64> {
+    let mut buf = [0u8; 8];
+    reader.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
@@ -0,0 +1,145 @@
+#[cfg(feature = "net")]
+use crate::Dictionary;
+use crate::{Definition, Pronunciation, paths::project_dirs};
+use anyhow::{Context, Result};
+#[cfg(any(feature = "net", feature = "audio"))]
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// Where downloaded recordings are kept: `audio` in the user cache dir
+pub fn cache_dir() -> Result<PathBuf> {
+    let dirs = project_dirs().context("Could not determine the user cache directory")?;
+    Ok(dirs.cache_dir().join("audio"))
+}
+
+/// Whether a recording is somewhere to download it from rather than a file
+/// bundled with the dictionary
+fn is_url(audio: &str) -> bool {
+    audio.starts_with("http://") || audio.starts_with("https://")
+}
+
+/// The first pronunciation of `def` with a recording, preferring one for
+/// `accent` (compared case-insensitively) when given
+pub fn pick<'a>(def: &'a Definition, accent: Option<&str>) -> Option<&'a Pronunciation> {
+    let mut recorded = def.pronunciations.iter().filter(|p| p.audio.is_some());
+    let first = recorded.clone().next();
+    let Some(accent) = accent else {
+        return first;
+    };
+    recorded
+        .find(|p| {
+            p.accent
+                .as_deref()
+                .is_some_and(|a| a.eq_ignore_ascii_case(accent))
+        })
+        .or(first)
+}
+
+/// Where the download of `url` is cached: the host and the file name from the
+/// URL under [`cache_dir`]
+fn cached_path(url: &str) -> Result<PathBuf> {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let rest = rest.split(['?', '#']).next().unwrap_or_default();
+    let host = rest.split('/').next().unwrap_or_default();
+    let name = rest.rsplit('/').next().unwrap_or_default();
+    if host.is_empty() || name.is_empty() || name == host || name.starts_with('.') {
+        anyhow::bail!("Can't tell what to call the recording at {}", url);
+    }
+    Ok(cache_dir()?.join(host).join(name))
+}
+
+/// Finds the file for a recording: a bundled one in the first of `roots` that
+/// has it, or the cached download of a URL, downloading it first if needed
+pub fn locate(audio: &str, roots: &[PathBuf]) -> Result<PathBuf> {
+    if !is_url(audio) {
+        // a bundled recording has to stay inside its dictionary
+        if !Path::new(audio)
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)))
+        {
+            anyhow::bail!("Recording path {} leaves the dictionary root", audio);
+        }
+        return roots
+            .iter()
+            .filter(|root| root.is_dir())
+            .map(|root| root.join(audio))
+            .find(|path| path.is_file())
+            .with_context(|| format!("Recording {} isn't in any dictionary root", audio));
+    }
+
+    let path = cached_path(audio)?;
+    if path.is_file() {
+        return Ok(path);
+    }
+    #[cfg(feature = "net")]
+    {
+        download(audio, &path)?;
+        Ok(path)
+    }
+    #[cfg(not(feature = "net"))]
+    anyhow::bail!(
+        "Recording {} hasn't been downloaded, and dibble was built without network support",
+        audio
+    )
+}
+
+/// Downloads `url` to `path`, through a temporary file so an interrupted
+/// download doesn't leave a broken recording in the cache
+#[cfg(feature = "net")]
+fn download(url: &str, path: &Path) -> Result<()> {
+    let data = ureq::get(url)
+        .call()
+        .with_context(|| format!("Failed to download {}", url))?
+        .body_mut()
+        .with_config()
+        .limit(u64::MAX)
+        .read_to_vec()?;
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    let partial = path.with_extension("part");
+    fs::write(&partial, data).with_context(|| format!("Failed to write {}", partial.display()))?;
+    fs::rename(&partial, path).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(())
+}
+
+/// Downloads every recording the entries in `dict` link to that isn't cached
+/// yet. Returns how many were downloaded.
+#[cfg(feature = "net")]
+pub fn download_all(dict: &Dictionary) -> Result<usize> {
+    let mut downloaded = 0;
+    for shard in dict.shards()? {
+        for def in dict.load_shard(&shard)?.values() {
+            for url in def
+                .pronunciations
+                .iter()
+                .filter_map(|p| p.audio.as_deref())
+                .filter(|audio| is_url(audio))
+            {
+                let path = cached_path(url)?;
+                if !path.is_file() {
+                    download(url, &path)?;
+                    downloaded += 1;
+                }
+            }
+        }
+    }
+    Ok(downloaded)
+}
+
+/// Plays the recording at `path` on the default output device and waits for
+/// it to finish
+#[cfg(feature = "audio")]
+pub fn play(path: &Path) -> Result<()> {
+    let data = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+
+    let mut sink = rodio::DeviceSinkBuilder::open_default_sink()
+        .context("Failed to open the default audio device")?;
+    sink.log_on_drop(false);
+    let player = rodio::play(sink.mixer(), std::io::Cursor::new(data))
+        .with_context(|| format!("Failed to play {}", path.display()))?;
+    player.sleep_until_end();
+    Ok(())
+}
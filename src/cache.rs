@@ -0,0 +1,123 @@
+use crate::{Definition, index::existing_roots, paths::project_dirs};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// How many entries the recent-lookup cache keeps
+pub const CAPACITY: usize = 64;
+
+const FILE_NAME: &str = "recent.msgpack";
+
+/// The most recently resolved entries, newest first, kept in the user cache
+/// dir so looking a word up again doesn't touch the dictionary at all. The
+/// entries only stand for the roots they were looked up in, so opening the
+/// cache for any other roots starts it afresh.
+#[derive(Debug, Default)]
+pub struct RecentCache {
+    path: PathBuf,
+    roots: Vec<PathBuf>,
+    entries: Vec<Definition>,
+    dirty: bool,
+}
+
+/// The cache file: the full paths of the roots that existed, and the entries
+#[derive(Default, Serialize, Deserialize)]
+struct Stored {
+    roots: Vec<PathBuf>,
+    entries: Vec<Definition>,
+}
+
+/// Where the recent-lookup cache lives: the user cache dir
+pub fn default_path() -> Result<PathBuf> {
+    let dirs = project_dirs().context("Could not determine the user cache directory")?;
+    Ok(dirs.cache_dir().join(FILE_NAME))
+}
+
+/// Deletes the cache file, if there is one
+pub fn clear() -> Result<()> {
+    let path = default_path()?;
+    match fs::remove_file(&path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            Err(e).with_context(|| format!("Failed to remove {}", path.display()))
+        }
+        _ => Ok(()),
+    }
+}
+
+impl RecentCache {
+    /// Loads the cache at the default path for lookups in `roots`
+    pub fn open(roots: &[PathBuf]) -> Result<Self> {
+        Self::open_at(&default_path()?, roots)
+    }
+
+    /// Loads the cache at `path` for lookups in `roots`. A missing or
+    /// unreadable file, or one kept for other roots, gives an empty cache
+    /// rather than an error, since it will just be rewritten.
+    pub fn open_at(path: &Path, roots: &[PathBuf]) -> Result<Self> {
+        let roots = existing_roots(roots);
+        let stored: Stored = fs::read(path)
+            .ok()
+            .and_then(|data| rmp_serde::from_slice(&data).ok())
+            .unwrap_or_default();
+        let entries = if stored.roots == roots {
+            stored.entries
+        } else {
+            Vec::new()
+        };
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            roots,
+            entries,
+            dirty: false,
+        })
+    }
+
+    /// Returns the cached entry for `word`, marking it most recently used
+    pub fn get(&mut self, word: &str) -> Option<Definition> {
+        let idx = self.entries.iter().position(|def| def.word == word)?;
+        if idx > 0 {
+            let def = self.entries.remove(idx);
+            self.entries.insert(0, def);
+            self.dirty = true;
+        }
+        self.entries.first().cloned()
+    }
+
+    /// The cached headwords, most recent first
+    pub fn words(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|def| def.word.as_str())
+    }
+
+    /// Adds an entry, evicting the least recently used one when full
+    pub fn insert(&mut self, def: Definition) {
+        self.entries.retain(|cached| cached.word != def.word);
+        self.entries.insert(0, def);
+        self.entries.truncate(CAPACITY);
+        self.dirty = true;
+    }
+
+    /// Writes the cache back to disk if it changed
+    pub fn save(&mut self) -> Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let partial = self.path.with_extension("partial");
+        let stored = Stored {
+            roots: self.roots.clone(),
+            entries: self.entries.clone(),
+        };
+        fs::write(&partial, rmp_serde::to_vec_named(&stored)?)?;
+        fs::rename(&partial, &self.path)?;
+
+        self.dirty = false;
+        Ok(())
+    }
+}
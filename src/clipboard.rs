@@ -0,0 +1,100 @@
+use anyhow::{Context, Result};
+use arboard::Clipboard;
+use std::{thread, time::Duration};
+
+/// The text to look up: on Linux the primary selection, which is whatever
+/// was last highlighted, falling back to the clipboard when nothing is
+/// selected. Elsewhere just the clipboard.
+pub fn text() -> Result<String> {
+    let mut clipboard = Clipboard::new().context("Could not open the clipboard")?;
+
+    #[cfg(all(unix, not(any(target_os = "macos", target_os = "android"))))]
+    {
+        use arboard::{GetExtLinux, LinuxClipboardKind};
+        if let Ok(text) = clipboard
+            .get()
+            .clipboard(LinuxClipboardKind::Primary)
+            .text()
+            && !text.trim().is_empty()
+        {
+            return Ok(text);
+        }
+    }
+
+    clipboard
+        .get_text()
+        .context("The clipboard doesn't hold any text")
+}
+
+/// The word in a copied or selected `text`: its first line, without the
+/// punctuation or spaces that tend to get selected along with a word
+pub fn word(text: &str) -> Option<String> {
+    let word = text
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())?
+        .trim_matches(|c: char| !c.is_alphanumeric());
+    (!word.is_empty()).then(|| word.to_string())
+}
+
+/// The word in `text` if that's all it is, so copying a sentence or a block
+/// of code doesn't count
+pub fn single_word(text: &str) -> Option<String> {
+    let mut words = text.split_whitespace();
+    match (words.next(), words.next()) {
+        (Some(only), None) => word(only),
+        _ => None,
+    }
+}
+
+/// Polls the clipboard for newly copied words
+pub struct Watcher {
+    clipboard: Clipboard,
+    last: Option<String>,
+    interval: Duration,
+}
+
+impl Watcher {
+    /// Starts watching, checking every `interval`. Whatever is in the
+    /// clipboard already doesn't count as new.
+    pub fn new(interval: Duration) -> Result<Self> {
+        let mut clipboard = Clipboard::new().context("Could not open the clipboard")?;
+        let last = clipboard.get_text().ok();
+        Ok(Self {
+            clipboard,
+            last,
+            interval,
+        })
+    }
+
+    /// Waits for a single word to be copied and returns it. Copying the
+    /// same text again doesn't count, nor does anything but a single word.
+    pub fn next_word(&mut self) -> String {
+        loop {
+            thread::sleep(self.interval);
+            // the clipboard holding an image or nothing at all is no change
+            let Ok(text) = self.clipboard.get_text() else {
+                continue;
+            };
+            if self.last.as_ref() == Some(&text) {
+                continue;
+            }
+            let word = single_word(&text);
+            self.last = Some(text);
+            if let Some(word) = word {
+                return word;
+            }
+        }
+    }
+}
+
+/// Shows `body` in a desktop notification titled `summary`
+pub fn notify(summary: &str, body: &str) -> Result<()> {
+    notify_rust::Notification::new()
+        .appname("dibble")
+        .summary(summary)
+        .body(body)
+        .show()
+        .context("Could not show a desktop notification")?;
+    Ok(())
+}
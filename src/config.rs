@@ -0,0 +1,69 @@
+use crate::{paths::project_dirs, theme::ThemeSpec};
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+const FILE_NAME: &str = "config.toml";
+
+/// Defaults for command line flags, read from `config.toml` in the user
+/// config dir. Keys are named after the flags they stand in for, and a flag
+/// given on the command line always wins.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub no_examples: bool,
+    pub relations: bool,
+    pub no_archaic: bool,
+    pub no_pager: bool,
+    pub no_cache: bool,
+    /// Language to look words up in, e.g. `"es"`
+    pub lang: Option<String>,
+    /// Output format, e.g. `"markdown"`
+    pub format: Option<String>,
+    /// `"auto"`, `"always"` or `"never"`
+    pub color: Option<String>,
+    /// Name of a built-in theme or a theme file
+    pub theme: Option<String>,
+    /// Per-element overrides on top of the theme, as in a theme file
+    pub colors: ThemeSpec,
+    /// Name of a saved output template
+    pub template: Option<String>,
+    /// DICT server to fall back to, e.g. `"dict://dict.org"`
+    pub remote: Option<String>,
+    /// Whether to fall back to the online dictionary
+    pub online: bool,
+    /// Extra dictionary roots, searched before the default locations
+    pub dict_paths: Vec<PathBuf>,
+    /// Hunspell dictionary to take inflected words apart with, e.g.
+    /// `"/usr/share/hunspell/en_US"`
+    pub hunspell: Option<PathBuf>,
+    /// Named dictionaries to search first, in order, e.g. `["wiktionary", "jargon"]`
+    pub dict_order: Vec<String>,
+    /// The only named dictionary to search
+    pub dict: Option<String>,
+}
+
+/// Where the config file lives: `config.toml` in the user config dir
+pub fn default_path() -> Result<PathBuf> {
+    let dirs = project_dirs().context("Could not determine the user config directory")?;
+    Ok(dirs.config_dir().join(FILE_NAME))
+}
+
+impl Config {
+    /// Loads the config file, or the defaults if there isn't one
+    pub fn load() -> Result<Self> {
+        Self::load_from(&default_path()?)
+    }
+
+    pub fn load_from(path: &Path) -> Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(contents) => toml::from_str(&contents)
+                .with_context(|| format!("Invalid config {}", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        }
+    }
+}
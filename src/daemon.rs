@@ -0,0 +1,172 @@
+use crate::{Definition, Dictionary, cache::RecentCache, error, paths::project_dirs, store::Store};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    io::{BufRead, BufReader, Write},
+    os::unix::net::{UnixListener, UnixStream},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    thread,
+};
+
+const SOCKET_NAME: &str = "dibble.sock";
+
+/// One request line sent to the daemon
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum Request {
+    Lookup { words: Vec<String> },
+    Prefix { stem: String },
+}
+
+/// One response line sent back by the daemon
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Response {
+    Entries(Vec<Option<Definition>>),
+    Words(Vec<String>),
+    /// A failure, with its kind as [`error::kind`] names it so the client
+    /// can exit the way a lookup of its own would
+    Error {
+        kind: String,
+        message: String,
+    },
+}
+
+/// Where the daemon listens: the user runtime dir if the platform has one,
+/// the cache dir otherwise
+pub fn socket_path() -> Result<PathBuf> {
+    let dirs = project_dirs().context("Could not determine the user cache directory")?;
+    let dir = dirs.runtime_dir().unwrap_or(dirs.cache_dir());
+    Ok(dir.join(SOCKET_NAME))
+}
+
+/// Serves lookups on `socket` until killed, the same way `dict` would answer
+/// them itself. Give `dict` a [shard cache](Dictionary::with_shard_cache) to
+/// keep the shards it reads parsed in memory; the shards of recently looked
+/// up words are read first.
+pub fn run(dict: &Dictionary, socket: &Path) -> Result<()> {
+    if let Some(parent) = socket.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if UnixStream::connect(socket).is_ok() {
+        anyhow::bail!("A daemon is already listening on {}", socket.display());
+    }
+    // whatever is left is a stale socket from a daemon that didn't shut down cleanly
+    let _ = fs::remove_file(socket);
+    let listener = UnixListener::bind(socket)
+        .with_context(|| format!("Failed to listen on {}", socket.display()))?;
+
+    let server = Arc::new(Server { dict: dict.clone() });
+    server.preload()?;
+
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let server = Arc::clone(&server);
+        thread::spawn(move || {
+            // a client hanging up mid-request only affects that client
+            let _ = server.serve(stream);
+        });
+    }
+
+    Ok(())
+}
+
+struct Server {
+    dict: Dictionary,
+}
+
+impl Server {
+    /// Parses the shards holding the words in the recent-lookup cache
+    fn preload(&self) -> Result<()> {
+        let cache = RecentCache::open(self.dict.roots())?;
+        let words: Vec<&str> = cache.words().collect();
+        // a shard that can't be read now fails the lookups that need it later
+        let _ = self.dict.lookup_many(&words);
+        Ok(())
+    }
+
+    fn serve(&self, stream: UnixStream) -> Result<()> {
+        let mut writer = &stream;
+        for line in BufReader::new(&stream).lines() {
+            let response = match serde_json::from_str(&line?) {
+                Ok(request) => self.handle(request),
+                Err(e) => Response::Error {
+                    kind: "invalid_input".to_string(),
+                    message: format!("Invalid request: {}", e),
+                },
+            };
+            serde_json::to_writer(&mut writer, &response)?;
+            writer.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+
+    fn handle(&self, request: Request) -> Response {
+        let result = match request {
+            Request::Lookup { words } => self.dict.lookup_many(&words).map(Response::Entries),
+            Request::Prefix { stem } => self.dict.prefix(&stem).map(Response::Words),
+        };
+        result.unwrap_or_else(|e| Response::Error {
+            kind: error::kind(&e).to_string(),
+            message: format!("{:#}", e),
+        })
+    }
+}
+
+/// A connection to a running daemon, usable anywhere a [`Store`] is
+#[derive(Debug)]
+pub struct Client {
+    /// One request at a time, so answers can't go to the wrong thread
+    stream: Mutex<UnixStream>,
+}
+
+impl Client {
+    /// Connects to the daemon on `socket`, or returns `None` if none is running
+    pub fn connect(socket: &Path) -> Option<Self> {
+        UnixStream::connect(socket).ok().map(|stream| Self {
+            stream: Mutex::new(stream),
+        })
+    }
+
+    fn request(&self, request: &Request) -> Result<Response> {
+        let stream = self.stream.lock().unwrap();
+        let mut writer = &*stream;
+        serde_json::to_writer(&mut writer, request)?;
+        writer.write_all(b"\n")?;
+
+        let mut line = String::new();
+        BufReader::new(&*stream).read_line(&mut line)?;
+        match serde_json::from_str(&line).context("Invalid response from the daemon")? {
+            Response::Error { kind, message } => {
+                Err(error::from_kind(&kind, message).context("The daemon failed"))
+            }
+            response => Ok(response),
+        }
+    }
+}
+
+impl Store for Client {
+    fn lookup(&self, word: &str) -> Result<Option<Definition>> {
+        Ok(self.lookup_many(&[word.to_string()])?.pop().flatten())
+    }
+
+    fn lookup_many(&self, words: &[String]) -> Result<Vec<Option<Definition>>> {
+        match self.request(&Request::Lookup {
+            words: words.to_vec(),
+        })? {
+            Response::Entries(entries) => Ok(entries),
+            _ => anyhow::bail!("Unexpected response from the daemon"),
+        }
+    }
+
+    fn prefix(&self, stem: &str) -> Result<Vec<String>> {
+        match self.request(&Request::Prefix {
+            stem: stem.to_string(),
+        })? {
+            Response::Words(words) => Ok(words),
+            _ => anyhow::bail!("Unexpected response from the daemon"),
+        }
+    }
+}
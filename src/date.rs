@@ -0,0 +1,57 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Seconds since the Unix epoch
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+/// Days since 1970-01-01 in UTC
+pub fn today_number() -> u64 {
+    now() / 86_400
+}
+
+/// Today's date in UTC as `YYYY-MM-DD`
+pub fn today() -> String {
+    format_day(today_number())
+}
+
+/// A day number from [`today_number`] as `YYYY-MM-DD`
+pub fn format_day(days: u64) -> String {
+    let (year, month, day) = civil_date(days as i64);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// A time from [`now`] as `YYYY-MM-DD HH:MM` in UTC
+pub fn format_time(secs: u64) -> String {
+    let minutes = secs % 86_400 / 60;
+    format!(
+        "{} {:02}:{:02}",
+        format_day(secs / 86_400),
+        minutes / 60,
+        minutes % 60
+    )
+}
+
+/// Converts days since 1970-01-01 to a year, month and day in the proleptic
+/// Gregorian calendar
+fn civil_date(days: i64) -> (i64, u32, u32) {
+    // Howard Hinnant's civil_from_days: count in 400 year eras
+    // of 146097 days, with years starting in March so leap days come last
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
@@ -0,0 +1,785 @@
+use crate::{
+    archive::{Archive, is_archive},
+    config::Config,
+    error::DibbleError,
+    index::{
+        self, binary::BinaryIndex, bloom::BloomFilter, folded::FoldedIndex,
+        headwords::HeadwordIndex,
+    },
+    manifest,
+    paths::{SystemPaths, project_dirs},
+    schema::{Definition, DictionaryFile},
+    shard_cache::ShardCache,
+    sharding::ShardScheme,
+};
+use anyhow::{Context, Result};
+use flate2::read::GzDecoder;
+use rayon::prelude::*;
+use ruzstd::decoding::StreamingDecoder;
+use serde::de::{self, DeserializeSeed, Deserializer, IgnoredAny, MapAccess, Visitor};
+use serde_json::from_str;
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    fmt,
+    fs::{self, File},
+    io::Read,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    vec,
+};
+use unicode_normalization::{UnicodeNormalization, char::is_combining_mark};
+
+/// Shard file extensions, in the order they're tried. Compressed shards are
+/// only read when the plain file is absent.
+pub const SHARD_EXTENSIONS: [&str; 3] = ["json", "json.zst", "json.gz"];
+
+/// A set of dictionary roots, searched in order for each shard
+#[derive(Debug, Clone)]
+pub struct Dictionary {
+    roots: Vec<PathBuf>,
+    scheme: ShardScheme,
+    index: Option<Arc<BinaryIndex>>,
+    bloom: Option<Arc<BloomFilter>>,
+    headwords: Option<Arc<HeadwordIndex>>,
+    folded: Option<Arc<FoldedIndex>>,
+    shards: Option<Arc<Mutex<ShardCache>>>,
+}
+
+impl Dictionary {
+    /// Opens the dictionary using the search order from [`search_roots`]. If
+    /// `dibble index build` has been run, lookups go through the binary index
+    /// first.
+    pub fn open() -> Result<Self> {
+        let lang = Config::load()?.lang;
+        Self::open_lang(lang.as_deref().unwrap_or(DEFAULT_LANG))
+    }
+
+    /// Like [`Dictionary::open`], for the dictionaries in language `lang`.
+    /// The binary index only covers the default language.
+    pub fn open_lang(lang: &str) -> Result<Self> {
+        let roots = search_roots(lang)?
+            .into_iter()
+            .map(|(root, _)| root)
+            .collect::<Vec<_>>();
+        for root in &roots {
+            manifest::check(root)?;
+        }
+        manifest::check_schemes(&roots)?;
+        let mut dict = Self::with_roots(roots);
+
+        let dir = index::default_dir()?;
+        let index = dir.join(index::binary::FILE_NAME);
+        if lang == DEFAULT_LANG && index.exists() {
+            let index = BinaryIndex::open(&index)?;
+            if index.covers(&dict.roots) {
+                dict = dict.with_index(index);
+            }
+        }
+        let bloom = dir.join(index::bloom::FILE_NAME);
+        if lang == DEFAULT_LANG && bloom.exists() {
+            let bloom = BloomFilter::open(&bloom)?;
+            if bloom.covers(&dict.roots) {
+                dict = dict.with_bloom(bloom);
+            }
+        }
+        let headwords = dir.join(index::headwords::FILE_NAME);
+        if lang == DEFAULT_LANG && headwords.exists() {
+            let headwords = HeadwordIndex::open(&headwords)?;
+            if headwords.covers(&dict.roots) {
+                dict = dict.with_headword_index(headwords);
+            }
+        }
+        let folded = dir.join(index::folded::FILE_NAME);
+        if lang == DEFAULT_LANG && folded.exists() {
+            let folded = FoldedIndex::open(&folded)?;
+            if folded.covers(&dict.roots) {
+                dict = dict.with_folded_index(folded);
+            }
+        }
+
+        Ok(dict)
+    }
+
+    /// Opens a dictionary that searches only the given roots, in order,
+    /// sharded the way the first of them with a manifest says
+    pub fn with_roots(roots: Vec<PathBuf>) -> Self {
+        Self {
+            scheme: manifest::scheme(&roots),
+            roots,
+            index: None,
+            bloom: None,
+            headwords: None,
+            folded: None,
+            shards: None,
+        }
+    }
+
+    /// Consults `index` before reading shards. Words missing from the index
+    /// still fall back to the shards. Anything writing shards removes the
+    /// index with [`index::invalidate`], so it never answers with an entry
+    /// that has changed since it was built.
+    pub fn with_index(mut self, index: BinaryIndex) -> Self {
+        self.index = Some(Arc::new(index));
+        self
+    }
+
+    /// Turns away words `bloom` says aren't headwords before looking for them
+    /// anywhere else. The filter must know every headword in the roots.
+    pub fn with_bloom(mut self, bloom: BloomFilter) -> Self {
+        self.bloom = Some(Arc::new(bloom));
+        self
+    }
+
+    /// Answers prefix, range and pattern queries from `headwords` rather than
+    /// reading shards. The index must hold every headword in the roots.
+    pub fn with_headword_index(mut self, headwords: HeadwordIndex) -> Self {
+        self.headwords = Some(Arc::new(headwords));
+        self
+    }
+
+    /// Falls back on `folded` for words that aren't headwords as typed, so
+    /// `facade` finds `façade`. The index must know every headword with
+    /// diacritics in the roots.
+    pub fn with_folded_index(mut self, folded: FoldedIndex) -> Self {
+        self.folded = Some(Arc::new(folded));
+        self
+    }
+
+    /// Keeps the shards lookups parse in `cache`, for processes that look up
+    /// word after word. Without one, a lookup only builds the entries it
+    /// asked for and throws the shard away. Clones share the cache.
+    pub fn with_shard_cache(mut self, cache: ShardCache) -> Self {
+        self.shards = Some(Arc::new(Mutex::new(cache)));
+        self
+    }
+
+    /// The roots searched for shards, in precedence order
+    pub fn roots(&self) -> &[PathBuf] {
+        &self.roots
+    }
+
+    /// How the roots file headwords into shards
+    pub fn scheme(&self) -> ShardScheme {
+        self.scheme
+    }
+
+    /// The shard `word` lives in, relative to a root and without extension,
+    /// or `None` if it has no letters to file it by
+    pub fn shard_path(&self, word: &str) -> Option<PathBuf> {
+        self.scheme.path(word)
+    }
+
+    /// Whether `word` may be a headword, in any of the spellings a lookup
+    /// tries. Only `false` is certain, and only with a headword filter
+    /// attached; without one every word may be.
+    pub fn may_have(&self, word: &str) -> bool {
+        self.may_have_as_given(word) || self.accented_spellings(word).next().is_some()
+    }
+
+    /// Like [`Dictionary::may_have`], for the word as given rather than any
+    /// spelling with diacritics
+    fn may_have_as_given(&self, word: &str) -> bool {
+        self.bloom.as_ref().is_none_or(|bloom| {
+            case_variants(word)
+                .iter()
+                .any(|variant| bloom.contains(variant))
+        })
+    }
+
+    /// The headwords with diacritics `word` folds into, from the folded
+    /// spelling index if one is attached
+    fn accented_spellings(&self, word: &str) -> impl Iterator<Item = &String> {
+        self.folded
+            .iter()
+            .flat_map(move |folded| folded.spellings(word))
+            .filter(move |spelling| spelling.as_str() != word)
+    }
+
+    /// The headword index queries go through, if one is attached
+    pub fn headword_index(&self) -> Option<&HeadwordIndex> {
+        self.headwords.as_deref()
+    }
+
+    /// The binary index lookups go through, if one is attached
+    pub fn index(&self) -> Option<&BinaryIndex> {
+        self.index.as_deref()
+    }
+
+    /// Looks up a single headword
+    pub fn lookup(&self, word: &str) -> Result<Option<Definition>> {
+        Ok(self.lookup_many(&[word])?.pop().flatten())
+    }
+
+    /// Looks up several headwords, parsing each shard only once. Results are
+    /// returned in the same order as `words`. Each word is tried as given,
+    /// then in lowercase, then capitalized; see [`case_variants`]. The words
+    /// are grouped by shard and the shards read in parallel. Words still
+    /// missing are then tried with diacritics, if the folded spelling index
+    /// has any for them, and give the first entry found.
+    pub fn lookup_many<S: AsRef<str> + Sync>(
+        &self,
+        words: &[S],
+    ) -> Result<Vec<Option<Definition>>> {
+        let mut results = self.lookup_as_given(words)?;
+
+        let mut accented: Vec<(usize, &String)> = Vec::new();
+        for (idx, word) in words.iter().enumerate() {
+            if results[idx].is_none() {
+                let word = normalize(word.as_ref());
+                accented.extend(
+                    self.accented_spellings(&word)
+                        .map(|spelling| (idx, spelling)),
+                );
+            }
+        }
+        if !accented.is_empty() {
+            let spellings: Vec<&String> = accented.iter().map(|(_, spelling)| *spelling).collect();
+            for ((idx, _), def) in accented.iter().zip(self.lookup_as_given(&spellings)?) {
+                if results[*idx].is_none() {
+                    results[*idx] = def;
+                }
+            }
+        }
+        Ok(results)
+    }
+
+    /// [`Dictionary::lookup_many`] without trying spellings with diacritics
+    fn lookup_as_given<S: AsRef<str> + Sync>(
+        &self,
+        words: &[S],
+    ) -> Result<Vec<Option<Definition>>> {
+        // every case variant of a word lives in the same shard
+        let mut groups: BTreeMap<PathBuf, Vec<usize>> = BTreeMap::new();
+        for (idx, word) in words.iter().enumerate() {
+            let word = normalize(word.as_ref());
+            if !self.may_have_as_given(&word) {
+                continue;
+            }
+            if let Some(shard) = self.shard_path(&word) {
+                groups.entry(shard).or_default().push(idx);
+            }
+        }
+
+        let lookup = |(shard, group): (PathBuf, Vec<usize>)| self.lookup_in(&shard, words, &group);
+        // a single shard isn't worth starting the thread pool for
+        let found: Vec<Vec<(usize, Definition)>> = if groups.len() > 1 {
+            groups.into_par_iter().map(lookup).collect::<Result<_>>()?
+        } else {
+            groups.into_iter().map(lookup).collect::<Result<_>>()?
+        };
+
+        let mut results = vec![None; words.len()];
+        for (idx, def) in found.into_iter().flatten() {
+            results[idx] = Some(def);
+        }
+        Ok(results)
+    }
+
+    /// Looks up the words at `group` in `words`, which all live in `shard`,
+    /// reading the shard only if the index doesn't have them all. Returns
+    /// the position of each word found with its entry.
+    fn lookup_in<S: AsRef<str>>(
+        &self,
+        shard: &Path,
+        words: &[S],
+        group: &[usize],
+    ) -> Result<Vec<(usize, Definition)>> {
+        let mut found = Vec::new();
+        let mut data: Option<Option<Arc<DictionaryFile>>> = None;
+
+        for &idx in group {
+            for variant in case_variants(&normalize(words[idx].as_ref())) {
+                if let Some(index) = &self.index
+                    && let Some(def) = index.lookup(&variant)?
+                {
+                    found.push((idx, def));
+                    break;
+                }
+
+                if data.is_none() {
+                    data = Some(match &self.shards {
+                        Some(cache) => Some(self.load_shard_cached(cache, shard)?),
+                        None => {
+                            let wanted: HashSet<String> = group
+                                .iter()
+                                .flat_map(|&idx| case_variants(&normalize(words[idx].as_ref())))
+                                .collect();
+                            self.load_entries_if_present(shard, &wanted)?.map(Arc::new)
+                        }
+                    });
+                }
+                if let Some(Some(data)) = &data
+                    && let Some(def) = data.get(&variant)
+                {
+                    found.push((idx, def.clone()));
+                    break;
+                }
+            }
+        }
+
+        Ok(found)
+    }
+
+    /// Reads and parses the shard at `path` (relative to a root, without extension)
+    /// from the first root that has it
+    pub fn load_shard(&self, path: &Path) -> Result<DictionaryFile> {
+        let contents = self.read_shard(path)?;
+        Ok(from_str(&contents)?)
+    }
+
+    /// Reads only the headwords of a shard, skipping the cost of building each entry
+    pub fn shard_words(&self, path: &Path) -> Result<Vec<String>> {
+        let contents = self.read_shard(path)?;
+        let data: HashMap<String, IgnoredAny> = from_str(&contents)?;
+        let mut words: Vec<String> = data.into_keys().collect();
+        words.sort();
+        Ok(words)
+    }
+
+    /// Reads the raw JSON of a shard from the first root that has it
+    pub fn read_shard(&self, path: &Path) -> Result<String> {
+        match self.find_shard(path)? {
+            Some(contents) => Ok(contents),
+            None => self.not_found(path),
+        }
+    }
+
+    /// The whole shard at `path` from `cache`, parsing it into the cache if
+    /// it isn't there. A shard no root has is empty rather than an error, as
+    /// long as some root exists at all.
+    fn load_shard_cached(
+        &self,
+        cache: &Mutex<ShardCache>,
+        path: &Path,
+    ) -> Result<Arc<DictionaryFile>> {
+        // parse without holding the lock, so other threads can use the cache meanwhile
+        if let Some(data) = cache.lock().unwrap().get(path) {
+            return Ok(data);
+        }
+
+        let data = Arc::new(match self.find_shard(path)? {
+            Some(contents) => from_str(&contents)?,
+            None if self.roots.iter().any(|root| root.exists()) => DictionaryFile::new(),
+            None => return self.not_found(path),
+        });
+        cache
+            .lock()
+            .unwrap()
+            .insert(path.to_path_buf(), data.clone());
+        Ok(data)
+    }
+
+    /// Reads the entries of the headwords in `wanted` from the shard at
+    /// `path`, skipping over the rest without building them. A shard no root
+    /// has is `None` rather than an error, as long as some root exists at all.
+    fn load_entries_if_present(
+        &self,
+        path: &Path,
+        wanted: &HashSet<String>,
+    ) -> Result<Option<DictionaryFile>> {
+        match self.find_shard(path)? {
+            Some(contents) => Ok(Some(parse_entries(&contents, wanted)?)),
+            None if self.roots.iter().any(|root| root.exists()) => Ok(None),
+            None => self.not_found(path),
+        }
+    }
+
+    fn find_shard(&self, path: &Path) -> Result<Option<String>> {
+        for root in &self.roots {
+            if is_archive(root) {
+                if root.is_file()
+                    && let Some(contents) = Archive::open(root)?.read_shard(path)?
+                {
+                    return Ok(Some(contents));
+                }
+                continue;
+            }
+
+            for ext in SHARD_EXTENSIONS {
+                let mut target = root.join(path);
+                target.set_extension(ext);
+
+                let Ok(file) = File::open(&target) else {
+                    continue;
+                };
+
+                let mut reader: Box<dyn Read> = match ext {
+                    "json.zst" => Box::new(StreamingDecoder::new(file).with_context(|| {
+                        format!("Failed to read zstd shard {}", target.display())
+                    })?),
+                    "json.gz" => Box::new(GzDecoder::new(file)),
+                    _ => Box::new(file),
+                };
+
+                let mut contents = String::new();
+                reader.read_to_string(&mut contents)?;
+                return Ok(Some(contents));
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn not_found<T>(&self, path: &Path) -> Result<T> {
+        let searched: Vec<String> = self
+            .roots
+            .iter()
+            .map(|root| {
+                if is_archive(root) {
+                    format!("  - {} (archive)", root.display())
+                } else {
+                    format!("  - {}", root.join(path).with_extension("json").display())
+                }
+            })
+            .collect();
+
+        Err(DibbleError::DictMissing(format!(
+            "Dictionary file not found. Searched:\n{}",
+            searched.join("\n")
+        ))
+        .into())
+    }
+
+    /// Lists every shard available across all roots, relative to the root and
+    /// without extension, sorted and deduplicated
+    pub fn shards(&self) -> Result<Vec<PathBuf>> {
+        let mut shards = BTreeSet::new();
+
+        for root in &self.roots {
+            if is_archive(root) {
+                if root.is_file() {
+                    shards.extend(Archive::open(root)?.shards());
+                }
+                continue;
+            }
+
+            if !root.is_dir() {
+                continue;
+            }
+
+            for entry in letter_dirs(root)? {
+                // some filesystems hand back decomposed names, so compose
+                // them to match what the scheme derives
+                let dir = normalize(&entry.file_name().to_string_lossy());
+                for file in fs::read_dir(entry.path())? {
+                    let name = file?.file_name();
+                    let name = name.to_string_lossy();
+                    if let Some(stem) = SHARD_EXTENSIONS
+                        .iter()
+                        .find_map(|ext| name.strip_suffix(&format!(".{}", ext)))
+                    {
+                        shards.insert(PathBuf::from(&dir).join(normalize(stem)));
+                    }
+                }
+            }
+        }
+
+        Ok(shards.into_iter().collect())
+    }
+
+    /// Lists the shards that can hold words beginning with `stem`
+    pub fn prefix_shards(&self, stem: &str) -> Result<Vec<PathBuf>> {
+        Ok(self
+            .shards()?
+            .into_iter()
+            .filter(|shard| self.scheme.may_hold_prefix(shard, stem))
+            .collect())
+    }
+
+    /// Lists every headword beginning with `stem`, sorted
+    pub fn prefix(&self, stem: &str) -> Result<Vec<String>> {
+        if let Some(headwords) = &self.headwords {
+            return headwords.prefix(stem);
+        }
+        let lower = stem.to_lowercase();
+        let mut words = Vec::new();
+
+        for shard in self.prefix_shards(stem)? {
+            words.extend(
+                self.shard_words(&shard)?
+                    .into_iter()
+                    .filter(|word| word.to_lowercase().starts_with(&lower)),
+            );
+        }
+
+        words.sort();
+        Ok(words)
+    }
+
+    /// Like [`Dictionary::prefix`], but returns the full entries
+    pub fn prefix_entries(&self, stem: &str) -> Result<Vec<Definition>> {
+        let lower = stem.to_lowercase();
+        let mut entries = Vec::new();
+
+        for shard in self.prefix_shards(stem)? {
+            entries.extend(
+                self.load_shard(&shard)?
+                    .into_values()
+                    .filter(|def| def.word.to_lowercase().starts_with(&lower)),
+            );
+        }
+
+        entries.sort_by(|a, b| a.word.cmp(&b.word));
+        Ok(entries)
+    }
+
+    /// Iterates over every headword in the dictionary, one shard at a time
+    pub fn iter_words(&self) -> Result<Words<'_>> {
+        Ok(Words {
+            dict: self,
+            shards: self.shards()?.into_iter(),
+            current: Vec::new().into_iter(),
+        })
+    }
+}
+
+/// The folders of the shard tree at `root` that hold its shards. A language
+/// folder like `en` is a shard tree of its own, searched as a separate root,
+/// so one with a manifest is left out.
+pub(crate) fn letter_dirs(root: &Path) -> Result<Vec<fs::DirEntry>> {
+    let mut dirs = Vec::new();
+    for entry in fs::read_dir(root)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() && !entry.path().join(manifest::FILE_NAME).is_file() {
+            dirs.push(entry);
+        }
+    }
+    Ok(dirs)
+}
+
+/// Iterator over headwords, returned by [`Dictionary::iter_words`]
+pub struct Words<'a> {
+    dict: &'a Dictionary,
+    shards: vec::IntoIter<PathBuf>,
+    current: vec::IntoIter<String>,
+}
+
+impl Iterator for Words<'_> {
+    type Item = Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(word) = self.current.next() {
+                return Some(Ok(word));
+            }
+
+            let shard = self.shards.next()?;
+            match self.dict.shard_words(&shard) {
+                Ok(words) => self.current = words.into_iter(),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+/// Parses the entries of the headwords in `wanted` from the JSON of a shard,
+/// skipping over the rest without building them
+pub fn parse_entries(contents: &str, wanted: &HashSet<String>) -> Result<DictionaryFile> {
+    let mut de = serde_json::Deserializer::from_str(contents);
+    let entries = Wanted(wanted).deserialize(&mut de)?;
+    de.end()?;
+    Ok(entries)
+}
+
+/// Deserializes a shard into just the entries whose headwords are wanted.
+/// Every other entry is still checked to be valid JSON, but never built.
+struct Wanted<'w>(&'w HashSet<String>);
+
+impl<'de> DeserializeSeed<'de> for Wanted<'_> {
+    type Value = DictionaryFile;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for Wanted<'_> {
+    type Value = DictionaryFile;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a map of headwords to entries")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut entries = DictionaryFile::new();
+        while let Some(Headword(word)) = map.next_key()? {
+            if self.0.contains(word.as_ref()) {
+                entries.insert(word.into_owned(), map.next_value()?);
+            } else {
+                map.next_value::<IgnoredAny>()?;
+            }
+        }
+        Ok(entries)
+    }
+}
+
+/// A shard key, borrowed straight from the JSON unless it has escapes in it,
+/// so skipping an entry doesn't allocate for its headword
+struct Headword<'de>(Cow<'de, str>);
+
+impl<'de> de::Deserialize<'de> for Headword<'de> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct HeadwordVisitor;
+
+        impl<'de> Visitor<'de> for HeadwordVisitor {
+            type Value = Headword<'de>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a headword")
+            }
+
+            fn visit_borrowed_str<E: de::Error>(self, word: &'de str) -> Result<Self::Value, E> {
+                Ok(Headword(Cow::Borrowed(word)))
+            }
+
+            fn visit_str<E: de::Error>(self, word: &str) -> Result<Self::Value, E> {
+                Ok(Headword(Cow::Owned(word.to_string())))
+            }
+        }
+
+        deserializer.deserialize_str(HeadwordVisitor)
+    }
+}
+
+/// The spellings a lookup tries, in order: exactly as given, lowercase, then
+/// capitalized, so `March` finds the month where the dictionary has it and
+/// `march` otherwise
+pub fn case_variants(word: &str) -> Vec<String> {
+    let lower = word.to_lowercase();
+    let mut chars = lower.chars();
+    let capitalized = match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    };
+
+    let mut variants = vec![word.to_string()];
+    for variant in [lower, capitalized] {
+        if !variants.contains(&variant) {
+            variants.push(variant);
+        }
+    }
+    variants
+}
+
+/// `word` in lowercase without its diacritics, so `Façade` and `facade`
+/// fold to the same key. Letters that don't decompose, like `ø`, are kept.
+pub fn fold(word: &str) -> String {
+    word.nfd()
+        .filter(|c| !is_combining_mark(*c))
+        .flat_map(char::to_lowercase)
+        .nfc()
+        .collect()
+}
+
+/// Puts `word` in Unicode NFC, the form headwords are stored in, so `café`
+/// matches whether its accent was typed as part of the letter or after it
+pub fn normalize(word: &str) -> String {
+    word.nfc().collect()
+}
+
+/// Whether `word` can be looked up: letters, joined by hyphens, apostrophes
+/// or spaces, as in `mother-in-law`, `o'clock` or `by and large`
+pub fn is_valid_word(word: &str) -> bool {
+    word.chars().any(char::is_alphabetic)
+        && word.chars().all(|c| {
+            c.is_alphabetic() || is_combining_mark(c) || matches!(c, '-' | '\'' | '\u{2019}' | ' ')
+        })
+}
+
+/// The language looked up unless `--lang` or the config file says otherwise
+pub const DEFAULT_LANG: &str = "en";
+
+/// Checks that `lang` looks like a language code (`en`, `pt-BR`), so it can
+/// safely name a folder
+pub fn check_lang(lang: &str) -> Result<()> {
+    if lang.is_empty() || !lang.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        anyhow::bail!("'{}' is not a language code, e.g. en or pt-BR", lang);
+    }
+    Ok(())
+}
+
+/// Environment variable holding extra dictionary roots, separated like `$PATH`
+pub const DICT_PATH_VAR: &str = "DIBBLE_DICT_PATH";
+
+/// Where a dictionary root in the search order came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RootSource {
+    /// The `DIBBLE_DICT_PATH` environment variable
+    Env,
+    /// `dict_paths` in the config file
+    Config,
+    /// One of the built-in locations
+    Default,
+}
+
+/// The roots [`Dictionary::open_lang`] searches, in precedence order: those in
+/// `DIBBLE_DICT_PATH`, then the config file's `dict_paths`, then `./dict`, the
+/// user data dir and the system installation. Each location holds one folder
+/// per language, e.g. `dict/es`; for the default language the location itself
+/// is searched too, after its `en` folder.
+pub fn search_roots(lang: &str) -> Result<Vec<(PathBuf, RootSource)>> {
+    check_lang(lang)?;
+    let mut roots = Vec::new();
+
+    if let Some(paths) = std::env::var_os(DICT_PATH_VAR) {
+        roots.extend(
+            std::env::split_paths(&paths)
+                .filter(|path| !path.as_os_str().is_empty())
+                .map(|path| (path, RootSource::Env)),
+        );
+    }
+    roots.extend(
+        Config::load()?
+            .dict_paths
+            .into_iter()
+            .map(|path| (path, RootSource::Config)),
+    );
+    roots.extend(
+        default_roots()
+            .into_iter()
+            .map(|path| (path, RootSource::Default)),
+    );
+
+    Ok(roots
+        .into_iter()
+        .flat_map(|(root, source)| {
+            language_roots(&root, lang)
+                .into_iter()
+                .map(move |root| (root, source))
+        })
+        .collect())
+}
+
+/// Where the dictionary in language `lang` lives under `root`: the `lang`
+/// folder, or `dict.<lang>.dibble` next to an archive
+fn language_roots(root: &Path, lang: &str) -> Vec<PathBuf> {
+    let localized = if is_archive(root) {
+        let stem = root.file_stem().unwrap_or_default().to_string_lossy();
+        root.with_file_name(format!("{}.{}.dibble", stem, lang))
+    } else {
+        root.join(lang)
+    };
+
+    if lang == DEFAULT_LANG {
+        vec![localized, root.to_path_buf()]
+    } else {
+        vec![localized]
+    }
+}
+
+fn default_roots() -> Vec<PathBuf> {
+    let mut roots = vec![PathBuf::from("./dict")];
+
+    if let Some(dirs) = project_dirs() {
+        roots.push(dirs.data_dir().join("dict"));
+        roots.push(dirs.data_dir().join("dict.dibble"));
+    }
+
+    // system installation fallback
+    let system = SystemPaths::new();
+    roots.push(system.data_dir().join("dict"));
+    roots.push(system.data_dir().join("dict.dibble"));
+
+    roots
+}
@@ -0,0 +1,170 @@
+use serde_json::{Value, json};
+use std::{fmt, io};
+
+/// Exit code for any error not covered by a [`DibbleError`]
+pub const EXIT_FAILURE: u8 = 1;
+
+const EXIT_PARSE_ERROR: u8 = 5;
+const EXIT_IO_ERROR: u8 = 6;
+
+/// The errors scripts calling dibble may want to tell apart, each with its
+/// own exit code:
+///
+/// | code | meaning                                   |
+/// |------|-------------------------------------------|
+/// | 0    | success                                   |
+/// | 1    | any other error                           |
+/// | 2    | invalid input, including bad arguments    |
+/// | 3    | a word wasn't found                       |
+/// | 4    | no dictionary is installed where searched |
+/// | 5    | a dictionary or data file didn't parse    |
+/// | 6    | reading or writing a file failed          |
+#[derive(Debug)]
+pub enum DibbleError {
+    /// Input that can't be looked up or acted on, like a word with digits in it
+    InvalidInput(String),
+    /// A word no dictionary has, with the nearest headwords there are.
+    /// Lookups report their misses as they go, so this isn't printed again
+    /// outside of JSON output.
+    WordNotFound {
+        word: String,
+        suggestions: Vec<String>,
+    },
+    /// No dictionary file where one was searched for
+    DictMissing(String),
+    /// A dictionary, index or other data file that doesn't parse
+    ParseError(String),
+    IoError(io::Error),
+    /// A word asked about with `--exists` that isn't defined. The exit code
+    /// is the whole answer, so like a miss this isn't printed outside of
+    /// JSON output.
+    Undefined(String),
+}
+
+impl DibbleError {
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Self::InvalidInput(_) => 2,
+            Self::WordNotFound { .. } => 3,
+            Self::DictMissing(_) => 4,
+            Self::ParseError(_) => EXIT_PARSE_ERROR,
+            Self::IoError(_) => EXIT_IO_ERROR,
+            Self::Undefined(_) => EXIT_FAILURE,
+        }
+    }
+}
+
+impl fmt::Display for DibbleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidInput(message) => write!(f, "Invalid input: {}", message),
+            Self::WordNotFound { word, .. } => write!(f, "Word not found: {}", word),
+            Self::DictMissing(message) => f.write_str(message),
+            Self::ParseError(message) => f.write_str(message),
+            Self::IoError(e) => e.fmt(f),
+            Self::Undefined(word) => write!(f, "Not defined: {}", word),
+        }
+    }
+}
+
+impl DibbleError {
+    /// The name of the variant in JSON errors, e.g. `not_found`
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::InvalidInput(_) => "invalid_input",
+            Self::WordNotFound { .. } => "not_found",
+            Self::DictMissing(_) => "dict_missing",
+            Self::ParseError(_) => "parse_error",
+            Self::IoError(_) => "io_error",
+            Self::Undefined(_) => "undefined",
+        }
+    }
+}
+
+impl std::error::Error for DibbleError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::IoError(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for DibbleError {
+    fn from(e: io::Error) -> Self {
+        Self::IoError(e)
+    }
+}
+
+/// `error` as it's written to stderr under `--format json`: an object with
+/// the kind of error, the same names as [`DibbleError::kind`] or `error` for
+/// anything else, and the full message, plus the word and suggestions for a
+/// miss:
+///
+/// ```json
+/// {"error": "not_found", "message": "Word not found: teh", "word": "teh", "suggestions": ["the", "ten"]}
+/// ```
+pub fn to_json(error: &anyhow::Error) -> Value {
+    let message = format!("{:#}", error);
+    match error.downcast_ref::<DibbleError>() {
+        Some(DibbleError::WordNotFound { word, suggestions }) => json!({
+            "error": "not_found",
+            "message": message,
+            "word": word,
+            "suggestions": suggestions,
+        }),
+        _ => json!({
+            "error": kind(error),
+            "message": message,
+        }),
+    }
+}
+
+/// The kind of `error` as [`to_json`] names it: that of the first
+/// [`DibbleError`] in its chain, treating JSON and I/O errors as the
+/// matching variant, or `error` for anything else
+pub fn kind(error: &anyhow::Error) -> &'static str {
+    error
+        .chain()
+        .find_map(|cause| {
+            if let Some(e) = cause.downcast_ref::<DibbleError>() {
+                return Some(e.kind());
+            }
+            if cause.is::<serde_json::Error>() {
+                return Some("parse_error");
+            }
+            cause.is::<io::Error>().then_some("io_error")
+        })
+        .unwrap_or("error")
+}
+
+/// The error of kind `kind`, as [`kind`] names it, with `message`, for
+/// errors passed along as text. A miss or any other kind gives a plain
+/// error, since only the message is left of it.
+pub fn from_kind(kind: &str, message: String) -> anyhow::Error {
+    match kind {
+        "invalid_input" => DibbleError::InvalidInput(message).into(),
+        "dict_missing" => DibbleError::DictMissing(message).into(),
+        "parse_error" => DibbleError::ParseError(message).into(),
+        "io_error" => DibbleError::IoError(io::Error::other(message)).into(),
+        _ => anyhow::anyhow!(message),
+    }
+}
+
+/// The exit code for `error`: that of the first [`DibbleError`] in its
+/// chain, treating JSON and I/O errors as the matching variant, or
+/// [`EXIT_FAILURE`] for anything else
+pub fn exit_code(error: &anyhow::Error) -> u8 {
+    for cause in error.chain() {
+        if let Some(e) = cause.downcast_ref::<DibbleError>() {
+            return e.exit_code();
+        }
+        if cause.is::<serde_json::Error>() {
+            return EXIT_PARSE_ERROR;
+        }
+        if cause.is::<io::Error>() {
+            return EXIT_IO_ERROR;
+        }
+    }
+    EXIT_FAILURE
+}
@@ -0,0 +1,123 @@
+use crate::{
+    Definition, Dictionary,
+    search::{glob_match, literal_prefix},
+};
+use anyhow::Result;
+use std::io::Write;
+
+/// Walks every shard (or only those that can match `pattern`) and calls `f`
+/// with each entry whose headword matches, in sorted order
+fn for_each_entry(
+    dict: &Dictionary,
+    pattern: Option<&str>,
+    mut f: impl FnMut(&Definition) -> Result<()>,
+) -> Result<()> {
+    let shards = match pattern {
+        Some(pattern) => dict.prefix_shards(&literal_prefix(pattern))?,
+        None => dict.shards()?,
+    };
+    let pattern: Option<Vec<char>> = pattern.map(|p| p.to_lowercase().chars().collect());
+
+    for shard in shards {
+        let data = dict.load_shard(&shard)?;
+        let mut words: Vec<&String> = data.keys().collect();
+        words.sort();
+
+        for word in words {
+            if let Some(pattern) = &pattern {
+                let lower: Vec<char> = word.to_lowercase().chars().collect();
+                if !glob_match(pattern, &lower) {
+                    continue;
+                }
+            }
+            f(&data[word])?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes a single JSON object mapping headwords to entries, the same shape as
+/// one shard file
+pub fn json(dict: &Dictionary, pattern: Option<&str>, out: &mut dyn Write) -> Result<()> {
+    let mut first = true;
+    write!(out, "{{")?;
+
+    for_each_entry(dict, pattern, |def| {
+        if !first {
+            write!(out, ",")?;
+        }
+        first = false;
+
+        serde_json::to_writer(&mut *out, &def.word)?;
+        write!(out, ":")?;
+        serde_json::to_writer(&mut *out, def)?;
+        Ok(())
+    })?;
+
+    writeln!(out, "}}")?;
+    Ok(())
+}
+
+/// Writes one row per sense (and per example) with the columns read by the csv
+/// importer: word, pos, sense, example
+pub fn csv(dict: &Dictionary, pattern: Option<&str>, out: &mut dyn Write) -> Result<()> {
+    let mut writer = ::csv::Writer::from_writer(out);
+    writer.write_record(["word", "pos", "sense", "example"])?;
+
+    for_each_entry(dict, pattern, |def| {
+        for etymology in &def.etymologies {
+            for pos in &etymology.parts_of_speech {
+                for sense in &pos.senses {
+                    if sense.examples.is_empty() {
+                        writer.write_record([&def.word, &pos.part_of_speech, &sense.sense, ""])?;
+                    }
+                    for example in &sense.examples {
+                        writer.write_record([
+                            &def.word,
+                            &pos.part_of_speech,
+                            &sense.sense,
+                            example,
+                        ])?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    })?;
+
+    writer.flush()?;
+    Ok(())
+}
+
+/// Writes every entry as a Markdown section
+pub fn markdown(dict: &Dictionary, pattern: Option<&str>, out: &mut dyn Write) -> Result<()> {
+    for_each_entry(dict, pattern, |def| {
+        write!(out, "{}", def.to_markdown(true))?;
+        Ok(())
+    })
+}
+
+/// Writes a standalone HTML page holding every entry, styled through the
+/// `dibble-` classes of [`Definition::to_html`](crate::Definition::to_html)
+pub fn html(dict: &Dictionary, pattern: Option<&str>, out: &mut dyn Write) -> Result<()> {
+    writeln!(
+        out,
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Glossary</title>\n<style>{}</style>\n</head>\n<body>",
+        HTML_STYLE
+    )?;
+    for_each_entry(dict, pattern, |def| {
+        write!(out, "{}", def.to_html(true))?;
+        Ok(())
+    })?;
+    writeln!(out, "</body>\n</html>")?;
+    Ok(())
+}
+
+const HTML_STYLE: &str = "
+body { font-family: sans-serif; max-width: 50em; margin: auto; }
+.dibble-word { color: #07a; }
+.dibble-pos { color: #080; margin-bottom: 0; }
+.dibble-date { color: #888; font-style: italic; }
+.dibble-example { color: #666; list-style: none; }
+";
@@ -0,0 +1,134 @@
+use crate::Dictionary;
+use anyhow::{Context, Result};
+use std::{
+    cell::RefCell,
+    ffi::{CStr, CString, c_char},
+    path::PathBuf,
+    ptr,
+};
+
+/// An open dictionary, from [`dibble_open`]
+pub struct DibbleDictionary(Dictionary);
+
+thread_local! {
+    /// Why the last call on this thread failed
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Records the outcome of a call for [`dibble_last_error`], turning a failure
+/// into `None`
+fn record<T>(result: Result<T>) -> Option<T> {
+    LAST_ERROR.with(|last| match result {
+        Ok(value) => {
+            *last.borrow_mut() = None;
+            Some(value)
+        }
+        Err(e) => {
+            // a message with a NUL in it is cut short rather than lost
+            let message = format!("{:#}", e).replace('\0', " ");
+            *last.borrow_mut() = CString::new(message).ok();
+            None
+        }
+    })
+}
+
+/// The UTF-8 string at `s`, which the caller promises is NUL-terminated
+unsafe fn str_arg<'a>(s: *const c_char, name: &str) -> Result<&'a str> {
+    if s.is_null() {
+        anyhow::bail!("{} is null", name);
+    }
+    // SAFETY: passed on from the caller
+    let s = unsafe { CStr::from_ptr(s) };
+    s.to_str()
+        .with_context(|| format!("{} is not valid UTF-8", name))
+}
+
+/// Opens the dictionary at `path`, a shard tree or `.dibble` archive, or the
+/// installed dictionaries if `path` is null. Returns null on failure; see
+/// [`dibble_last_error`]. Close it with [`dibble_close`].
+///
+/// # Safety
+///
+/// `path` must be null or a NUL-terminated UTF-8 string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn dibble_open(path: *const c_char) -> *mut DibbleDictionary {
+    let dict = if path.is_null() {
+        Dictionary::open()
+    } else {
+        // SAFETY: passed on from the caller
+        unsafe { str_arg(path, "path") }
+            .map(|path| Dictionary::with_roots(vec![PathBuf::from(path)]))
+    };
+    match record(dict) {
+        Some(dict) => Box::into_raw(Box::new(DibbleDictionary(dict))),
+        None => ptr::null_mut(),
+    }
+}
+
+/// Looks up `word` in `dict`, returning its entry as JSON, shaped as in a
+/// shard. Returns null if the word isn't there, with no error set, or on
+/// failure, with one set; see [`dibble_last_error`]. Free the result with
+/// [`dibble_free`].
+///
+/// # Safety
+///
+/// `dict` must come from [`dibble_open`] and not have been closed, and `word`
+/// must be a NUL-terminated UTF-8 string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn dibble_lookup_json(
+    dict: *const DibbleDictionary,
+    word: *const c_char,
+) -> *mut c_char {
+    let found = (|| {
+        // SAFETY: passed on from the caller
+        let dict = unsafe { dict.as_ref() }.context("dict is null")?;
+        // SAFETY: passed on from the caller
+        let word = unsafe { str_arg(word, "word") }?;
+        match dict.0.lookup(word)? {
+            Some(def) => Ok(Some(CString::new(serde_json::to_string(&def)?)?)),
+            None => Ok(None),
+        }
+    })();
+    match record(found).flatten() {
+        Some(json) => json.into_raw(),
+        None => ptr::null_mut(),
+    }
+}
+
+/// Frees a string returned by dibble. Does nothing if `s` is null.
+///
+/// # Safety
+///
+/// `s` must be null or a string from [`dibble_lookup_json`] that hasn't been
+/// freed already.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn dibble_free(s: *mut c_char) {
+    if !s.is_null() {
+        // SAFETY: passed on from the caller
+        drop(unsafe { CString::from_raw(s) });
+    }
+}
+
+/// Closes a dictionary from [`dibble_open`]. Does nothing if `dict` is null.
+///
+/// # Safety
+///
+/// `dict` must be null or come from [`dibble_open`] and not have been
+/// closed already.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn dibble_close(dict: *mut DibbleDictionary) {
+    if !dict.is_null() {
+        // SAFETY: passed on from the caller
+        drop(unsafe { Box::from_raw(dict) });
+    }
+}
+
+/// Why the last call on this thread failed, or null if it didn't. The string
+/// belongs to dibble and is only valid until the next call on the thread.
+#[unsafe(no_mangle)]
+pub extern "C" fn dibble_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| match &*last.borrow() {
+        Some(message) => message.as_ptr(),
+        None => ptr::null(),
+    })
+}
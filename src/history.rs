@@ -0,0 +1,110 @@
+use crate::{date, paths::project_dirs};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::PathBuf,
+};
+
+const FILE_NAME: &str = "history.jsonl";
+
+/// One lookup, as a line of the history file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Visit {
+    /// The headword that was shown, or the word as typed if it wasn't found
+    pub word: String,
+    /// When, in seconds since the Unix epoch
+    pub time: u64,
+    /// Optional: The dictionary it came from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// Whether the word wasn't found
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub missed: bool,
+}
+
+impl Visit {
+    /// A visit to `word` right now
+    pub fn now(word: &str, source: Option<String>) -> Self {
+        Self {
+            word: word.to_string(),
+            time: date::now(),
+            source,
+            missed: false,
+        }
+    }
+
+    /// A lookup of `word` right now that found nothing
+    pub fn miss(word: &str) -> Self {
+        Self {
+            missed: true,
+            ..Self::now(word, None)
+        }
+    }
+
+    /// The time as `YYYY-MM-DD HH:MM` in UTC
+    pub fn time_string(&self) -> String {
+        date::format_time(self.time)
+    }
+}
+
+/// Where the lookup history lives: the user data dir
+pub fn default_path() -> Result<PathBuf> {
+    let dirs = project_dirs().context("Could not determine the user data directory")?;
+    Ok(dirs.data_dir().join(FILE_NAME))
+}
+
+/// Appends `visits` to the history file
+pub fn record(visits: &[Visit]) -> Result<()> {
+    if visits.is_empty() {
+        return Ok(());
+    }
+    let path = default_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut lines = String::new();
+    for visit in visits {
+        lines.push_str(&serde_json::to_string(visit)?);
+        lines.push('\n');
+    }
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| file.write_all(lines.as_bytes()))
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Every lookup that found its word, oldest first
+pub fn load() -> Result<Vec<Visit>> {
+    let mut visits = load_all()?;
+    visits.retain(|visit| !visit.missed);
+    Ok(visits)
+}
+
+/// Every recorded lookup including misses, oldest first. Lines that don't
+/// parse are skipped.
+pub fn load_all() -> Result<Vec<Visit>> {
+    let path = default_path()?;
+    let Ok(contents) = fs::read_to_string(&path) else {
+        return Ok(Vec::new());
+    };
+    Ok(contents
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// Deletes the history file, if there is one
+pub fn clear() -> Result<()> {
+    let path = default_path()?;
+    match fs::remove_file(&path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            Err(e).with_context(|| format!("Failed to remove {}", path.display()))
+        }
+        _ => Ok(()),
+    }
+}
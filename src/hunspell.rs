@@ -0,0 +1,326 @@
+use anyhow::{Context, Result};
+use std::{collections::HashMap, fs, path::Path};
+
+/// A Hunspell dictionary: the stems from its `.dic` file and the prefix and
+/// suffix rules from its `.aff` file, enough to take inflected forms apart
+/// and to put them together
+#[derive(Debug, Default)]
+pub struct Hunspell {
+    words: HashMap<String, Vec<String>>,
+    prefixes: Vec<Affix>,
+    suffixes: Vec<Affix>,
+}
+
+/// One `PFX` or `SFX` rule
+#[derive(Debug)]
+struct Affix {
+    flag: String,
+    /// Whether the rule combines with affixes of the other kind
+    cross: bool,
+    strip: String,
+    add: String,
+    condition: Vec<Condition>,
+}
+
+/// One character of an affix condition
+#[derive(Debug)]
+enum Condition {
+    Any,
+    Char(char),
+    /// `[abc]`, or `[^abc]` when negated
+    Set(Vec<char>, bool),
+}
+
+/// How the flags after a `/` in the `.dic` file are written
+#[derive(Debug, Clone, Copy)]
+enum FlagType {
+    /// One character per flag
+    Char,
+    /// Two characters per flag (`FLAG long`)
+    Long,
+    /// Comma separated numbers (`FLAG num`)
+    Num,
+}
+
+impl Hunspell {
+    /// Loads a dictionary from `path`, which names the `.aff` file, the
+    /// `.dic` file, or both without their extension (`/usr/share/hunspell/en_US`)
+    pub fn open(path: &Path) -> Result<Self> {
+        let aff = path.with_extension("aff");
+        let dic = path.with_extension("dic");
+        Self::parse(&read_text(&aff)?, &read_text(&dic)?)
+            .with_context(|| format!("Invalid Hunspell dictionary {}", path.display()))
+    }
+
+    /// Builds a dictionary from the contents of an `.aff` and a `.dic` file
+    pub fn parse(aff: &str, dic: &str) -> Result<Self> {
+        let mut hunspell = Self::default();
+        let mut flag_type = FlagType::Char;
+
+        let mut lines = aff.lines();
+        while let Some(line) = lines.next() {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields.as_slice() {
+                ["FLAG", kind, ..] => {
+                    flag_type = match *kind {
+                        "long" => FlagType::Long,
+                        "num" => FlagType::Num,
+                        _ => FlagType::Char,
+                    }
+                }
+                [kind @ ("PFX" | "SFX"), flag, cross, count, ..] => {
+                    let count: usize = count
+                        .parse()
+                        .with_context(|| format!("Bad rule count in '{}'", line))?;
+                    let cross = *cross == "Y";
+
+                    for _ in 0..count {
+                        let rule = lines.next().context("Affix block ends early")?;
+                        let parts: Vec<&str> = rule.split_whitespace().collect();
+                        let [_, _, strip, add, rest @ ..] = parts.as_slice() else {
+                            anyhow::bail!("Bad affix rule '{}'", rule);
+                        };
+                        // continuation flags after the affix aren't followed
+                        let add = add.split('/').next().unwrap_or_default();
+                        let affix = Affix {
+                            flag: flag.to_string(),
+                            cross,
+                            strip: zero(strip).to_string(),
+                            add: zero(add).to_string(),
+                            condition: parse_condition(rest.first().copied().unwrap_or(".")),
+                        };
+                        if *kind == "PFX" {
+                            hunspell.prefixes.push(affix);
+                        } else {
+                            hunspell.suffixes.push(affix);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // the first line of the .dic file is its approximate word count
+        for line in dic.lines().skip(1) {
+            // morphological fields follow a tab or space
+            let Some(entry) = line.split(['\t', ' ']).next().filter(|e| !e.is_empty()) else {
+                continue;
+            };
+            let (word, flags) = match entry.split_once('/') {
+                Some((word, flags)) => (word, split_flags(flags, flag_type)),
+                None => (entry, Vec::new()),
+            };
+            hunspell
+                .words
+                .entry(word.to_string())
+                .or_default()
+                .extend(flags);
+        }
+
+        Ok(hunspell)
+    }
+
+    /// The stems `word` could be an inflection of, according to the affix
+    /// rules. Every stem is in the `.dic` file with the flag for the rule.
+    pub fn stems(&self, word: &str) -> Vec<String> {
+        let mut stems = Vec::new();
+        let mut push = |stem: String| {
+            if stem != word && !stems.contains(&stem) {
+                stems.push(stem);
+            }
+        };
+
+        for suffix in &self.suffixes {
+            if let Some(stem) = suffix.remove_suffix(word)
+                && self.has_flag(&stem, &suffix.flag)
+            {
+                push(stem);
+            }
+        }
+
+        for prefix in &self.prefixes {
+            let Some(stem) = prefix.remove_prefix(word) else {
+                continue;
+            };
+            if self.has_flag(&stem, &prefix.flag) {
+                push(stem.clone());
+            }
+
+            // a prefix and a suffix together, as in `unfolded`
+            if prefix.cross {
+                for suffix in self.suffixes.iter().filter(|suffix| suffix.cross) {
+                    if let Some(root) = suffix.remove_suffix(&stem)
+                        && self.has_flag(&root, &suffix.flag)
+                        && self.has_flag(&root, &prefix.flag)
+                    {
+                        push(root);
+                    }
+                }
+            }
+        }
+
+        stems
+    }
+
+    /// Every form the affix rules allow for `word`, which must be a stem in
+    /// the `.dic` file. The word itself isn't included.
+    pub fn forms(&self, word: &str) -> Vec<String> {
+        let Some(flags) = self.words.get(word) else {
+            return Vec::new();
+        };
+        let applies = |affix: &&Affix| flags.contains(&affix.flag);
+
+        let mut forms = Vec::new();
+        for suffix in self.suffixes.iter().filter(applies) {
+            forms.extend(suffix.add_suffix(word));
+        }
+        for prefix in self.prefixes.iter().filter(applies) {
+            let Some(prefixed) = prefix.add_prefix(word) else {
+                continue;
+            };
+            if prefix.cross {
+                for suffix in self.suffixes.iter().filter(applies).filter(|s| s.cross) {
+                    forms.extend(suffix.add_suffix(&prefixed));
+                }
+            }
+            forms.push(prefixed);
+        }
+
+        forms.sort();
+        forms.dedup();
+        forms.retain(|form| form != word);
+        forms
+    }
+
+    /// Whether `word` is a stem in the `.dic` file
+    pub fn contains(&self, word: &str) -> bool {
+        self.words.contains_key(word)
+    }
+
+    fn has_flag(&self, word: &str, flag: &str) -> bool {
+        self.words
+            .get(word)
+            .is_some_and(|flags| flags.iter().any(|f| f == flag))
+    }
+}
+
+impl Affix {
+    fn remove_suffix(&self, word: &str) -> Option<String> {
+        let base = word.strip_suffix(self.add.as_str())?;
+        if base.is_empty() {
+            return None;
+        }
+        let stem = format!("{}{}", base, self.strip);
+        matches_end(&self.condition, &stem).then_some(stem)
+    }
+
+    fn add_suffix(&self, word: &str) -> Option<String> {
+        if !matches_end(&self.condition, word) {
+            return None;
+        }
+        let base = word.strip_suffix(self.strip.as_str())?;
+        Some(format!("{}{}", base, self.add))
+    }
+
+    fn remove_prefix(&self, word: &str) -> Option<String> {
+        let base = word.strip_prefix(self.add.as_str())?;
+        if base.is_empty() {
+            return None;
+        }
+        let stem = format!("{}{}", self.strip, base);
+        matches_start(&self.condition, &stem).then_some(stem)
+    }
+
+    fn add_prefix(&self, word: &str) -> Option<String> {
+        if !matches_start(&self.condition, word) {
+            return None;
+        }
+        let base = word.strip_prefix(self.strip.as_str())?;
+        Some(format!("{}{}", self.add, base))
+    }
+}
+
+impl Condition {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            Self::Any => true,
+            Self::Char(expected) => c == *expected,
+            Self::Set(chars, negated) => chars.contains(&c) != *negated,
+        }
+    }
+}
+
+fn matches_end(condition: &[Condition], word: &str) -> bool {
+    let chars: Vec<char> = word.chars().collect();
+    chars.len() >= condition.len()
+        && condition
+            .iter()
+            .zip(&chars[chars.len() - condition.len()..])
+            .all(|(cond, c)| cond.matches(*c))
+}
+
+fn matches_start(condition: &[Condition], word: &str) -> bool {
+    let mut chars = word.chars();
+    condition
+        .iter()
+        .all(|cond| chars.next().is_some_and(|c| cond.matches(c)))
+}
+
+/// Parses a condition like `[^aeiou]y`. A lone `.` matches anything, so it's empty.
+fn parse_condition(condition: &str) -> Vec<Condition> {
+    if condition == "." {
+        return Vec::new();
+    }
+
+    let mut parts = Vec::new();
+    let mut chars = condition.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '.' => parts.push(Condition::Any),
+            '[' => {
+                let mut set = Vec::new();
+                let mut negated = false;
+                for c in chars.by_ref() {
+                    match c {
+                        ']' => break,
+                        '^' if set.is_empty() && !negated => negated = true,
+                        c => set.push(c),
+                    }
+                }
+                parts.push(Condition::Set(set, negated));
+            }
+            c => parts.push(Condition::Char(c)),
+        }
+    }
+    parts
+}
+
+fn split_flags(flags: &str, flag_type: FlagType) -> Vec<String> {
+    match flag_type {
+        FlagType::Char => flags.chars().map(String::from).collect(),
+        FlagType::Long => flags
+            .chars()
+            .collect::<Vec<_>>()
+            .chunks(2)
+            .map(|pair| pair.iter().collect())
+            .collect(),
+        FlagType::Num => flags
+            .split(',')
+            .map(|flag| flag.trim().to_string())
+            .collect(),
+    }
+}
+
+/// `0` stands for the empty string in affix rules
+fn zero(field: &str) -> &str {
+    if field == "0" { "" } else { field }
+}
+
+/// Reads a dictionary file, which is often Latin-1 rather than UTF-8
+fn read_text(path: &Path) -> Result<String> {
+    let data = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(match String::from_utf8(data) {
+        Ok(text) => text,
+        Err(e) => e.into_bytes().into_iter().map(char::from).collect(),
+    })
+}
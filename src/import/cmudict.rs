@@ -0,0 +1,181 @@
+use super::{ShardWriter, read_maybe_compressed};
+use crate::{Dictionary, Pronunciation};
+use anyhow::Result;
+use std::path::Path;
+
+/// Adds the pronunciations from the CMU Pronouncing Dictionary (a word and
+/// its ARPAbet phones per line, alternates as `WORD(2)`) to the entries
+/// already in `existing`, converted to IPA and labelled as US English.
+/// Words without an entry are skipped.
+pub fn import(path: &Path, existing: &Dictionary, writer: &mut ShardWriter) -> Result<()> {
+    let data = read_maybe_compressed(path)?;
+    let data = String::from_utf8_lossy(&data);
+
+    // alternates follow their main entry, so each word's lines are adjacent
+    let mut words: Vec<(String, Vec<String>)> = Vec::new();
+    for line in data.lines() {
+        if line.starts_with(";;;") {
+            continue;
+        }
+        // newer releases put comments after a `#`
+        let line = line.split('#').next().unwrap_or_default();
+        let Some((word, phones)) = line.trim().split_once(char::is_whitespace) else {
+            continue;
+        };
+        let word = strip_variant(word).to_lowercase();
+        let ipa = to_ipa(phones);
+
+        match words.last_mut() {
+            Some((last, ipas)) if *last == word => ipas.push(ipa),
+            _ => words.push((word, vec![ipa])),
+        }
+    }
+
+    let keys: Vec<&str> = words.iter().map(|(word, _)| word.as_str()).collect();
+    let entries = existing.lookup_many(&keys)?;
+
+    for ((word, ipas), entry) in words.iter().zip(entries) {
+        let Some(mut def) = entry.filter(|def| def.word.to_lowercase() == *word) else {
+            writer.skip();
+            continue;
+        };
+        for ipa in ipas {
+            let pronunciation = Pronunciation {
+                ipa: ipa.clone(),
+                accent: Some("US".to_string()),
+                audio: None,
+            };
+            if !def.pronunciations.contains(&pronunciation) {
+                def.pronunciations.push(pronunciation);
+            }
+        }
+        writer.insert(def);
+    }
+
+    Ok(())
+}
+
+/// `READ(2)` to `READ`
+fn strip_variant(word: &str) -> &str {
+    match word.split_once('(') {
+        Some((base, rest)) if rest.ends_with(')') => base,
+        _ => word,
+    }
+}
+
+/// Converts ARPAbet phones like `HH AH0 L OW1` to IPA like `/həˈloʊ/`. Stress
+/// marks go before the consonants that start the stressed syllable, and are
+/// left out of words with one syllable.
+fn to_ipa(phones: &str) -> String {
+    // cmudict.dict writes them in lowercase
+    let phones = phones.to_uppercase();
+    let phones: Vec<&str> = phones.split_whitespace().collect();
+    let vowels = phones.iter().filter(|p| stress(p).is_some()).count();
+
+    let mut out: Vec<String> = Vec::new();
+    // where the consonants before the next vowel start in `out`
+    let mut onset = 0;
+    for (i, phone) in phones.iter().enumerate() {
+        let Some(level) = stress(phone) else {
+            out.push(consonant(phone).to_string());
+            continue;
+        };
+
+        let mark = match level {
+            '1' if vowels > 1 => Some("ˈ"),
+            '2' if vowels > 1 => Some("ˌ"),
+            _ => None,
+        };
+        if let Some(mark) = mark {
+            let start = if onset == 0 {
+                0
+            } else {
+                out.len() - onset_len(&phones[i - (out.len() - onset)..i])
+            };
+            out.insert(start, mark.to_string());
+        }
+        out.push(vowel(&phone[..phone.len() - 1], level).to_string());
+        onset = out.len();
+    }
+
+    format!("/{}/", out.concat())
+}
+
+/// The stress digit on a vowel, which consonants don't have
+fn stress(phone: &str) -> Option<char> {
+    phone.chars().last().filter(|c| c.is_ascii_digit())
+}
+
+/// How many of the consonants between two vowels start the second syllable:
+/// three for `str` or `spl`, two for clusters like `pr` or `st`, otherwise one
+fn onset_len(consonants: &[&str]) -> usize {
+    match consonants {
+        [] => 0,
+        [.., "S", first, second] if is_cluster("S", first) && is_cluster(first, second) => 3,
+        [.., first, second] if is_cluster(first, second) => 2,
+        _ => 1,
+    }
+}
+
+fn is_cluster(first: &str, second: &str) -> bool {
+    matches!(
+        (first, second),
+        ("P" | "B" | "K" | "G" | "F", "L" | "R")
+            | ("T" | "D" | "TH", "R")
+            | ("S", "P" | "T" | "K" | "M" | "N" | "L" | "W")
+            | ("K" | "T" | "D", "W")
+    )
+}
+
+fn vowel(phone: &str, level: char) -> &'static str {
+    match (phone, level) {
+        ("AA", _) => "ɑ",
+        ("AE", _) => "æ",
+        ("AH", '0') => "ə",
+        ("AH", _) => "ʌ",
+        ("AO", _) => "ɔ",
+        ("AW", _) => "aʊ",
+        ("AY", _) => "aɪ",
+        ("EH", _) => "ɛ",
+        ("ER", '0') => "ɚ",
+        ("ER", _) => "ɝ",
+        ("EY", _) => "eɪ",
+        ("IH", _) => "ɪ",
+        ("IY", _) => "i",
+        ("OW", _) => "oʊ",
+        ("OY", _) => "ɔɪ",
+        ("UH", _) => "ʊ",
+        ("UW", _) => "u",
+        _ => "",
+    }
+}
+
+fn consonant(phone: &str) -> &'static str {
+    match phone {
+        "B" => "b",
+        "CH" => "tʃ",
+        "D" => "d",
+        "DH" => "ð",
+        "F" => "f",
+        "G" => "ɡ",
+        "HH" => "h",
+        "JH" => "dʒ",
+        "K" => "k",
+        "L" => "l",
+        "M" => "m",
+        "N" => "n",
+        "NG" => "ŋ",
+        "P" => "p",
+        "R" => "ɹ",
+        "S" => "s",
+        "SH" => "ʃ",
+        "T" => "t",
+        "TH" => "θ",
+        "V" => "v",
+        "W" => "w",
+        "Y" => "j",
+        "Z" => "z",
+        "ZH" => "ʒ",
+        _ => "",
+    }
+}
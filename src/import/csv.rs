@@ -0,0 +1,169 @@
+use super::ShardWriter;
+use crate::{Definition, Etymology, PartOfSpeech, Sense};
+use anyhow::{Context, Result};
+use std::{collections::BTreeMap, path::Path};
+
+/// Which header names hold each field. Only `word` and `sense` are required.
+#[derive(Debug, Clone)]
+pub struct Columns {
+    pub word: String,
+    pub pos: String,
+    pub sense: String,
+    pub example: String,
+    pub origin: String,
+    pub labels: String,
+}
+
+impl Default for Columns {
+    fn default() -> Self {
+        Self {
+            word: String::from("word"),
+            pos: String::from("pos"),
+            sense: String::from("sense"),
+            example: String::from("example"),
+            origin: String::from("origin"),
+            labels: String::from("labels"),
+        }
+    }
+}
+
+impl Columns {
+    /// Parses a mapping like `word=Term,sense=Meaning`. Fields left out keep
+    /// their default header name.
+    pub fn parse(spec: &str) -> Result<Self> {
+        let mut columns = Self::default();
+
+        for pair in spec.split(',').filter(|pair| !pair.trim().is_empty()) {
+            let (field, header) = pair
+                .split_once('=')
+                .with_context(|| format!("Expected field=header, got '{}'", pair))?;
+            let header = header.trim().to_string();
+
+            match field.trim() {
+                "word" => columns.word = header,
+                "pos" => columns.pos = header,
+                "sense" => columns.sense = header,
+                "example" => columns.example = header,
+                "origin" => columns.origin = header,
+                "labels" => columns.labels = header,
+                other => anyhow::bail!(
+                    "Unknown field '{}', expected word, pos, sense, example, origin or labels",
+                    other
+                ),
+            }
+        }
+
+        Ok(columns)
+    }
+}
+
+/// Imports a CSV (or TSV, picked by a `.tsv`/`.tab` extension) file with a
+/// header row. Each row is one sense; rows sharing a word and part of speech
+/// are grouped, and repeated senses collect their examples.
+pub fn import(path: &Path, columns: &Columns, writer: &mut ShardWriter) -> Result<()> {
+    let delimiter = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("tsv" | "tab") => b'\t',
+        _ => b',',
+    };
+
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_path(path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+
+    let headers = reader.headers()?.clone();
+    let find = |name: &str| headers.iter().position(|header| header.trim() == name);
+    let word_idx = find(&columns.word)
+        .with_context(|| format!("No '{}' column in {}", columns.word, path.display()))?;
+    let sense_idx = find(&columns.sense)
+        .with_context(|| format!("No '{}' column in {}", columns.sense, path.display()))?;
+    let pos_idx = find(&columns.pos);
+    let example_idx = find(&columns.example);
+    let origin_idx = find(&columns.origin);
+    let labels_idx = find(&columns.labels);
+
+    let mut entries: BTreeMap<String, Definition> = BTreeMap::new();
+
+    for record in reader.records() {
+        let record = record?;
+        let field = |idx: Option<usize>| {
+            idx.and_then(|idx| record.get(idx))
+                .map(str::trim)
+                .unwrap_or_default()
+        };
+
+        let word = field(Some(word_idx));
+        let sense = field(Some(sense_idx));
+        if word.is_empty() || sense.is_empty() {
+            continue;
+        }
+        let pos = match field(pos_idx) {
+            "" => "Definition",
+            pos => pos,
+        };
+        let example = field(example_idx);
+        let origin = field(origin_idx);
+        // several labels share a cell, like "archaic; slang"
+        let labels: Vec<String> = field(labels_idx)
+            .split([',', ';'])
+            .map(str::trim)
+            .filter(|label| !label.is_empty())
+            .map(str::to_string)
+            .collect();
+
+        let def = entries
+            .entry(word.to_string())
+            .or_insert_with(|| Definition {
+                word: word.to_string(),
+                etymologies: vec![Etymology {
+                    parts_of_speech: Vec::new(),
+                    origin: None,
+                    source: None,
+                }],
+                pronunciations: Vec::new(),
+                frequency: None,
+            });
+        if def.etymologies[0].origin.is_none() && !origin.is_empty() {
+            def.etymologies[0].origin = Some(origin.to_string());
+        }
+        let parts = &mut def.etymologies[0].parts_of_speech;
+
+        let part = match parts.iter().position(|part| part.part_of_speech == pos) {
+            Some(idx) => &mut parts[idx],
+            None => {
+                parts.push(PartOfSpeech {
+                    part_of_speech: pos.to_string(),
+                    senses: Vec::new(),
+                });
+                parts.last_mut().unwrap()
+            }
+        };
+
+        let existing = part.senses.iter().position(|s| s.sense == sense);
+        let target = match existing {
+            Some(idx) => &mut part.senses[idx],
+            None => {
+                part.senses.push(Sense {
+                    sense: sense.to_string(),
+                    date: None,
+                    labels,
+                    examples: Vec::new(),
+                    synonyms: Vec::new(),
+                    antonyms: Vec::new(),
+                });
+                part.senses.last_mut().unwrap()
+            }
+        };
+
+        if !example.is_empty() {
+            target.examples.push(example.to_string());
+        }
+    }
+
+    for def in entries.into_values() {
+        writer.insert(def);
+    }
+
+    Ok(())
+}
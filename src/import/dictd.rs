@@ -0,0 +1,53 @@
+use super::{ShardWriter, read_maybe_compressed, simple_definition};
+use anyhow::{Context, Result};
+use std::path::Path;
+
+/// Imports a dictd database given the path to its `.index` file. The `.dict`
+/// (or dictzipped `.dict.dz`) must sit next to it. Each article becomes an
+/// entry with a single sense.
+pub fn import(index: &Path, writer: &mut ShardWriter) -> Result<()> {
+    let entries = read_maybe_compressed(index)?;
+    let entries = String::from_utf8_lossy(&entries);
+    let dict = read_maybe_compressed(&index.with_extension("dict"))?;
+
+    for line in entries.lines().filter(|line| !line.is_empty()) {
+        let mut fields = line.split('\t');
+        let (Some(word), Some(offset), Some(length)) =
+            (fields.next(), fields.next(), fields.next())
+        else {
+            anyhow::bail!("Malformed .index line: {}", line);
+        };
+
+        // metadata entries like 00-database-info describe the database itself
+        if word.starts_with("00-database-") || word.starts_with("00database") {
+            continue;
+        }
+
+        let offset = decode_b64(offset).with_context(|| format!("Bad offset in: {}", line))?;
+        let length = decode_b64(length).with_context(|| format!("Bad length in: {}", line))?;
+        let article = dict
+            .get(offset..offset + length)
+            .with_context(|| format!("Article for '{}' is outside the .dict file", word))?;
+
+        let text = String::from_utf8_lossy(article)
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        if !text.is_empty() {
+            writer.insert(simple_definition(word, "Definition", vec![text]));
+        }
+    }
+
+    Ok(())
+}
+
+/// Decodes dictd's base64 numbers, most significant digit first
+fn decode_b64(digits: &str) -> Option<usize> {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    digits.bytes().try_fold(0usize, |acc, digit| {
+        let value = ALPHABET.iter().position(|&c| c == digit)?;
+        Some(acc * 64 + value)
+    })
+}
@@ -0,0 +1,46 @@
+use super::{ShardWriter, read_maybe_compressed};
+use crate::Dictionary;
+use anyhow::Result;
+use std::{collections::HashSet, path::Path};
+
+/// Adds frequency ranks from a word frequency list to the entries already in
+/// `existing`. The list has one word per line, most frequent first, optionally
+/// followed by its count (`the 23135851162`); the line a word is on gives its
+/// rank. Words without an entry are skipped.
+pub fn import(path: &Path, existing: &Dictionary, writer: &mut ShardWriter) -> Result<()> {
+    let data = read_maybe_compressed(path)?;
+    let data = String::from_utf8_lossy(&data);
+
+    // `Paris` and `paris` would find the same entry, so only the first counts
+    let mut seen = HashSet::new();
+    let mut words: Vec<String> = Vec::new();
+    for line in data.lines() {
+        if line.starts_with('#') {
+            continue;
+        }
+        let Some(word) = line
+            .split(['\t', ',', ' '])
+            .map(str::trim)
+            .find(|w| !w.is_empty())
+        else {
+            continue;
+        };
+        if seen.insert(word.to_lowercase()) {
+            words.push(word.to_string());
+        }
+    }
+
+    let entries = existing.lookup_many(&words)?;
+
+    for (idx, (word, entry)) in words.iter().zip(entries).enumerate() {
+        let Some(mut def) = entry.filter(|def| def.word.to_lowercase() == word.to_lowercase())
+        else {
+            writer.skip();
+            continue;
+        };
+        def.frequency = Some(idx as u32 + 1);
+        writer.insert(def);
+    }
+
+    Ok(())
+}
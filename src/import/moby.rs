@@ -0,0 +1,53 @@
+use super::{ShardWriter, read_maybe_compressed};
+use crate::Dictionary;
+use anyhow::Result;
+use std::path::Path;
+
+/// Adds the synonyms from the Moby Thesaurus (`mthesaur.txt`: a root word
+/// and its synonyms per line, comma separated) to the entries already in
+/// `existing`. The thesaurus doesn't tell senses apart, so each word's
+/// synonyms go on the first sense of its entry. Words without an entry are
+/// skipped.
+pub fn import(path: &Path, existing: &Dictionary, writer: &mut ShardWriter) -> Result<()> {
+    let data = read_maybe_compressed(path)?;
+    let data = String::from_utf8_lossy(&data);
+
+    let lines: Vec<Vec<&str>> = data
+        .lines()
+        .map(|line| {
+            line.split(',')
+                .map(str::trim)
+                .filter(|w| !w.is_empty())
+                .collect()
+        })
+        .filter(|words: &Vec<&str>| words.len() > 1)
+        .collect();
+    let roots: Vec<&str> = lines.iter().map(|words| words[0]).collect();
+    let entries = existing.lookup_many(&roots)?;
+
+    for (words, entry) in lines.iter().zip(entries) {
+        let Some(mut def) = entry.filter(|def| def.word == words[0]) else {
+            writer.skip();
+            continue;
+        };
+        let Some(sense) = def
+            .etymologies
+            .iter_mut()
+            .flat_map(|etym| &mut etym.parts_of_speech)
+            .flat_map(|pos| &mut pos.senses)
+            .next()
+        else {
+            writer.skip();
+            continue;
+        };
+
+        for synonym in &words[1..] {
+            if !sense.synonyms.iter().any(|s| s == synonym) {
+                sense.synonyms.push(synonym.to_string());
+            }
+        }
+        writer.insert(def);
+    }
+
+    Ok(())
+}
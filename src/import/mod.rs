@@ -0,0 +1,223 @@
+use crate::{
+    Definition, DictionaryFile, Etymology, PartOfSpeech, Sense,
+    dictionary::normalize,
+    index, manifest,
+    paths::project_dirs,
+    sharding::{ShardScheme, shard_key},
+};
+use anyhow::{Context, Result};
+use flate2::read::GzDecoder;
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+pub mod cmudict;
+pub mod csv;
+pub mod dictd;
+pub mod frequency;
+pub mod moby;
+pub mod stardict;
+pub mod wiktextract;
+
+/// Collects imported entries and writes them out as a shard tree
+#[derive(Debug, Default)]
+pub struct ShardWriter {
+    entries: DictionaryFile,
+    scheme: ShardScheme,
+    skipped: usize,
+}
+
+impl ShardWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Shards a new tree by `scheme` rather than the default. A tree that
+    /// already exists keeps the scheme in its manifest.
+    pub fn with_scheme(mut self, scheme: ShardScheme) -> Self {
+        self.scheme = scheme;
+        self
+    }
+
+    /// Adds an entry, with its headword in NFC. A headword seen twice keeps
+    /// both sets of etymologies.
+    pub fn insert(&mut self, mut def: Definition) {
+        def.word = normalize(&def.word);
+        if shard_key(&def.word).next().is_none() || !storable(&def.word) {
+            self.skipped += 1;
+            return;
+        }
+
+        match self.entries.get_mut(&def.word) {
+            Some(existing) => {
+                existing.etymologies.extend(def.etymologies);
+                existing.frequency = match (existing.frequency, def.frequency) {
+                    (Some(a), Some(b)) => Some(a.min(b)),
+                    (a, b) => a.or(b),
+                };
+                for pronunciation in def.pronunciations {
+                    if !existing.pronunciations.contains(&pronunciation) {
+                        existing.pronunciations.push(pronunciation);
+                    }
+                }
+            }
+            None => {
+                self.entries.insert(def.word.clone(), def);
+            }
+        }
+    }
+
+    /// Number of headwords collected so far
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Number of entries dropped, because their headword can't be stored as a
+    /// shard or the importer had nothing to make of them
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    /// Counts an entry the importer dropped
+    pub fn skip(&mut self) {
+        self.skipped += 1;
+    }
+
+    /// Writes every shard under `root`, filed by the tree's scheme. Entries
+    /// already in a shard on disk are kept unless an imported entry has the
+    /// same headword.
+    pub fn write(self, root: &Path) -> Result<()> {
+        let scheme = manifest::prepare_for_write(root, self.scheme)?;
+        index::invalidate()?;
+        let mut shards: BTreeMap<PathBuf, DictionaryFile> = BTreeMap::new();
+        for (word, def) in self.entries {
+            if let Some(shard) = scheme.path(&word) {
+                shards.entry(shard).or_default().insert(word, def);
+            }
+        }
+
+        for (shard, data) in shards {
+            let mut target = root.join(&shard);
+            target.set_extension("json");
+
+            let mut merged: DictionaryFile = match fs::read_to_string(&target) {
+                Ok(contents) => serde_json::from_str(&contents)
+                    .with_context(|| format!("Failed to parse {}", target.display()))?,
+                Err(_) => DictionaryFile::new(),
+            };
+            merged.extend(data);
+
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&target, serde_json::to_string(&merged)?)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Where imports go unless told otherwise: the folder for `lang` in the
+/// `dict` folder of the user data dir
+pub fn default_target(lang: &str) -> Result<PathBuf> {
+    crate::dictionary::check_lang(lang)?;
+    let dirs = project_dirs().context("Could not determine the user data directory")?;
+    Ok(dirs.data_dir().join("dict").join(lang))
+}
+
+/// Reads `path`, falling back to `path.gz` or `path.dz` and decompressing those
+pub(crate) fn read_maybe_compressed(path: &Path) -> Result<Vec<u8>> {
+    if let Ok(data) = fs::read(path) {
+        return Ok(data);
+    }
+
+    for ext in ["gz", "dz"] {
+        let mut compressed = PathBuf::from(path);
+        compressed.as_mut_os_string().push(format!(".{}", ext));
+
+        if let Ok(file) = fs::File::open(&compressed) {
+            let mut data = Vec::new();
+            GzDecoder::new(file).read_to_end(&mut data)?;
+            return Ok(data);
+        }
+    }
+
+    anyhow::bail!("{} not found (also tried .gz and .dz)", path.display())
+}
+
+/// Whether a headword maps to a safe shard path inside the root
+fn storable(word: &str) -> bool {
+    !word.starts_with('.')
+        && !word
+            .chars()
+            .take(2)
+            .any(|c| matches!(c, '/' | '\\' | '\0') || c.is_control())
+}
+
+/// Builds an entry with a single etymology and part of speech, one sense per item
+pub fn simple_definition(word: &str, part_of_speech: &str, senses: Vec<String>) -> Definition {
+    Definition {
+        word: word.to_string(),
+        etymologies: vec![Etymology {
+            parts_of_speech: vec![PartOfSpeech {
+                part_of_speech: part_of_speech.to_string(),
+                senses: senses
+                    .into_iter()
+                    .map(|sense| Sense {
+                        sense,
+                        date: None,
+                        labels: Vec::new(),
+                        examples: Vec::new(),
+                        synonyms: Vec::new(),
+                        antonyms: Vec::new(),
+                    })
+                    .collect(),
+            }],
+            origin: None,
+            source: None,
+        }],
+        pronunciations: Vec::new(),
+        frequency: None,
+    }
+}
+
+/// Removes `<tags>` and decodes the common entities from markup fields, turning
+/// line break and paragraph tags into newlines
+pub fn strip_markup(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut tag: Option<String> = None;
+
+    for c in text.chars() {
+        match (&mut tag, c) {
+            (None, '<') => tag = Some(String::new()),
+            (None, c) => out.push(c),
+            (Some(name), '>') => {
+                let name = name
+                    .trim_end_matches('/')
+                    .split_whitespace()
+                    .next()
+                    .unwrap_or_default()
+                    .to_lowercase();
+                if matches!(name.as_str(), "br" | "/p" | "/div" | "/li") {
+                    out.push('\n');
+                }
+                tag = None;
+            }
+            (Some(name), c) => name.push(c),
+        }
+    }
+
+    out.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
@@ -0,0 +1,148 @@
+use super::{ShardWriter, read_maybe_compressed, simple_definition, strip_markup};
+use anyhow::{Context, Result};
+use std::{collections::HashMap, fs, path::Path};
+
+/// Imports a StarDict dictionary given the path to its `.ifo` file. The `.idx`
+/// and `.dict` files (optionally gzipped / dictzipped) must sit next to it.
+pub fn import(ifo: &Path, writer: &mut ShardWriter) -> Result<()> {
+    let info = read_ifo(ifo)?;
+
+    let idx = read_maybe_compressed(&ifo.with_extension("idx"))?;
+    let dict = read_maybe_compressed(&ifo.with_extension("dict"))?;
+
+    let offset_bits: u32 = info
+        .get("idxoffsetbits")
+        .map(|bits| bits.parse())
+        .transpose()?
+        .unwrap_or(32);
+    let types = info.get("sametypesequence").map(String::as_str);
+
+    for (word, offset, size) in parse_idx(&idx, offset_bits)? {
+        let data = dict
+            .get(offset..offset + size)
+            .with_context(|| format!("Entry for '{}' is outside the .dict file", word))?;
+
+        let senses: Vec<String> = parse_fields(data, types)
+            .into_iter()
+            .flat_map(|text| {
+                text.lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty())
+                    .map(str::to_string)
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        if !senses.is_empty() {
+            writer.insert(simple_definition(&word, "Definition", senses));
+        }
+    }
+
+    Ok(())
+}
+
+fn read_ifo(path: &Path) -> Result<HashMap<String, String>> {
+    let contents =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let mut lines = contents.lines();
+
+    if lines.next().map(str::trim) != Some("StarDict's dict ifo file") {
+        anyhow::bail!("{} is not a StarDict .ifo file", path.display());
+    }
+
+    Ok(lines
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect())
+}
+
+/// Splits the `.idx` file into (word, offset, size) triples
+fn parse_idx(idx: &[u8], offset_bits: u32) -> Result<Vec<(String, usize, usize)>> {
+    let offset_len = if offset_bits == 64 { 8 } else { 4 };
+    let mut entries = Vec::new();
+    let mut rest = idx;
+
+    while !rest.is_empty() {
+        let nul = rest
+            .iter()
+            .position(|&b| b == 0)
+            .context("Truncated .idx file")?;
+        let word = String::from_utf8_lossy(&rest[..nul]).into_owned();
+        rest = &rest[nul + 1..];
+
+        if rest.len() < offset_len + 4 {
+            anyhow::bail!("Truncated .idx file");
+        }
+        let offset = read_be(&rest[..offset_len]);
+        let size = read_be(&rest[offset_len..offset_len + 4]);
+        rest = &rest[offset_len + 4..];
+
+        entries.push((word, offset, size));
+    }
+
+    Ok(entries)
+}
+
+fn read_be(bytes: &[u8]) -> usize {
+    bytes.iter().fold(0, |acc, &b| (acc << 8) | b as usize)
+}
+
+/// Extracts the textual fields of an entry, dropping audio, images and other
+/// binary data
+fn parse_fields(data: &[u8], types: Option<&str>) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut rest = data;
+
+    match types {
+        // every field's type is given up front, and the last field runs to the end
+        Some(types) => {
+            let types: Vec<char> = types.chars().collect();
+            for (idx, &kind) in types.iter().enumerate() {
+                let last = idx + 1 == types.len();
+                let Some((field, remaining)) = take_field(rest, kind, last) else {
+                    break;
+                };
+                push_text(&mut fields, kind, field);
+                rest = remaining;
+            }
+        }
+        // each field is prefixed by its type
+        None => {
+            while let Some((&kind, remaining)) = rest.split_first() {
+                let Some((field, remaining)) = take_field(remaining, kind as char, false) else {
+                    break;
+                };
+                push_text(&mut fields, kind as char, field);
+                rest = remaining;
+            }
+        }
+    }
+
+    fields
+}
+
+/// Lowercase types are NUL terminated strings, uppercase ones are prefixed by a
+/// 32 bit size. The final field of a `sametypesequence` entry has neither.
+fn take_field(data: &[u8], kind: char, last: bool) -> Option<(&[u8], &[u8])> {
+    if last {
+        return Some((data, &[]));
+    }
+
+    if kind.is_ascii_lowercase() {
+        let end = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+        Some((&data[..end], data.get(end + 1..).unwrap_or_default()))
+    } else {
+        let size = read_be(data.get(..4)?);
+        let end = 4 + size;
+        Some((data.get(4..end)?, &data[end..]))
+    }
+}
+
+fn push_text(fields: &mut Vec<String>, kind: char, data: &[u8]) {
+    let text = String::from_utf8_lossy(data);
+    match kind {
+        'm' | 'l' | 'y' | 'k' | 'w' => fields.push(text.into_owned()),
+        'g' | 'h' | 'x' => fields.push(strip_markup(&text)),
+        _ => {}
+    }
+}
@@ -0,0 +1,227 @@
+use super::ShardWriter;
+use crate::{Definition, Etymology, PartOfSpeech, Pronunciation, Sense};
+use anyhow::{Context, Result};
+use flate2::read::GzDecoder;
+use serde::Deserialize;
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Read},
+    path::Path,
+};
+
+/// One line of a wiktextract dump: a single part of speech of a single word
+#[derive(Debug, Deserialize)]
+struct Entry {
+    word: String,
+    #[serde(default)]
+    pos: String,
+    #[serde(default)]
+    etymology_number: Option<u32>,
+    #[serde(default)]
+    etymology_text: Option<String>,
+    #[serde(default)]
+    senses: Vec<EntrySense>,
+    #[serde(default)]
+    sounds: Vec<Sound>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Sound {
+    #[serde(default)]
+    ipa: Option<String>,
+    #[serde(default)]
+    ogg_url: Option<String>,
+    #[serde(default)]
+    mp3_url: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EntrySense {
+    #[serde(default)]
+    glosses: Vec<String>,
+    #[serde(default)]
+    raw_glosses: Vec<String>,
+    #[serde(default)]
+    examples: Vec<Example>,
+    #[serde(default)]
+    synonyms: Vec<Related>,
+    #[serde(default)]
+    antonyms: Vec<Related>,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+/// A word linked to a sense, like a synonym or antonym
+#[derive(Debug, Deserialize)]
+struct Related {
+    word: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Example {
+    #[serde(default)]
+    text: String,
+}
+
+/// Streams a kaikki.org wiktextract JSONL dump (optionally gzipped) into the
+/// writer. Consecutive lines for the same word and etymology number become
+/// parts of speech of one etymology.
+pub fn import(path: &Path, writer: &mut ShardWriter) -> Result<()> {
+    let file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let reader: Box<dyn Read> = if path.extension().is_some_and(|ext| ext == "gz") {
+        Box::new(GzDecoder::new(file))
+    } else {
+        Box::new(file)
+    };
+
+    let mut pending: Option<(Definition, Option<u32>)> = None;
+
+    for (line_no, line) in BufReader::new(reader).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let entry: Entry = serde_json::from_str(&line)
+            .with_context(|| format!("{}:{}: invalid entry", path.display(), line_no + 1))?;
+        let Some(pos) = convert_pos(&entry) else {
+            continue;
+        };
+        let pronunciations = convert_sounds(&entry);
+        let origin = entry.etymology_text.filter(|text| !text.is_empty());
+
+        match &mut pending {
+            Some((def, number)) if def.word == entry.word && *number == entry.etymology_number => {
+                if let Some(etymology) = def.etymologies.last_mut() {
+                    etymology.parts_of_speech.push(pos);
+                    if etymology.origin.is_none() {
+                        etymology.origin = origin;
+                    }
+                }
+                for pronunciation in pronunciations {
+                    if !def.pronunciations.contains(&pronunciation) {
+                        def.pronunciations.push(pronunciation);
+                    }
+                }
+            }
+            _ => {
+                if let Some((def, _)) = pending.take() {
+                    writer.insert(def);
+                }
+
+                let def = Definition {
+                    word: entry.word,
+                    etymologies: vec![Etymology {
+                        parts_of_speech: vec![pos],
+                        origin,
+                        source: None,
+                    }],
+                    pronunciations,
+                    frequency: None,
+                };
+                pending = Some((def, entry.etymology_number));
+            }
+        }
+    }
+
+    if let Some((def, _)) = pending {
+        writer.insert(def);
+    }
+
+    Ok(())
+}
+
+fn convert_pos(entry: &Entry) -> Option<PartOfSpeech> {
+    let senses: Vec<Sense> = entry
+        .senses
+        .iter()
+        .filter_map(|sense| {
+            // raw glosses keep qualifiers like "(informal)", matching the bundled data
+            let gloss = sense.raw_glosses.last().or(sense.glosses.last())?;
+
+            Some(Sense {
+                sense: gloss.clone(),
+                date: None,
+                labels: sense.tags.clone(),
+                examples: sense
+                    .examples
+                    .iter()
+                    .map(|example| example.text.trim().to_string())
+                    .filter(|text| !text.is_empty())
+                    .collect(),
+                synonyms: sense.synonyms.iter().map(|s| s.word.clone()).collect(),
+                antonyms: sense.antonyms.iter().map(|s| s.word.clone()).collect(),
+            })
+        })
+        .collect();
+
+    if senses.is_empty() {
+        return None;
+    }
+
+    Some(PartOfSpeech {
+        part_of_speech: pos_name(&entry.pos),
+        senses,
+    })
+}
+
+/// The IPA transcriptions and recordings among the entry's sounds, labelled
+/// with the first accent tag wiktextract gives them
+fn convert_sounds(entry: &Entry) -> Vec<Pronunciation> {
+    let mut pronunciations: Vec<Pronunciation> = Vec::new();
+    for sound in &entry.sounds {
+        let ipa = sound.ipa.clone().unwrap_or_default();
+        let audio = sound.ogg_url.clone().or_else(|| sound.mp3_url.clone());
+        if ipa.is_empty() && audio.is_none() {
+            continue;
+        }
+        let pronunciation = Pronunciation {
+            ipa,
+            accent: sound.tags.first().cloned(),
+            audio,
+        };
+        if !pronunciations.contains(&pronunciation) {
+            pronunciations.push(pronunciation);
+        }
+    }
+    pronunciations
+}
+
+/// Expands wiktextract's short part of speech tags into the names used by the
+/// bundled dictionary
+fn pos_name(pos: &str) -> String {
+    let name = match pos {
+        "noun" => "Noun",
+        "verb" => "Verb",
+        "adj" => "Adjective",
+        "adv" => "Adverb",
+        "pron" => "Pronoun",
+        "prep" => "Preposition",
+        "conj" => "Conjunction",
+        "intj" => "Interjection",
+        "det" => "Determiner",
+        "num" => "Numeral",
+        "name" => "Proper noun",
+        "abbrev" => "Abbreviation",
+        "prefix" => "Prefix",
+        "suffix" => "Suffix",
+        "particle" => "Particle",
+        "article" => "Article",
+        "phrase" => "Phrase",
+        "prep_phrase" => "Prepositional phrase",
+        "proverb" => "Proverb",
+        "contraction" => "Contraction",
+        "symbol" => "Symbol",
+        other => {
+            let mut chars = other.chars();
+            return match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::from("Unknown"),
+            };
+        }
+    };
+
+    name.to_string()
+}
@@ -0,0 +1,114 @@
+use super::existing_roots;
+use crate::{Dictionary, dictionary::normalize};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Name of the anagram index inside the index directory
+pub const FILE_NAME: &str = "anagrams.msgpack";
+
+/// Headwords grouped by their letters in sorted order, so every anagram of a
+/// set of letters is one map lookup away. Spaces, hyphens and apostrophes
+/// don't count as letters.
+///
+/// It only knows the headwords of the roots it was built from, so it's
+/// ignored for any other set of roots and removed by
+/// [`invalidate`](super::invalidate).
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AnagramIndex {
+    groups: BTreeMap<String, Vec<String>>,
+    /// The full paths of the roots that existed, empty in an index from
+    /// before they were recorded
+    #[serde(default)]
+    roots: Vec<PathBuf>,
+}
+
+/// The letters of `word`, lowercased and sorted: `Listen` gives `eilnst`
+pub fn key(word: &str) -> String {
+    let mut letters: Vec<char> = normalize(word)
+        .to_lowercase()
+        .chars()
+        .filter(|c| c.is_alphabetic())
+        .collect();
+    letters.sort_unstable();
+    letters.into_iter().collect()
+}
+
+/// Whether every letter of `part` appears in `whole` at least as often. Both
+/// are sorted keys.
+fn contains(whole: &str, part: &str) -> bool {
+    let mut whole = whole.chars();
+    part.chars().all(|c| whole.any(|w| w == c))
+}
+
+impl AnagramIndex {
+    pub fn open(path: &Path) -> Result<Self> {
+        let data = fs::read(path).with_context(|| format!("Failed to open {}", path.display()))?;
+        rmp_serde::from_slice(&data)
+            .with_context(|| format!("{} is not a dibble anagram index", path.display()))
+    }
+
+    /// Whether the index was built from exactly the roots of `roots` that
+    /// exist, in that order
+    pub fn covers(&self, roots: &[PathBuf]) -> bool {
+        existing_roots(roots) == self.roots
+    }
+
+    /// Groups every headword in the dictionary
+    pub fn from_dict(dict: &Dictionary) -> Result<Self> {
+        let mut index = Self {
+            roots: existing_roots(dict.roots()),
+            ..Self::default()
+        };
+        for word in dict.iter_words()? {
+            let word = word?;
+            let key = key(&word);
+            if !key.is_empty() {
+                index.groups.entry(key).or_default().push(word);
+            }
+        }
+        for words in index.groups.values_mut() {
+            words.sort();
+            words.dedup();
+        }
+        Ok(index)
+    }
+
+    /// Writes the dictionary's anagram index to `path`, replacing any
+    /// existing one. Returns the number of headwords indexed.
+    pub fn build(dict: &Dictionary, path: &Path) -> Result<usize> {
+        let index = Self::from_dict(dict)?;
+        let partial = path.with_extension("partial");
+        fs::write(&partial, rmp_serde::to_vec(&index)?)?;
+        fs::rename(&partial, path)?;
+        Ok(index.groups.values().map(Vec::len).sum())
+    }
+
+    /// The headwords spelled with exactly `letters`, sorted
+    pub fn anagrams(&self, letters: &str) -> Vec<String> {
+        self.groups.get(&key(letters)).cloned().unwrap_or_default()
+    }
+
+    /// The headwords spelled with some of `letters`, each used at most as
+    /// often as it's given. Longest first, then alphabetical.
+    pub fn partial(&self, letters: &str) -> Vec<String> {
+        let letters = key(letters);
+        let mut words: Vec<String> = self
+            .groups
+            .iter()
+            .filter(|(key, _)| key.len() <= letters.len() && contains(&letters, key))
+            .flat_map(|(_, words)| words.iter().cloned())
+            .collect();
+        words.sort_by(|a, b| {
+            b.chars()
+                .count()
+                .cmp(&a.chars().count())
+                .then_with(|| a.cmp(b))
+        });
+        words
+    }
+}
@@ -0,0 +1,196 @@
+use super::{existing_roots, read_paths, write_paths};
+use crate::{Definition, Dictionary};
+use anyhow::{Context, Result};
+use memmap2::Mmap;
+use std::{
+    cmp::Ordering,
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+/// Name of the entry index inside the index directory
+pub const FILE_NAME: &str = "entries.bin";
+
+const MAGIC: &[u8; 8] = b"DIBBLEIX";
+const VERSION: u32 = 2;
+/// magic, version, entry count, length of the key blob
+const HEADER_LEN: u64 = 8 + 4 + 4 + 8;
+/// key offset, key length, value offset, value length
+const ROW_LEN: u64 = 4 + 4 + 8 + 4;
+
+/// Every entry in one file, found by binary search over a sorted offset table.
+/// The file is memory mapped, so a lookup only pages in the few table rows it
+/// probes plus the entry itself.
+///
+/// It answers with the entries of the roots it was built from, as they were
+/// then, so it's ignored for any other set of roots, and anything writing
+/// shards removes it with [`invalidate`](super::invalidate).
+///
+/// Layout (little endian): a header, the full paths of the roots that
+/// existed, then one row per entry sorted by headword, then all headwords
+/// back to back, then all entries. Entries are MessagePack with named
+/// fields, which stays compact while tolerating the optional fields in
+/// [`Definition`].
+#[derive(Debug)]
+pub struct BinaryIndex {
+    map: Mmap,
+    count: u32,
+    roots: Vec<PathBuf>,
+    rows_start: u64,
+    keys_start: u64,
+    values_start: u64,
+}
+
+impl BinaryIndex {
+    pub fn open(path: &Path) -> Result<Self> {
+        let file =
+            File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+        // SAFETY: the index is only ever replaced by renaming a new file over
+        // it, never modified in place, so the mapped pages can't change under us
+        let map = unsafe { Mmap::map(&file)? };
+
+        let header = map
+            .get(..HEADER_LEN as usize)
+            .with_context(|| format!("{} is truncated", path.display()))?;
+        if &header[..8] != MAGIC {
+            anyhow::bail!("{} is not a dibble index", path.display());
+        }
+        let version = u32::from_le_bytes(header[8..12].try_into()?);
+        if version != VERSION {
+            anyhow::bail!(
+                "{} has index version {}, expected {} (rebuild it with `dibble index build`)",
+                path.display(),
+                version,
+                VERSION
+            );
+        }
+        let count = u32::from_le_bytes(header[12..16].try_into()?);
+        let keys_len = u64::from_le_bytes(header[16..24].try_into()?);
+
+        let (roots, rows_start) = read_paths(&map, HEADER_LEN as usize)
+            .with_context(|| format!("{} is truncated", path.display()))?;
+        let rows_start = rows_start as u64;
+
+        let keys_start = rows_start + ROW_LEN * count as u64;
+        if (map.len() as u64) < keys_start + keys_len {
+            anyhow::bail!("{} is truncated", path.display());
+        }
+
+        Ok(Self {
+            map,
+            count,
+            roots,
+            rows_start,
+            keys_start,
+            values_start: keys_start + keys_len,
+        })
+    }
+
+    /// Whether the index was built from exactly the roots of `roots` that
+    /// exist, in that order
+    pub fn covers(&self, roots: &[PathBuf]) -> bool {
+        existing_roots(roots) == self.roots
+    }
+
+    /// Number of headwords in the index
+    pub fn len(&self) -> usize {
+        self.count as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    fn read_at(&self, offset: u64, len: usize) -> Result<&[u8]> {
+        let start = offset as usize;
+        self.map
+            .get(start..start + len)
+            .context("Index entry points past the end of the file")
+    }
+
+    /// Reads row `idx` of the offset table as (key offset, key length, value offset, value length)
+    fn row(&self, idx: u32) -> Result<(u64, usize, u64, usize)> {
+        let row = self.read_at(self.rows_start + ROW_LEN * idx as u64, ROW_LEN as usize)?;
+        Ok((
+            u32::from_le_bytes(row[0..4].try_into()?) as u64,
+            u32::from_le_bytes(row[4..8].try_into()?) as usize,
+            u64::from_le_bytes(row[8..16].try_into()?),
+            u32::from_le_bytes(row[16..20].try_into()?) as usize,
+        ))
+    }
+
+    /// The headword in row `idx`
+    pub fn key(&self, idx: u32) -> Result<String> {
+        let (key_off, key_len, _, _) = self.row(idx)?;
+        Ok(std::str::from_utf8(self.read_at(self.keys_start + key_off, key_len)?)?.to_string())
+    }
+
+    /// Looks up a single headword
+    pub fn lookup(&self, word: &str) -> Result<Option<Definition>> {
+        let (mut low, mut high) = (0, self.count);
+
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let (key_off, key_len, value_off, value_len) = self.row(mid)?;
+            let key = self.read_at(self.keys_start + key_off, key_len)?;
+
+            match key.cmp(word.as_bytes()) {
+                Ordering::Less => low = mid + 1,
+                Ordering::Greater => high = mid,
+                Ordering::Equal => {
+                    let value = self.read_at(self.values_start + value_off, value_len)?;
+                    return Ok(Some(rmp_serde::from_slice(value)?));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Compiles the dictionary's shards into an index at `path`, replacing any
+    /// existing one. Returns the number of entries written.
+    pub fn build(dict: &Dictionary, path: &Path) -> Result<usize> {
+        let mut entries = Vec::new();
+        for shard in dict.shards()? {
+            entries.extend(dict.load_shard(&shard)?);
+        }
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        entries.dedup_by(|a, b| a.0 == b.0);
+
+        let mut rows = Vec::with_capacity(entries.len());
+        let mut keys = Vec::new();
+        let mut values = Vec::new();
+        for (word, def) in &entries {
+            let value = rmp_serde::to_vec_named(def)?;
+            rows.push((
+                keys.len() as u32,
+                word.len() as u32,
+                values.len() as u64,
+                value.len() as u32,
+            ));
+            keys.extend_from_slice(word.as_bytes());
+            values.extend_from_slice(&value);
+        }
+
+        let partial = path.with_extension("partial");
+        let mut out = BufWriter::new(File::create(&partial)?);
+        out.write_all(MAGIC)?;
+        out.write_all(&VERSION.to_le_bytes())?;
+        out.write_all(&(rows.len() as u32).to_le_bytes())?;
+        out.write_all(&(keys.len() as u64).to_le_bytes())?;
+        write_paths(&mut out, &existing_roots(dict.roots()))?;
+        for (key_off, key_len, value_off, value_len) in rows {
+            out.write_all(&key_off.to_le_bytes())?;
+            out.write_all(&key_len.to_le_bytes())?;
+            out.write_all(&value_off.to_le_bytes())?;
+            out.write_all(&value_len.to_le_bytes())?;
+        }
+        out.write_all(&keys)?;
+        out.write_all(&values)?;
+        out.into_inner()?.sync_all()?;
+
+        fs::rename(&partial, path)?;
+        Ok(entries.len())
+    }
+}
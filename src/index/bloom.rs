@@ -0,0 +1,149 @@
+use super::{existing_roots, read_paths, write_paths};
+use crate::Dictionary;
+use anyhow::{Context, Result};
+use memmap2::Mmap;
+use std::{
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+/// Name of the headword filter inside the index directory
+pub const FILE_NAME: &str = "headwords.bloom";
+
+const MAGIC: &[u8; 8] = b"DIBBLEBF";
+const VERSION: u32 = 1;
+/// magic, version, hash count, bit count
+const HEADER_LEN: usize = 8 + 4 + 4 + 8;
+
+/// Bits per headword and hashes per lookup for about one false positive in
+/// a hundred
+const BITS_PER_WORD: u64 = 10;
+const HASHES: u32 = 7;
+
+/// A Bloom filter over every headword, so a word the dictionary doesn't
+/// have is turned away without opening a shard. It can wrongly let a missing
+/// word through, which then just misses in the shard as usual, but never
+/// turns away a word it was built with.
+///
+/// It only knows the headwords of the roots it was built from, as they were
+/// then, so it's ignored for any other set of roots, and anything writing
+/// shards removes it with [`invalidate`](super::invalidate).
+///
+/// Layout (little endian): a header, the full paths of the roots that
+/// existed one per line, then the bits.
+#[derive(Debug)]
+pub struct BloomFilter {
+    map: Mmap,
+    hashes: u32,
+    bits: u64,
+    roots: Vec<PathBuf>,
+    bits_start: usize,
+}
+
+impl BloomFilter {
+    pub fn open(path: &Path) -> Result<Self> {
+        let file =
+            File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+        // SAFETY: the filter is only ever replaced by renaming a new file over
+        // it or removed, never modified in place, so the mapped pages can't
+        // change under us
+        let map = unsafe { Mmap::map(&file)? };
+
+        let header = map
+            .get(..HEADER_LEN)
+            .with_context(|| format!("{} is truncated", path.display()))?;
+        if &header[..8] != MAGIC {
+            anyhow::bail!("{} is not a dibble headword filter", path.display());
+        }
+        let version = u32::from_le_bytes(header[8..12].try_into()?);
+        if version != VERSION {
+            anyhow::bail!(
+                "{} has filter version {}, expected {} (rebuild it with `dibble index build`)",
+                path.display(),
+                version,
+                VERSION
+            );
+        }
+        let hashes = u32::from_le_bytes(header[12..16].try_into()?);
+        let bits = u64::from_le_bytes(header[16..24].try_into()?);
+        let (roots, bits_start) = read_paths(&map, HEADER_LEN)
+            .with_context(|| format!("{} is truncated", path.display()))?;
+        if bits == 0 || (map.len() as u64) < bits_start as u64 + bits.div_ceil(8) {
+            anyhow::bail!("{} is truncated", path.display());
+        }
+
+        Ok(Self {
+            map,
+            hashes,
+            bits,
+            roots,
+            bits_start,
+        })
+    }
+
+    /// Whether the filter was built from exactly the roots of `roots` that
+    /// exist, in that order
+    pub fn covers(&self, roots: &[PathBuf]) -> bool {
+        existing_roots(roots) == self.roots
+    }
+
+    /// Whether `word` may be a headword. `false` means it certainly isn't.
+    pub fn contains(&self, word: &str) -> bool {
+        positions(word, self.hashes, self.bits).all(|bit| {
+            let byte = self.map[self.bits_start + (bit / 8) as usize];
+            byte & (1 << (bit % 8)) != 0
+        })
+    }
+
+    /// Builds a filter over every headword of `dict` at `path`, replacing any
+    /// existing one. Returns the number of headwords added.
+    pub fn build(dict: &Dictionary, path: &Path) -> Result<usize> {
+        let words = dict.iter_words()?.collect::<Result<Vec<_>>>()?;
+        let bits = (words.len() as u64 * BITS_PER_WORD).max(64);
+        let mut filter = vec![0u8; bits.div_ceil(8) as usize];
+        for word in &words {
+            for bit in positions(word, HASHES, bits) {
+                filter[(bit / 8) as usize] |= 1 << (bit % 8);
+            }
+        }
+
+        let roots = existing_roots(dict.roots());
+
+        let partial = path.with_extension("partial");
+        let mut out = BufWriter::new(File::create(&partial)?);
+        out.write_all(MAGIC)?;
+        out.write_all(&VERSION.to_le_bytes())?;
+        out.write_all(&HASHES.to_le_bytes())?;
+        out.write_all(&bits.to_le_bytes())?;
+        write_paths(&mut out, &roots)?;
+        out.write_all(&filter)?;
+        out.into_inner()?.sync_all()?;
+
+        fs::rename(&partial, path)?;
+        Ok(words.len())
+    }
+}
+
+/// The bits `word` sets, by double hashing: the `i`th is `h1 + i * h2`
+fn positions(word: &str, hashes: u32, bits: u64) -> impl Iterator<Item = u64> {
+    let h1 = fnv1a(word.as_bytes());
+    // a step of zero would set the same bit every time
+    let h2 = splitmix64(h1) | 1;
+    (0..u64::from(hashes)).map(move |i| h1.wrapping_add(i.wrapping_mul(h2)) % bits)
+}
+
+/// 64-bit FNV-1a, which is the same on every build and platform
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Scrambles `x` into an unrelated second hash
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
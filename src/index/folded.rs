@@ -0,0 +1,78 @@
+use super::existing_roots;
+use crate::{Dictionary, dictionary::fold};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Name of the folded spelling index inside the index directory
+pub const FILE_NAME: &str = "folded.msgpack";
+
+/// The headwords with diacritics, grouped by their [folded](fold) spelling,
+/// so a lookup of `facade` that misses can find `façade`. Headwords that fold
+/// to their own lowercase are left out, which keeps the index small.
+///
+/// It only knows the headwords of the roots it was built from, so it's
+/// ignored for any other set of roots and removed by
+/// [`invalidate`](super::invalidate).
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct FoldedIndex {
+    groups: BTreeMap<String, Vec<String>>,
+    /// The full paths of the roots that existed, empty in an index from
+    /// before they were recorded
+    #[serde(default)]
+    roots: Vec<PathBuf>,
+}
+
+impl FoldedIndex {
+    pub fn open(path: &Path) -> Result<Self> {
+        let data = fs::read(path).with_context(|| format!("Failed to open {}", path.display()))?;
+        rmp_serde::from_slice(&data)
+            .with_context(|| format!("{} is not a dibble folded spelling index", path.display()))
+    }
+
+    /// Whether the index was built from exactly the roots of `roots` that
+    /// exist, in that order
+    pub fn covers(&self, roots: &[PathBuf]) -> bool {
+        existing_roots(roots) == self.roots
+    }
+
+    /// Groups every headword in the dictionary that has diacritics
+    pub fn from_dict(dict: &Dictionary) -> Result<Self> {
+        let mut index = Self {
+            roots: existing_roots(dict.roots()),
+            ..Self::default()
+        };
+        for word in dict.iter_words()? {
+            let word = word?;
+            let folded = fold(&word);
+            if folded != word.to_lowercase() {
+                index.groups.entry(folded).or_default().push(word);
+            }
+        }
+        for words in index.groups.values_mut() {
+            words.sort();
+            words.dedup();
+        }
+        Ok(index)
+    }
+
+    /// Writes the dictionary's folded spelling index to `path`, replacing
+    /// any existing one. Returns the number of headwords indexed.
+    pub fn build(dict: &Dictionary, path: &Path) -> Result<usize> {
+        let index = Self::from_dict(dict)?;
+        let partial = path.with_extension("partial");
+        fs::write(&partial, rmp_serde::to_vec(&index)?)?;
+        fs::rename(&partial, path)?;
+        Ok(index.groups.values().map(Vec::len).sum())
+    }
+
+    /// The headwords with diacritics that `word` is spelled like once they're
+    /// folded away, sorted
+    pub fn spellings(&self, word: &str) -> &[String] {
+        self.groups.get(&fold(word)).map_or(&[], Vec::as_slice)
+    }
+}
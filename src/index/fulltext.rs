@@ -0,0 +1,277 @@
+use super::{existing_roots, read_paths, write_paths};
+use crate::{
+    Dictionary,
+    search::{matching_sense, rank, tokens},
+};
+use anyhow::{Context, Result};
+use memmap2::Mmap;
+use std::{
+    cmp::Ordering,
+    collections::BTreeMap,
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+/// Name of the full-text index inside the index directory
+pub const FILE_NAME: &str = "fulltext.bin";
+
+const MAGIC: &[u8; 8] = b"DIBBLEFT";
+const VERSION: u32 = 2;
+/// magic, version, term count, headword count, length of the term blob,
+/// length of the headword blob
+const HEADER_LEN: u64 = 8 + 4 + 4 + 4 + 8 + 8;
+/// term offset, term length, postings offset, postings count
+const TERM_ROW_LEN: u64 = 4 + 4 + 8 + 4;
+/// headword offset, headword length
+const WORD_ROW_LEN: u64 = 4 + 4;
+
+/// An inverted index from every word used in a sense to the headwords using
+/// it, so reverse lookups only read the entries that can match. Memory mapped
+/// like the [`BinaryIndex`](super::binary::BinaryIndex), and like it only
+/// used for the roots it was built from and removed by
+/// [`invalidate`](super::invalidate).
+///
+/// Layout (little endian): a header, the full paths of the roots that
+/// existed one per line, one row per term sorted by term, one row
+/// per headword sorted by headword, all terms back to back, all headwords back
+/// to back, then the postings: for each term, the ascending numbers of the
+/// headword rows it appears in, as u32s.
+#[derive(Debug)]
+pub struct FulltextIndex {
+    map: Mmap,
+    roots: Vec<PathBuf>,
+    terms: u32,
+    terms_start: u64,
+    words: u32,
+    words_start: u64,
+    term_blob_start: u64,
+    word_blob_start: u64,
+    postings_start: u64,
+}
+
+impl FulltextIndex {
+    pub fn open(path: &Path) -> Result<Self> {
+        let file =
+            File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+        // SAFETY: the index is only ever replaced by renaming a new file over
+        // it, never modified in place, so the mapped pages can't change under us
+        let map = unsafe { Mmap::map(&file)? };
+
+        let header = map
+            .get(..HEADER_LEN as usize)
+            .with_context(|| format!("{} is truncated", path.display()))?;
+        if &header[..8] != MAGIC {
+            anyhow::bail!("{} is not a dibble full-text index", path.display());
+        }
+        let version = u32::from_le_bytes(header[8..12].try_into()?);
+        if version != VERSION {
+            anyhow::bail!(
+                "{} has index version {}, expected {} (rebuild it with `dibble index build --fulltext`)",
+                path.display(),
+                version,
+                VERSION
+            );
+        }
+        let terms = u32::from_le_bytes(header[12..16].try_into()?);
+        let words = u32::from_le_bytes(header[16..20].try_into()?);
+        let term_blob_len = u64::from_le_bytes(header[20..28].try_into()?);
+        let word_blob_len = u64::from_le_bytes(header[28..36].try_into()?);
+
+        let (roots, terms_start) = read_paths(&map, HEADER_LEN as usize)
+            .with_context(|| format!("{} is truncated", path.display()))?;
+        let terms_start = terms_start as u64;
+        let words_start = terms_start + TERM_ROW_LEN * terms as u64;
+        let term_blob_start = words_start + WORD_ROW_LEN * words as u64;
+        let word_blob_start = term_blob_start + term_blob_len;
+        let postings_start = word_blob_start + word_blob_len;
+        if (map.len() as u64) < postings_start {
+            anyhow::bail!("{} is truncated", path.display());
+        }
+
+        Ok(Self {
+            map,
+            roots,
+            terms,
+            terms_start,
+            words,
+            words_start,
+            term_blob_start,
+            word_blob_start,
+            postings_start,
+        })
+    }
+
+    /// Whether the index was built from exactly the roots of `roots` that
+    /// exist, in that order
+    pub fn covers(&self, roots: &[PathBuf]) -> bool {
+        existing_roots(roots) == self.roots
+    }
+
+    fn read_at(&self, offset: u64, len: usize) -> Result<&[u8]> {
+        let start = offset as usize;
+        self.map
+            .get(start..start + len)
+            .context("Index entry points past the end of the file")
+    }
+
+    fn u32_at(&self, offset: u64) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.read_at(offset, 4)?.try_into()?))
+    }
+
+    /// The numbers of the headwords whose senses use `term`, ascending
+    fn postings(&self, term: &str) -> Result<Vec<u32>> {
+        let (mut low, mut high) = (0, self.terms);
+
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let row = self.terms_start + TERM_ROW_LEN * mid as u64;
+            let term_off = self.u32_at(row)? as u64;
+            let term_len = self.u32_at(row + 4)? as usize;
+            let key = self.read_at(self.term_blob_start + term_off, term_len)?;
+
+            match key.cmp(term.as_bytes()) {
+                Ordering::Less => low = mid + 1,
+                Ordering::Greater => high = mid,
+                Ordering::Equal => {
+                    let offset = u64::from_le_bytes(self.read_at(row + 8, 8)?.try_into()?);
+                    let count = self.u32_at(row + 16)? as usize;
+                    let bytes = self.read_at(self.postings_start + offset * 4, count * 4)?;
+                    return Ok(bytes
+                        .chunks_exact(4)
+                        .map(|id| u32::from_le_bytes([id[0], id[1], id[2], id[3]]))
+                        .collect());
+                }
+            }
+        }
+
+        Ok(Vec::new())
+    }
+
+    /// The headword in row `idx`
+    fn word(&self, idx: u32) -> Result<String> {
+        if idx >= self.words {
+            anyhow::bail!("Index entry points past the end of the file");
+        }
+        let row = self.words_start + WORD_ROW_LEN * idx as u64;
+        let off = self.u32_at(row)? as u64;
+        let len = self.u32_at(row + 4)? as usize;
+        Ok(std::str::from_utf8(self.read_at(self.word_blob_start + off, len)?)?.to_string())
+    }
+
+    /// The headwords whose senses use every word of `phrase`, in any order
+    pub fn candidates(&self, phrase: &str) -> Result<Vec<String>> {
+        let mut lists = Vec::new();
+        for term in tokens(phrase) {
+            lists.push(self.postings(&term)?);
+        }
+        // intersect starting from the rarest word, which keeps the work small
+        lists.sort_by_key(Vec::len);
+        let mut lists = lists.into_iter();
+        let Some(mut ids) = lists.next() else {
+            return Ok(Vec::new());
+        };
+        for list in lists {
+            ids.retain(|id| list.binary_search(id).is_ok());
+        }
+        ids.into_iter().map(|id| self.word(id)).collect()
+    }
+
+    /// Like [`crate::search::reverse`], but only reads the entries the index
+    /// says use every word of `phrase`
+    pub fn reverse(&self, dict: &Dictionary, phrase: &str) -> Result<Vec<(String, String)>> {
+        let words = self.candidates(phrase)?;
+        let phrase = tokens(phrase);
+        let mut found: Vec<(String, String)> = dict
+            .lookup_many(&words)?
+            .into_iter()
+            .flatten()
+            .filter_map(|def| {
+                let sense = matching_sense(&def, &phrase)?;
+                Some((def.word, sense))
+            })
+            .collect();
+        rank(&mut found);
+        Ok(found)
+    }
+
+    /// Indexes the senses of every entry into `path`, replacing any existing
+    /// index. Returns the number of headwords indexed.
+    pub fn build(dict: &Dictionary, path: &Path) -> Result<usize> {
+        let mut entries = Vec::new();
+        for shard in dict.shards()? {
+            entries.extend(dict.load_shard(&shard)?);
+        }
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        entries.dedup_by(|a, b| a.0 == b.0);
+
+        let mut postings: BTreeMap<String, Vec<u32>> = BTreeMap::new();
+        for (id, (_, def)) in entries.iter().enumerate() {
+            let senses = def
+                .etymologies
+                .iter()
+                .flat_map(|etym| &etym.parts_of_speech)
+                .flat_map(|pos| &pos.senses);
+            for sense in senses {
+                for term in tokens(&sense.sense) {
+                    let ids = postings.entry(term).or_default();
+                    // entries go in order, so a repeat can only be the last one
+                    if ids.last() != Some(&(id as u32)) {
+                        ids.push(id as u32);
+                    }
+                }
+            }
+        }
+
+        let mut term_blob = Vec::new();
+        let mut term_rows = Vec::with_capacity(postings.len());
+        let mut posting_count: u64 = 0;
+        for (term, ids) in &postings {
+            term_rows.push((
+                term_blob.len() as u32,
+                term.len() as u32,
+                posting_count,
+                ids.len() as u32,
+            ));
+            term_blob.extend_from_slice(term.as_bytes());
+            posting_count += ids.len() as u64;
+        }
+        let mut word_blob = Vec::new();
+        let mut word_rows = Vec::with_capacity(entries.len());
+        for (word, _) in &entries {
+            word_rows.push((word_blob.len() as u32, word.len() as u32));
+            word_blob.extend_from_slice(word.as_bytes());
+        }
+
+        let partial = path.with_extension("partial");
+        let mut out = BufWriter::new(File::create(&partial)?);
+        out.write_all(MAGIC)?;
+        out.write_all(&VERSION.to_le_bytes())?;
+        out.write_all(&(term_rows.len() as u32).to_le_bytes())?;
+        out.write_all(&(word_rows.len() as u32).to_le_bytes())?;
+        out.write_all(&(term_blob.len() as u64).to_le_bytes())?;
+        out.write_all(&(word_blob.len() as u64).to_le_bytes())?;
+        write_paths(&mut out, &existing_roots(dict.roots()))?;
+        for (term_off, term_len, postings_off, postings_len) in term_rows {
+            out.write_all(&term_off.to_le_bytes())?;
+            out.write_all(&term_len.to_le_bytes())?;
+            out.write_all(&postings_off.to_le_bytes())?;
+            out.write_all(&postings_len.to_le_bytes())?;
+        }
+        for (word_off, word_len) in word_rows {
+            out.write_all(&word_off.to_le_bytes())?;
+            out.write_all(&word_len.to_le_bytes())?;
+        }
+        out.write_all(&term_blob)?;
+        out.write_all(&word_blob)?;
+        for ids in postings.values() {
+            for id in ids {
+                out.write_all(&id.to_le_bytes())?;
+            }
+        }
+        out.into_inner()?.sync_all()?;
+
+        fs::rename(&partial, path)?;
+        Ok(entries.len())
+    }
+}
@@ -0,0 +1,256 @@
+use super::{existing_roots, read_paths, write_paths};
+use crate::Dictionary;
+use anyhow::{Context, Result};
+use fst::{Automaton, IntoStreamer, Map, MapBuilder, automaton::Levenshtein};
+use memmap2::Mmap;
+use regex_automata::{
+    Anchored,
+    dfa::{Automaton as _, dense},
+    util::{primitives::StateID, start},
+};
+use std::{
+    fmt,
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+/// Name of the headword index inside the index directory
+pub const FILE_NAME: &str = "headwords.fst";
+
+const MAGIC: &[u8; 8] = b"DIBBLEFS";
+const VERSION: u32 = 1;
+/// magic, version
+const HEADER_LEN: usize = 8 + 4;
+
+/// Most memory a regex query's DFA may take before the query is refused
+const DFA_SIZE_LIMIT: usize = 16 << 20;
+
+/// Every headword in a finite state transducer, mapped to the shard it's in
+/// and its row in the binary index built alongside it, so prefix, range,
+/// fuzzy and regex queries walk the transducer rather than any JSON.
+///
+/// Like the [headword filter](super::bloom::BloomFilter), it's ignored for
+/// roots other than the ones it was built from and removed when shards are
+/// written, since queries would miss the new headwords.
+///
+/// Layout (little endian): a header, the shard names, the full paths of the
+/// roots that existed, then the transducer. Each value is the shard's
+/// number in the top 32 bits and the row below.
+pub struct HeadwordIndex {
+    map: Map<Tail>,
+    shards: Vec<PathBuf>,
+    roots: Vec<PathBuf>,
+}
+
+/// The transducer at the end of the memory mapped file
+struct Tail {
+    map: Mmap,
+    start: usize,
+}
+
+impl AsRef<[u8]> for Tail {
+    fn as_ref(&self) -> &[u8] {
+        &self.map[self.start..]
+    }
+}
+
+impl fmt::Debug for HeadwordIndex {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("HeadwordIndex")
+            .field("len", &self.len())
+            .field("shards", &self.shards.len())
+            .field("roots", &self.roots)
+            .finish()
+    }
+}
+
+impl HeadwordIndex {
+    pub fn open(path: &Path) -> Result<Self> {
+        let file =
+            File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+        // SAFETY: the index is only ever replaced by renaming a new file over
+        // it or removed, never modified in place, so the mapped pages can't
+        // change under us
+        let map = unsafe { Mmap::map(&file)? };
+
+        let header = map
+            .get(..HEADER_LEN)
+            .with_context(|| format!("{} is truncated", path.display()))?;
+        if &header[..8] != MAGIC {
+            anyhow::bail!("{} is not a dibble headword index", path.display());
+        }
+        let version = u32::from_le_bytes(header[8..12].try_into()?);
+        if version != VERSION {
+            anyhow::bail!(
+                "{} has index version {}, expected {} (rebuild it with `dibble index build`)",
+                path.display(),
+                version,
+                VERSION
+            );
+        }
+        let (shards, roots_start) = read_paths(&map, HEADER_LEN)
+            .with_context(|| format!("{} is truncated", path.display()))?;
+        let (roots, fst_start) = read_paths(&map, roots_start)
+            .with_context(|| format!("{} is truncated", path.display()))?;
+        let map = Map::new(Tail {
+            map,
+            start: fst_start,
+        })
+        .with_context(|| format!("{} is not a dibble headword index", path.display()))?;
+
+        Ok(Self { map, shards, roots })
+    }
+
+    /// Whether the index was built from exactly the roots of `roots` that
+    /// exist, in that order
+    pub fn covers(&self, roots: &[PathBuf]) -> bool {
+        existing_roots(roots) == self.roots
+    }
+
+    /// Number of headwords in the index
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// The shard `word` is in and its row in the binary index, if it's a
+    /// headword
+    pub fn locate(&self, word: &str) -> Option<(&Path, u32)> {
+        let value = self.map.get(word)?;
+        let shard = self.shards.get((value >> 32) as usize)?;
+        Some((shard, value as u32))
+    }
+
+    /// Every headword beginning with `stem`, ignoring case, sorted
+    pub fn prefix(&self, stem: &str) -> Result<Vec<String>> {
+        self.regex(&format!("^{}", regex::escape(stem)), true)
+    }
+
+    /// Every headword from `from` up to but not including `to`, or to the
+    /// end without one, in byte order
+    pub fn range(&self, from: &str, to: Option<&str>) -> Result<Vec<String>> {
+        let mut range = self.map.range().ge(from);
+        if let Some(to) = to {
+            range = range.lt(to);
+        }
+        Ok(range.into_stream().into_str_keys()?)
+    }
+
+    /// Every headword within `distance` edits of `word`, sorted. The query
+    /// is limited in size, so a long word with a large distance fails.
+    pub fn fuzzy(&self, word: &str, distance: u32) -> Result<Vec<String>> {
+        let automaton = Levenshtein::new(word, distance)?;
+        Ok(self.map.search(automaton).into_stream().into_str_keys()?)
+    }
+
+    /// Every headword `pattern` matches somewhere in, sorted, as
+    /// [`regex::Regex::is_match`] would. Patterns too big to compile into a
+    /// DFA fail, so callers can fall back to scanning.
+    pub fn regex(&self, pattern: &str, ignore_case: bool) -> Result<Vec<String>> {
+        let dfa = dense::Builder::new()
+            .configure(
+                dense::Config::new()
+                    .dfa_size_limit(Some(DFA_SIZE_LIMIT))
+                    .determinize_size_limit(Some(DFA_SIZE_LIMIT)),
+            )
+            .syntax(regex_automata::util::syntax::Config::new().case_insensitive(ignore_case))
+            .build(pattern)?;
+        let automaton = RegexAutomaton::new(&dfa)?;
+        Ok(self.map.search(automaton).into_stream().into_str_keys()?)
+    }
+
+    /// Writes an index of every headword of `dict` to `path`, replacing any
+    /// existing one. Returns the number of headwords indexed.
+    pub fn build(dict: &Dictionary, path: &Path) -> Result<usize> {
+        let shards = dict.shards()?;
+        let mut words = Vec::new();
+        for (number, shard) in shards.iter().enumerate() {
+            for word in dict.shard_words(shard)? {
+                words.push((word, number as u64));
+            }
+        }
+        // sorted and deduplicated the way the binary index's rows are
+        words.sort();
+        words.dedup_by(|a, b| a.0 == b.0);
+
+        let mut builder = MapBuilder::memory();
+        for (row, (word, shard)) in words.iter().enumerate() {
+            builder.insert(word, shard << 32 | row as u64)?;
+        }
+        let fst = builder.into_inner()?;
+
+        let partial = path.with_extension("partial");
+        let mut out = BufWriter::new(File::create(&partial)?);
+        out.write_all(MAGIC)?;
+        out.write_all(&VERSION.to_le_bytes())?;
+        write_paths(&mut out, &shards)?;
+        write_paths(&mut out, &existing_roots(dict.roots()))?;
+        out.write_all(&fst)?;
+        out.into_inner()?.sync_all()?;
+
+        fs::rename(&partial, path)?;
+        Ok(words.len())
+    }
+}
+
+/// Walks a transducer with a DFA, matching a key if the regex matches
+/// anywhere in it. Once some prefix of a key has matched, every key
+/// extending it matches too.
+struct RegexAutomaton<'a> {
+    dfa: &'a dense::DFA<Vec<u32>>,
+    start: StateID,
+}
+
+impl<'a> RegexAutomaton<'a> {
+    fn new(dfa: &'a dense::DFA<Vec<u32>>) -> Result<Self> {
+        let start = dfa.start_state(&start::Config::new().anchored(Anchored::No))?;
+        Ok(Self { dfa, start })
+    }
+}
+
+/// Where the DFA is, and whether it has matched already. `None` once it
+/// can't match any more.
+type RegexState = Option<(StateID, bool)>;
+
+impl Automaton for RegexAutomaton<'_> {
+    type State = RegexState;
+
+    fn start(&self) -> RegexState {
+        Some((self.start, false))
+    }
+
+    fn is_match(&self, state: &RegexState) -> bool {
+        match *state {
+            Some((id, matched)) => matched || self.dfa.is_match_state(self.dfa.next_eoi_state(id)),
+            None => false,
+        }
+    }
+
+    fn can_match(&self, state: &RegexState) -> bool {
+        state.is_some()
+    }
+
+    fn will_always_match(&self, state: &RegexState) -> bool {
+        matches!(state, Some((_, true)))
+    }
+
+    fn accept(&self, state: &RegexState, byte: u8) -> RegexState {
+        let (id, matched) = (*state)?;
+        if matched {
+            return *state;
+        }
+        let next = self.dfa.next_state(id, byte);
+        if self.dfa.is_match_state(next) {
+            // a dense DFA reports a match one byte late, so it ended before `byte`
+            Some((next, true))
+        } else if self.dfa.is_dead_state(next) {
+            None
+        } else {
+            Some((next, false))
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use crate::{Dictionary, paths::project_dirs};
+use anyhow::{Context, Result};
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+pub mod anagram;
+pub mod binary;
+pub mod bloom;
+pub mod folded;
+pub mod fulltext;
+pub mod headwords;
+pub mod phonetic;
+
+/// Where `dibble index build` writes its files: the `index` folder in the user data dir
+pub fn default_dir() -> Result<PathBuf> {
+    let dirs = project_dirs().context("Could not determine the user data directory")?;
+    Ok(dirs.data_dir().join("index"))
+}
+
+/// Compiles the indexes and headword filter from the dictionary's shards into
+/// `dir`, along with the full-text index if `fulltext` is set, since it's by
+/// far the largest.
+/// Returns the number of headwords indexed.
+pub fn build(dict: &Dictionary, dir: &Path, fulltext: bool) -> Result<usize> {
+    fs::create_dir_all(dir)?;
+    anagram::AnagramIndex::build(dict, &dir.join(anagram::FILE_NAME))?;
+    bloom::BloomFilter::build(dict, &dir.join(bloom::FILE_NAME))?;
+    folded::FoldedIndex::build(dict, &dir.join(folded::FILE_NAME))?;
+    headwords::HeadwordIndex::build(dict, &dir.join(headwords::FILE_NAME))?;
+    phonetic::PhoneticIndex::build(dict, &dir.join(phonetic::FILE_NAME))?;
+    if fulltext {
+        fulltext::FulltextIndex::build(dict, &dir.join(fulltext::FILE_NAME))?;
+    }
+    binary::BinaryIndex::build(dict, &dir.join(binary::FILE_NAME))
+}
+
+/// Removes the files in the default index directory that have to know every
+/// headword, or hold whole entries, to give right answers, since they don't
+/// once shards have been written. Lookups and queries go to the shards until
+/// `dibble index build` runs again.
+pub fn invalidate() -> Result<()> {
+    invalidate_in(&default_dir()?)
+}
+
+/// Removes the files [`invalidate`] does from the index directory `dir`
+fn invalidate_in(dir: &Path) -> Result<()> {
+    for name in [
+        anagram::FILE_NAME,
+        binary::FILE_NAME,
+        bloom::FILE_NAME,
+        folded::FILE_NAME,
+        fulltext::FILE_NAME,
+        headwords::FILE_NAME,
+        phonetic::FILE_NAME,
+    ] {
+        let path = dir.join(name);
+        match fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                return Err(e).with_context(|| format!("Failed to remove {}", path.display()));
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// The full paths of the roots that exist, which is what an index that has
+/// to know every headword records it was built from, so `./dict` means the
+/// same folder whichever directory dibble runs in
+pub(crate) fn existing_roots(roots: &[PathBuf]) -> Vec<PathBuf> {
+    roots
+        .iter()
+        .filter_map(|root| fs::canonicalize(root).ok())
+        .collect()
+}
+
+/// Writes `paths` one per line, after their length in bytes
+fn write_paths(out: &mut impl Write, paths: &[PathBuf]) -> Result<()> {
+    let paths: String = paths
+        .iter()
+        .map(|path| format!("{}\n", path.display()))
+        .collect();
+    out.write_all(&(paths.len() as u32).to_le_bytes())?;
+    out.write_all(paths.as_bytes())?;
+    Ok(())
+}
+
+/// Reads the paths [`write_paths`] wrote at `start` in `data`, returning
+/// them and where they end
+fn read_paths(data: &[u8], start: usize) -> Result<(Vec<PathBuf>, usize)> {
+    let len = data.get(start..start + 4).context("No paths")?;
+    let end = start + 4 + u32::from_le_bytes(len.try_into()?) as usize;
+    let paths = std::str::from_utf8(data.get(start + 4..end).context("No paths")?)?
+        .lines()
+        .map(PathBuf::from)
+        .collect();
+    Ok((paths, end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::{ShardWriter, simple_definition};
+
+    /// A shard tree at `root` holding `words`
+    fn tree(root: &Path, words: &[&str]) {
+        let mut writer = ShardWriter::new();
+        for word in words {
+            writer.insert(simple_definition(word, "Noun", vec![format!("A {}", word)]));
+        }
+        writer.write(root).unwrap();
+    }
+
+    #[test]
+    fn indexes_cover_only_the_roots_they_were_built_from() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (a, b) = (tmp.path().join("a"), tmp.path().join("b"));
+        tree(&a, &["cat", "façade"]);
+        tree(&b, &["dog"]);
+        let missing = tmp.path().join("missing");
+        let dir = tmp.path().join("index");
+        build(
+            &Dictionary::with_roots(vec![a.clone(), missing.clone()]),
+            &dir,
+            true,
+        )
+        .unwrap();
+
+        let built = [a.clone(), missing];
+        for (name, covers) in coverage(&dir, &built) {
+            assert!(covers, "{} should cover its roots", name);
+        }
+        // a root that doesn't exist isn't recorded, so leaving it out changes nothing
+        for (name, covers) in coverage(&dir, &built[..1]) {
+            assert!(covers, "{} should ignore missing roots", name);
+        }
+        for (name, covers) in coverage(&dir, &[b, a]) {
+            assert!(!covers, "{} shouldn't cover other roots", name);
+        }
+    }
+
+    /// Whether each index in `dir` covers `roots`
+    fn coverage(dir: &Path, roots: &[PathBuf]) -> [(&'static str, bool); 7] {
+        let open = |name| dir.join(name);
+        [
+            (
+                "anagram",
+                anagram::AnagramIndex::open(&open(anagram::FILE_NAME))
+                    .unwrap()
+                    .covers(roots),
+            ),
+            (
+                "binary",
+                binary::BinaryIndex::open(&open(binary::FILE_NAME))
+                    .unwrap()
+                    .covers(roots),
+            ),
+            (
+                "bloom",
+                bloom::BloomFilter::open(&open(bloom::FILE_NAME))
+                    .unwrap()
+                    .covers(roots),
+            ),
+            (
+                "folded",
+                folded::FoldedIndex::open(&open(folded::FILE_NAME))
+                    .unwrap()
+                    .covers(roots),
+            ),
+            (
+                "fulltext",
+                fulltext::FulltextIndex::open(&open(fulltext::FILE_NAME))
+                    .unwrap()
+                    .covers(roots),
+            ),
+            (
+                "headwords",
+                headwords::HeadwordIndex::open(&open(headwords::FILE_NAME))
+                    .unwrap()
+                    .covers(roots),
+            ),
+            (
+                "phonetic",
+                phonetic::PhoneticIndex::open(&open(phonetic::FILE_NAME))
+                    .unwrap()
+                    .covers(roots),
+            ),
+        ]
+    }
+
+    #[test]
+    fn invalidate_removes_every_index_built() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().join("dict");
+        tree(&root, &["cat"]);
+        let dir = tmp.path().join("index");
+        build(&Dictionary::with_roots(vec![root]), &dir, true).unwrap();
+        assert!(fs::read_dir(&dir).unwrap().next().is_some());
+
+        invalidate_in(&dir).unwrap();
+        let left: Vec<_> = fs::read_dir(&dir).unwrap().collect();
+        assert!(left.is_empty(), "left behind: {:?}", left);
+        // nothing to remove is fine too
+        invalidate_in(&dir).unwrap();
+    }
+}
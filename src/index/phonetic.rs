@@ -0,0 +1,116 @@
+use super::existing_roots;
+use crate::Dictionary;
+use anyhow::{Context, Result};
+use rphonetic::DoubleMetaphone;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+use unicode_normalization::{UnicodeNormalization, char::is_combining_mark};
+
+/// Name of the sounds-like index inside the index directory
+pub const FILE_NAME: &str = "sounds.msgpack";
+
+/// Headwords grouped by their Double Metaphone codes, so a word spelled the
+/// way it sounds, like `nefew`, is a lookup or two away from `nephew`. A
+/// headword with an alternate pronunciation is in the group of each code.
+///
+/// Like the [`BloomFilter`](super::bloom::BloomFilter), it only knows the
+/// headwords of the roots it was built from, so it's ignored for any other
+/// set of roots and removed by [`invalidate`](super::invalidate).
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PhoneticIndex {
+    groups: BTreeMap<String, Vec<String>>,
+    /// The full paths of the roots that existed, empty in an index from
+    /// before they were recorded
+    #[serde(default)]
+    roots: Vec<PathBuf>,
+}
+
+/// The Double Metaphone codes of `word`, primary first, then the alternate
+/// if it's different: `Smith` gives `SM0` and `XMT`. Accents are dropped and
+/// anything but ASCII letters skipped first, so a word with none has no codes.
+pub fn codes(word: &str) -> Vec<String> {
+    let letters: String = word
+        .nfd()
+        .filter(|c| !is_combining_mark(*c) && c.is_ascii_alphabetic())
+        .collect();
+    if letters.is_empty() {
+        return Vec::new();
+    }
+
+    let result = DoubleMetaphone::default().double_metaphone(&letters);
+    let (primary, alternate) = (result.primary(), result.alternate());
+    let mut codes = vec![primary];
+    if !alternate.is_empty() && !codes.contains(&alternate) {
+        codes.push(alternate);
+    }
+    codes.retain(|code| !code.is_empty());
+    codes
+}
+
+impl PhoneticIndex {
+    pub fn open(path: &Path) -> Result<Self> {
+        let data = fs::read(path).with_context(|| format!("Failed to open {}", path.display()))?;
+        rmp_serde::from_slice(&data)
+            .with_context(|| format!("{} is not a dibble sounds-like index", path.display()))
+    }
+
+    /// Whether the index was built from exactly the roots of `roots` that
+    /// exist, in that order
+    pub fn covers(&self, roots: &[PathBuf]) -> bool {
+        existing_roots(roots) == self.roots
+    }
+
+    /// Groups every headword in the dictionary
+    pub fn from_dict(dict: &Dictionary) -> Result<Self> {
+        let mut index = Self {
+            roots: existing_roots(dict.roots()),
+            ..Self::default()
+        };
+        for word in dict.iter_words()? {
+            let word = word?;
+            for code in codes(&word) {
+                index.groups.entry(code).or_default().push(word.clone());
+            }
+        }
+        for words in index.groups.values_mut() {
+            words.sort();
+            words.dedup();
+        }
+        Ok(index)
+    }
+
+    /// Writes the dictionary's sounds-like index to `path`, replacing any
+    /// existing one. Returns the number of headwords indexed.
+    pub fn build(dict: &Dictionary, path: &Path) -> Result<usize> {
+        let index = Self::from_dict(dict)?;
+        let partial = path.with_extension("partial");
+        fs::write(&partial, rmp_serde::to_vec(&index)?)?;
+        fs::rename(&partial, path)?;
+        let mut words: Vec<&String> = index.groups.values().flatten().collect();
+        words.sort();
+        words.dedup();
+        Ok(words.len())
+    }
+
+    /// The headwords sharing a code with `word`, those spelled most like it
+    /// first, then alphabetical
+    pub fn sounds_like(&self, word: &str) -> Vec<String> {
+        let mut words: Vec<String> = codes(word)
+            .iter()
+            .filter_map(|code| self.groups.get(code))
+            .flatten()
+            .cloned()
+            .collect();
+        words.sort();
+        words.dedup();
+
+        let lower = word.to_lowercase();
+        words
+            .sort_by_cached_key(|candidate| strsim::levenshtein(&lower, &candidate.to_lowercase()));
+        words
+    }
+}
@@ -0,0 +1,355 @@
+use crate::{Dictionary, dictionary::DEFAULT_LANG, index, library, paths::project_dirs};
+use anyhow::{Context, Result};
+use flate2::read::GzDecoder;
+use minisign_verify::{PublicKey, Signature};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Where dictionary bundles are listed unless `--index` says otherwise
+pub const DEFAULT_INDEX: &str =
+    "https://raw.githubusercontent.com/moogoesmeow0/dibble/main/dicts.json";
+
+/// A downloadable dictionary, as listed in the bundle index
+#[derive(Debug, Clone, Deserialize)]
+pub struct Bundle {
+    /// Location of the `.tar.gz` holding the shard tree
+    pub url: String,
+    /// Hex encoded SHA-256 of the archive
+    pub sha256: String,
+    #[serde(default)]
+    pub description: String,
+    /// Language code of the entries, `en` if the index doesn't say
+    #[serde(default = "default_lang")]
+    pub lang: String,
+    /// Optional: A version to show when updating, e.g. `2024-06`
+    #[serde(default)]
+    pub version: Option<String>,
+    /// Optional: Location of a minisign signature of the archive, checked
+    /// against the keys in [`keys_dir`]
+    #[serde(default)]
+    pub signature: Option<String>,
+}
+
+fn default_lang() -> String {
+    DEFAULT_LANG.to_string()
+}
+
+/// The bundle index: dictionary name to bundle
+pub type BundleIndex = HashMap<String, Bundle>;
+
+/// Fetches and parses the bundle index
+pub fn fetch_index(url: &str) -> Result<BundleIndex> {
+    let body = ureq::get(url)
+        .call()
+        .with_context(|| format!("Failed to fetch dictionary index from {}", url))?
+        .body_mut()
+        .read_to_string()?;
+    Ok(serde_json::from_str(&body)?)
+}
+
+/// Where the public keys dibble trusts to sign bundles live: one minisign
+/// `.pub` file per key in `trusted-keys` in the user config dir
+pub fn keys_dir() -> Result<PathBuf> {
+    let dirs = project_dirs().context("Could not determine the user config directory")?;
+    Ok(dirs.config_dir().join("trusted-keys"))
+}
+
+/// Every key in [`keys_dir`], with the file it came from
+pub fn trusted_keys() -> Result<Vec<(PathBuf, PublicKey)>> {
+    let Ok(entries) = fs::read_dir(keys_dir()?) else {
+        return Ok(Vec::new());
+    };
+
+    let mut keys = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension().is_none_or(|ext| ext != "pub") {
+            continue;
+        }
+        let key = PublicKey::from_file(&path)
+            .with_context(|| format!("{} isn't a minisign public key", path.display()))?;
+        keys.push((path, key));
+    }
+    keys.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(keys)
+}
+
+/// Checks `data` against a minisign `signature` made by one of `keys`
+pub fn verify_signature(data: &[u8], signature: &str, keys: &[(PathBuf, PublicKey)]) -> Result<()> {
+    let signature = Signature::decode(signature).context("Invalid minisign signature")?;
+    if keys
+        .iter()
+        .any(|(_, key)| key.verify(data, &signature, false).is_ok())
+    {
+        return Ok(());
+    }
+    if keys.is_empty() {
+        anyhow::bail!(
+            "The bundle is signed, but no key is trusted. Put the publisher's minisign public key in {} to trust it",
+            keys_dir()?.display()
+        );
+    }
+    anyhow::bail!(
+        "The signature doesn't match any trusted key; the bundle may have been tampered with"
+    )
+}
+
+/// Verifies the signature of a downloaded bundle. An unsigned bundle is
+/// refused once any key is trusted, unless `allow_unsigned` says otherwise.
+fn check_signature(bundle: &Bundle, archive: &[u8], allow_unsigned: bool) -> Result<()> {
+    let keys = trusted_keys()?;
+    match &bundle.signature {
+        Some(url) => {
+            let signature = String::from_utf8(download(url)?)
+                .with_context(|| format!("{} isn't a minisign signature", url))?;
+            verify_signature(archive, &signature, &keys)
+        }
+        None if keys.is_empty() || allow_unsigned => Ok(()),
+        None => anyhow::bail!(
+            "{} isn't signed; pass --allow-unsigned to install it anyway",
+            bundle.url
+        ),
+    }
+}
+
+/// Name of the file in an installed dictionary's folder recording where it came from
+pub const SOURCE_FILE: &str = "source.json";
+
+/// Where an installed dictionary came from, so `dibble update` knows where
+/// to look for a newer one
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Source {
+    /// URL of the bundle index it was listed in
+    pub index: String,
+    /// Checksum of the bundle that was installed
+    pub sha256: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+}
+
+impl Source {
+    /// The source recorded in the dictionary installed at `dir`, or `None`
+    /// if it wasn't installed with `dibble install`
+    pub fn read(dir: &Path) -> Result<Option<Self>> {
+        let path = dir.join(SOURCE_FILE);
+        match fs::read_to_string(&path) {
+            Ok(contents) => Ok(Some(
+                serde_json::from_str(&contents)
+                    .with_context(|| format!("Invalid install record {}", path.display()))?,
+            )),
+            Err(_) => Ok(None),
+        }
+    }
+
+    fn write(&self, dir: &Path) -> Result<()> {
+        fs::write(dir.join(SOURCE_FILE), serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// How the headwords of an updated dictionary differ from the copy it replaced
+#[derive(Debug, Default)]
+pub struct Changes {
+    pub added: usize,
+    pub removed: usize,
+    pub changed: usize,
+    /// The versions before and after, when the index gives them
+    pub from_version: Option<String>,
+    pub to_version: Option<String>,
+}
+
+/// What [`update`] did
+#[derive(Debug)]
+pub enum Update {
+    /// The index still lists the bundle that's installed
+    UpToDate,
+    Updated(Changes),
+}
+
+fn find<'a>(index: &'a BundleIndex, name: &str) -> Result<&'a Bundle> {
+    index.get(name).with_context(|| {
+        let mut known: Vec<&str> = index.keys().map(String::as_str).collect();
+        known.sort();
+        format!(
+            "No dictionary named '{}'. Available: {}",
+            name,
+            known.join(", ")
+        )
+    })
+}
+
+/// Downloads the named dictionary, verifies it and unpacks it into its own
+/// folder under [`library::dir`] for its language, replacing whatever was installed there
+/// under that name. Returns the install path.
+pub fn install(name: &str, index_url: &str, allow_unsigned: bool) -> Result<PathBuf> {
+    let index = fetch_index(index_url)?;
+    let bundle = find(&index, name)?;
+
+    let archive = download(&bundle.url)?;
+    verify(&archive, &bundle.sha256)?;
+    check_signature(bundle, &archive, allow_unsigned)?;
+
+    let target = library::dir(&bundle.lang)?.join(name);
+    let staged = stage(&archive, &target)?;
+    source(index_url, bundle).write(&staged)?;
+    swap(&staged, &target)?;
+
+    Ok(target)
+}
+
+/// Checks the index the dictionary installed at `target` came from for a
+/// different bundle under `name`, and if there is one, downloads it and
+/// swaps it in the way [`install`] does
+pub fn update(name: &str, target: &Path, allow_unsigned: bool) -> Result<Update> {
+    let Some(installed) = Source::read(target)? else {
+        anyhow::bail!(
+            "'{}' wasn't installed with `dibble install`, so there's nowhere to update it from",
+            name
+        );
+    };
+    let index = fetch_index(&installed.index)?;
+    let bundle = find(&index, name)?;
+    if bundle
+        .sha256
+        .trim()
+        .eq_ignore_ascii_case(installed.sha256.trim())
+    {
+        return Ok(Update::UpToDate);
+    }
+
+    let archive = download(&bundle.url)?;
+    verify(&archive, &bundle.sha256)?;
+    check_signature(bundle, &archive, allow_unsigned)?;
+
+    let staged = stage(&archive, target)?;
+    let mut changes = compare(target, &staged)?;
+    changes.from_version = installed.version;
+    changes.to_version = bundle.version.clone();
+    source(&installed.index, bundle).write(&staged)?;
+    swap(&staged, target)?;
+
+    Ok(Update::Updated(changes))
+}
+
+fn source(index_url: &str, bundle: &Bundle) -> Source {
+    Source {
+        index: index_url.to_string(),
+        sha256: bundle.sha256.trim().to_lowercase(),
+        version: bundle.version.clone(),
+    }
+}
+
+/// Counts the headwords added, removed and changed going from the shard
+/// tree at `old` to the one at `new`
+fn compare(old: &Path, new: &Path) -> Result<Changes> {
+    let old = Dictionary::with_roots(vec![old.to_path_buf()]);
+    let new = Dictionary::with_roots(vec![new.to_path_buf()]);
+    let old_shards: BTreeSet<PathBuf> = old.shards()?.into_iter().collect();
+    let new_shards: BTreeSet<PathBuf> = new.shards()?.into_iter().collect();
+
+    // entries are compared as plain JSON, so nothing an old version of the
+    // schema doesn't know about is missed
+    let entries = |dict: &Dictionary, shards: &BTreeSet<PathBuf>, shard: &PathBuf| {
+        if !shards.contains(shard) {
+            return Ok(HashMap::new());
+        }
+        let contents = dict.read_shard(shard)?;
+        serde_json::from_str::<HashMap<String, serde_json::Value>>(&contents)
+            .with_context(|| format!("Failed to parse shard {}", shard.display()))
+    };
+
+    let mut changes = Changes::default();
+    for shard in old_shards.union(&new_shards) {
+        let before = entries(&old, &old_shards, shard)?;
+        let after = entries(&new, &new_shards, shard)?;
+        for (word, entry) in &after {
+            match before.get(word) {
+                Some(old) if old != entry => changes.changed += 1,
+                Some(_) => {}
+                None => changes.added += 1,
+            }
+        }
+        changes.removed += before
+            .keys()
+            .filter(|word| !after.contains_key(*word))
+            .count();
+    }
+
+    Ok(changes)
+}
+
+fn download(url: &str) -> Result<Vec<u8>> {
+    Ok(ureq::get(url)
+        .call()
+        .with_context(|| format!("Failed to download {}", url))?
+        .body_mut()
+        .with_config()
+        .limit(u64::MAX)
+        .read_to_vec()?)
+}
+
+/// Checks `data` against a hex encoded SHA-256 checksum
+pub fn verify(data: &[u8], sha256: &str) -> Result<()> {
+    let actual: String = Sha256::digest(data)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+
+    if !actual.eq_ignore_ascii_case(sha256.trim()) {
+        anyhow::bail!(
+            "Checksum mismatch: expected {}, got {}",
+            sha256.trim(),
+            actual
+        );
+    }
+
+    Ok(())
+}
+
+/// Extracts a `.tar.gz` shard tree next to `target`, then swaps it into place
+/// so a failed extraction never leaves a half-written dictionary behind
+pub fn unpack(archive: &[u8], target: &Path) -> Result<()> {
+    let staged = stage(archive, target)?;
+    swap(&staged, target)
+}
+
+/// Extracts a `.tar.gz` shard tree into a staging folder next to `target`.
+/// Returns the root of the shard tree inside it.
+fn stage(archive: &[u8], target: &Path) -> Result<PathBuf> {
+    let staging = target.with_extension("partial");
+    let _ = fs::remove_dir_all(&staging);
+    fs::create_dir_all(&staging)?;
+
+    tar::Archive::new(GzDecoder::new(archive)).unpack(&staging)?;
+
+    // bundles may wrap the shard tree in a top level `dict/` directory
+    Ok(if staging.join("dict").is_dir() {
+        staging.join("dict")
+    } else {
+        staging
+    })
+}
+
+/// Moves the shard tree staged at `root` into place at `target`, replacing
+/// what's there, and clears away the staging folder
+fn swap(root: &Path, target: &Path) -> Result<()> {
+    let staging = target.with_extension("partial");
+    let old = target.with_extension("old");
+
+    if target.exists() {
+        let _ = fs::remove_dir_all(&old);
+        fs::rename(target, &old)?;
+    }
+    fs::rename(root, target)?;
+    index::invalidate()?;
+
+    let _ = fs::remove_dir_all(&old);
+    let _ = fs::remove_dir_all(&staging);
+
+    Ok(())
+}
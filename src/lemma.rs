@@ -0,0 +1,253 @@
+/// Inflected forms that suffix stripping can't undo, and their lemmas
+const IRREGULAR: &[(&str, &str)] = &[
+    ("am", "be"),
+    ("are", "be"),
+    ("is", "be"),
+    ("was", "be"),
+    ("were", "be"),
+    ("been", "be"),
+    ("being", "be"),
+    ("has", "have"),
+    ("had", "have"),
+    ("does", "do"),
+    ("did", "do"),
+    ("done", "do"),
+    ("went", "go"),
+    ("gone", "go"),
+    ("goes", "go"),
+    ("ran", "run"),
+    ("came", "come"),
+    ("saw", "see"),
+    ("seen", "see"),
+    ("took", "take"),
+    ("taken", "take"),
+    ("gave", "give"),
+    ("given", "give"),
+    ("made", "make"),
+    ("said", "say"),
+    ("got", "get"),
+    ("gotten", "get"),
+    ("knew", "know"),
+    ("known", "know"),
+    ("thought", "think"),
+    ("brought", "bring"),
+    ("bought", "buy"),
+    ("caught", "catch"),
+    ("taught", "teach"),
+    ("found", "find"),
+    ("told", "tell"),
+    ("felt", "feel"),
+    ("left", "leave"),
+    ("kept", "keep"),
+    ("slept", "sleep"),
+    ("meant", "mean"),
+    ("met", "meet"),
+    ("sat", "sit"),
+    ("stood", "stand"),
+    ("understood", "understand"),
+    ("wrote", "write"),
+    ("written", "write"),
+    ("spoke", "speak"),
+    ("spoken", "speak"),
+    ("broke", "break"),
+    ("broken", "break"),
+    ("chose", "choose"),
+    ("chosen", "choose"),
+    ("began", "begin"),
+    ("begun", "begin"),
+    ("drank", "drink"),
+    ("drunk", "drink"),
+    ("sang", "sing"),
+    ("sung", "sing"),
+    ("swam", "swim"),
+    ("swum", "swim"),
+    ("ate", "eat"),
+    ("eaten", "eat"),
+    ("fell", "fall"),
+    ("fallen", "fall"),
+    ("flew", "fly"),
+    ("flown", "fly"),
+    ("drove", "drive"),
+    ("driven", "drive"),
+    ("rode", "ride"),
+    ("ridden", "ride"),
+    ("rose", "rise"),
+    ("risen", "rise"),
+    ("grew", "grow"),
+    ("grown", "grow"),
+    ("threw", "throw"),
+    ("thrown", "throw"),
+    ("wore", "wear"),
+    ("worn", "wear"),
+    ("tore", "tear"),
+    ("torn", "tear"),
+    ("froze", "freeze"),
+    ("frozen", "freeze"),
+    ("stole", "steal"),
+    ("stolen", "steal"),
+    ("hid", "hide"),
+    ("hidden", "hide"),
+    ("bit", "bite"),
+    ("bitten", "bite"),
+    ("fought", "fight"),
+    ("sought", "seek"),
+    ("sold", "sell"),
+    ("held", "hold"),
+    ("led", "lead"),
+    ("fed", "feed"),
+    ("fled", "flee"),
+    ("paid", "pay"),
+    ("laid", "lay"),
+    ("lay", "lie"),
+    ("lain", "lie"),
+    ("built", "build"),
+    ("sent", "send"),
+    ("spent", "spend"),
+    ("lent", "lend"),
+    ("lost", "lose"),
+    ("won", "win"),
+    ("heard", "hear"),
+    ("struck", "strike"),
+    ("stuck", "stick"),
+    ("hung", "hang"),
+    ("dug", "dig"),
+    ("shook", "shake"),
+    ("shaken", "shake"),
+    ("forgot", "forget"),
+    ("forgotten", "forget"),
+    ("forgave", "forgive"),
+    ("forgiven", "forgive"),
+    ("better", "good"),
+    ("best", "good"),
+    ("worse", "bad"),
+    ("worst", "bad"),
+    ("more", "much"),
+    ("most", "much"),
+    ("less", "little"),
+    ("least", "little"),
+    ("farther", "far"),
+    ("further", "far"),
+    ("men", "man"),
+    ("women", "woman"),
+    ("children", "child"),
+    ("people", "person"),
+    ("mice", "mouse"),
+    ("lice", "louse"),
+    ("geese", "goose"),
+    ("feet", "foot"),
+    ("teeth", "tooth"),
+    ("oxen", "ox"),
+    ("dice", "die"),
+    ("knives", "knife"),
+    ("wives", "wife"),
+    ("lives", "life"),
+    ("leaves", "leaf"),
+    ("wolves", "wolf"),
+    ("halves", "half"),
+    ("selves", "self"),
+    ("shelves", "shelf"),
+    ("thieves", "thief"),
+    ("loaves", "loaf"),
+    ("calves", "calf"),
+    ("criteria", "criterion"),
+    ("phenomena", "phenomenon"),
+    ("data", "datum"),
+    ("cacti", "cactus"),
+    ("fungi", "fungus"),
+    ("nuclei", "nucleus"),
+    ("analyses", "analysis"),
+    ("crises", "crisis"),
+    ("theses", "thesis"),
+    ("indices", "index"),
+    ("matrices", "matrix"),
+    ("appendices", "appendix"),
+];
+
+/// Suffixes to strip and what to put back, tried in order. Each rule gives
+/// the ending of the inflected form and the ending of the lemma.
+const RULES: &[(&str, &str)] = &[
+    // possessives
+    ("'s", ""),
+    ("\u{2019}s", ""),
+    ("s'", "s"),
+    // plurals and third person
+    ("es", "e"),
+    ("ies", "y"),
+    ("ves", "f"),
+    ("ves", "fe"),
+    ("ses", "s"),
+    ("xes", "x"),
+    ("zes", "z"),
+    ("ches", "ch"),
+    ("shes", "sh"),
+    ("oes", "o"),
+    ("s", ""),
+    // past tense and participles
+    ("ed", "e"),
+    ("ied", "y"),
+    ("ed", ""),
+    ("ying", "ie"),
+    ("ing", ""),
+    ("ing", "e"),
+    // comparatives and superlatives
+    ("ier", "y"),
+    ("iest", "y"),
+    ("er", ""),
+    ("er", "e"),
+    ("est", ""),
+    ("est", "e"),
+    // adverbs
+    ("ily", "y"),
+    ("ly", ""),
+];
+
+/// Endings that can double the final consonant of the lemma, as in
+/// `running`, `stopped` or `bigger`
+const DOUBLING: &[&str] = &["ing", "ed", "er", "est"];
+
+/// The lemmas `word` might be an inflection of, most likely first: an
+/// irregular form from the table, then every regular suffix that could have
+/// been added. None of them is checked against a dictionary.
+pub fn candidates(word: &str) -> Vec<String> {
+    let lower = word.to_lowercase();
+    let mut lemmas = Vec::new();
+    let mut push = |lemma: String| {
+        if lemma.chars().count() >= 2 && lemma != lower && !lemmas.contains(&lemma) {
+            lemmas.push(lemma);
+        }
+    };
+
+    for (form, lemma) in IRREGULAR {
+        if *form == lower {
+            push(lemma.to_string());
+        }
+    }
+
+    for (suffix, replacement) in RULES {
+        let Some(stem) = lower.strip_suffix(suffix) else {
+            continue;
+        };
+        // `ss` is the end of the word, not a plural: `glass`, not `glas` + `s`
+        if *suffix == "s" && stem.ends_with('s') {
+            continue;
+        }
+
+        push(format!("{}{}", stem, replacement));
+        if DOUBLING.contains(suffix)
+            && let Some(undoubled) = undouble(stem)
+        {
+            push(undoubled);
+        }
+    }
+
+    lemmas
+}
+
+/// `runn` to `run`: the stem without a doubled final consonant, if it has one
+fn undouble(stem: &str) -> Option<String> {
+    let mut chars = stem.chars().rev();
+    let last = chars.next()?;
+    let before = chars.next()?;
+    (last == before && !"aeiouy".contains(last) && last.is_alphabetic())
+        .then(|| stem[..stem.len() - last.len_utf8()].to_string())
+}
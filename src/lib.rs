@@ -0,0 +1,62 @@
+pub mod archive;
+pub mod audio;
+pub mod cache;
+#[cfg(feature = "clipboard")]
+pub mod clipboard;
+pub mod config;
+#[cfg(unix)]
+pub mod daemon;
+pub mod date;
+pub mod dictionary;
+pub mod error;
+pub mod export;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod history;
+pub mod hunspell;
+pub mod import;
+pub mod index;
+#[cfg(feature = "net")]
+pub mod install;
+pub mod lemma;
+pub mod library;
+pub mod lsp;
+pub mod manifest;
+pub mod menu;
+pub mod merge;
+pub mod migrate;
+pub mod overlay;
+pub mod pager;
+pub mod paths;
+pub mod period;
+pub mod pos;
+#[cfg(feature = "pyo3")]
+pub mod python;
+pub mod quiz;
+pub mod remote;
+pub mod render;
+#[cfg(feature = "repl")]
+pub mod repl;
+pub mod rhyme;
+pub mod schema;
+pub mod scrabble;
+pub mod search;
+pub mod server;
+pub mod shard_cache;
+pub mod sharding;
+pub mod spellcheck;
+pub mod stars;
+pub mod stats;
+pub mod store;
+pub mod study;
+pub mod suggest;
+pub mod template;
+pub mod theme;
+#[cfg(feature = "tui")]
+pub mod tui;
+pub mod validate;
+pub mod web;
+pub mod wotd;
+
+pub use dictionary::Dictionary;
+pub use schema::{Definition, DictionaryFile, Etymology, PartOfSpeech, Pronunciation, Sense};
@@ -0,0 +1,143 @@
+use crate::{Definition, Dictionary, dictionary::check_lang, paths::project_dirs, store::Store};
+use anyhow::{Context, Result};
+use std::{fs, path::PathBuf};
+
+/// What the dictionary in the usual search roots is called next to the named ones
+pub const DEFAULT_NAME: &str = "default";
+
+/// Where named dictionaries in language `lang` live: one shard tree or
+/// `.dibble` archive per dictionary in `dicts/<lang>` in the user data dir
+pub fn dir(lang: &str) -> Result<PathBuf> {
+    check_lang(lang)?;
+    let dirs = project_dirs().context("Could not determine the user data directory")?;
+    Ok(dirs.data_dir().join("dicts").join(lang))
+}
+
+/// Lists the installed named dictionaries in `lang` and their paths, sorted by name
+pub fn installed(lang: &str) -> Result<Vec<(String, PathBuf)>> {
+    let Ok(entries) = fs::read_dir(dir(lang)?) else {
+        return Ok(Vec::new());
+    };
+
+    let mut found = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        let name = if path.is_dir() {
+            path.file_name()
+        } else if path.extension().is_some_and(|ext| ext == "dibble") {
+            path.file_stem()
+        } else {
+            None
+        };
+        if let Some(name) = name {
+            found.push((name.to_string_lossy().into_owned(), path));
+        }
+    }
+    found.sort();
+    Ok(found)
+}
+
+/// Several dictionaries searched side by side. A lookup asks every one of
+/// them and merges the answers into a single entry, each etymology tagged
+/// with the dictionary it came from.
+#[derive(Debug, Clone)]
+pub struct Library {
+    sources: Vec<(String, Dictionary)>,
+}
+
+impl Library {
+    /// `default` followed by every named dictionary in `lang`. Names in
+    /// `order` come first, in that order; the rest follow alphabetically.
+    pub fn open(default: &Dictionary, lang: &str, order: &[String]) -> Result<Self> {
+        let named = installed(lang)?;
+
+        let mut sources = Vec::new();
+        // with nothing else installed, keep the default around so a missing
+        // dictionary is still reported
+        if default.roots().iter().any(|root| root.exists()) || named.is_empty() {
+            sources.push((DEFAULT_NAME.to_string(), default.clone()));
+        }
+        for (name, path) in named {
+            sources.push((name, Dictionary::with_roots(vec![path])));
+        }
+        // a stable sort, so the unlisted ones keep the default first and the rest by name
+        sources.sort_by_key(|(name, _)| order.iter().position(|o| o == name).unwrap_or(usize::MAX));
+
+        Ok(Self { sources })
+    }
+
+    /// Keeps only the dictionary called `name`
+    pub fn only(mut self, name: &str) -> Result<Self> {
+        let Some(idx) = self.sources.iter().position(|(n, _)| n == name) else {
+            anyhow::bail!(
+                "No dictionary named '{}'. Installed: {}",
+                name,
+                self.names().collect::<Vec<_>>().join(", ")
+            );
+        };
+        self.sources = vec![self.sources.swap_remove(idx)];
+        Ok(self)
+    }
+
+    /// The names of the dictionaries searched, in priority order
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.sources.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// The dictionaries searched, in priority order
+    pub fn sources(&self) -> &[(String, Dictionary)] {
+        &self.sources
+    }
+
+    /// Whether lookups merge more than one dictionary
+    pub fn is_merged(&self) -> bool {
+        self.sources.len() > 1
+    }
+}
+
+impl Store for Library {
+    fn lookup(&self, word: &str) -> Result<Option<Definition>> {
+        Ok(self.lookup_many(&[word.to_string()])?.pop().flatten())
+    }
+
+    fn lookup_many(&self, words: &[String]) -> Result<Vec<Option<Definition>>> {
+        let mut merged: Vec<Option<Definition>> = vec![None; words.len()];
+
+        for (name, dict) in &self.sources {
+            for (slot, found) in merged.iter_mut().zip(dict.lookup_many(words)?) {
+                let Some(mut def) = found else {
+                    continue;
+                };
+                if self.is_merged() {
+                    for etymology in &mut def.etymologies {
+                        etymology.source = Some(name.clone());
+                    }
+                }
+                match slot {
+                    Some(existing) => {
+                        existing.etymologies.extend(def.etymologies);
+                        existing.frequency = existing.frequency.or(def.frequency);
+                        for pronunciation in def.pronunciations {
+                            if !existing.pronunciations.contains(&pronunciation) {
+                                existing.pronunciations.push(pronunciation);
+                            }
+                        }
+                    }
+                    None => *slot = Some(def),
+                }
+            }
+        }
+
+        Ok(merged)
+    }
+
+    fn prefix(&self, stem: &str) -> Result<Vec<String>> {
+        let mut words = Vec::new();
+        for (_, dict) in &self.sources {
+            words.extend(dict.prefix(stem)?);
+        }
+        words.sort();
+        words.dedup();
+        Ok(words)
+    }
+}
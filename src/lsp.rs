@@ -0,0 +1,239 @@
+use crate::{Definition, lemma, store::Store};
+use anyhow::{Context, Result};
+use serde_json::{Value, json};
+use std::{
+    collections::HashMap,
+    io::{BufRead, Write},
+};
+
+/// JSON-RPC error codes for a request the server doesn't implement, and for
+/// one that failed
+const METHOD_NOT_FOUND: i64 = -32601;
+const INTERNAL_ERROR: i64 = -32603;
+
+/// Serves a minimal Language Server on `input` and `output` until the client
+/// sends `exit`: open documents are kept in full, and `textDocument/hover`
+/// answers with the entry for the word under the cursor, or its lemma's.
+pub fn run(
+    store: &dyn Store,
+    examples: bool,
+    input: impl BufRead,
+    output: impl Write,
+) -> Result<()> {
+    let mut server = Server {
+        store,
+        examples,
+        documents: HashMap::new(),
+        input,
+        output,
+    };
+    server.serve()
+}
+
+struct Server<'a, R, W> {
+    store: &'a dyn Store,
+    examples: bool,
+    /// The text of every open document, by URI
+    documents: HashMap<String, String>,
+    input: R,
+    output: W,
+}
+
+impl<R: BufRead, W: Write> Server<'_, R, W> {
+    fn serve(&mut self) -> Result<()> {
+        while let Some(message) = self.read()? {
+            let method = message["method"].as_str().unwrap_or_default();
+            if method == "exit" {
+                break;
+            }
+            let result = self.handle(method, &message["params"]);
+
+            // notifications have no id and get no response
+            let Some(id) = message.get("id") else {
+                continue;
+            };
+            let response = match result {
+                Ok(Some(result)) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                Ok(None) => {
+                    let message = format!("Unsupported method {}", method);
+                    let error = json!({ "code": METHOD_NOT_FOUND, "message": message });
+                    json!({ "jsonrpc": "2.0", "id": id, "error": error })
+                }
+                Err(e) => {
+                    let error = json!({ "code": INTERNAL_ERROR, "message": e.to_string() });
+                    json!({ "jsonrpc": "2.0", "id": id, "error": error })
+                }
+            };
+            self.write(&response)?;
+        }
+        Ok(())
+    }
+
+    /// The result of `method`, or `None` if it isn't one this server knows.
+    /// Notifications it doesn't know are ignored, as the protocol asks.
+    fn handle(&mut self, method: &str, params: &Value) -> Result<Option<Value>> {
+        let uri = || {
+            params["textDocument"]["uri"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string()
+        };
+        Ok(Some(match method {
+            "initialize" => json!({
+                "capabilities": {
+                    // the whole text on every change
+                    "textDocumentSync": 1,
+                    "hoverProvider": true,
+                },
+                "serverInfo": { "name": "dibble" },
+            }),
+            "initialized" | "shutdown" => Value::Null,
+            "textDocument/didOpen" => {
+                let text = params["textDocument"]["text"].as_str().unwrap_or_default();
+                self.documents.insert(uri(), text.to_string());
+                Value::Null
+            }
+            "textDocument/didChange" => {
+                let changes = params["contentChanges"].as_array();
+                if let Some(text) = changes
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change["text"].as_str())
+                {
+                    self.documents.insert(uri(), text.to_string());
+                }
+                Value::Null
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(&uri());
+                Value::Null
+            }
+            "textDocument/hover" => self.hover(&uri(), &params["position"])?,
+            _ => return Ok(None),
+        }))
+    }
+
+    /// The hover for `position` in the document at `uri`: the entry as
+    /// Markdown, covering the word it's for
+    fn hover(&self, uri: &str, position: &Value) -> Result<Value> {
+        let (Some(line), Some(character)) =
+            (position["line"].as_u64(), position["character"].as_u64())
+        else {
+            anyhow::bail!("Invalid position {}", position);
+        };
+        let Some(text) = self
+            .documents
+            .get(uri)
+            .and_then(|text| text.lines().nth(line as usize))
+        else {
+            return Ok(Value::Null);
+        };
+        let Some((start, end)) = word_at(text, character as usize) else {
+            return Ok(Value::Null);
+        };
+        let word: String = text.chars().skip(start).take(end - start).collect();
+        let Some(def) = self.lookup(&word)? else {
+            return Ok(Value::Null);
+        };
+
+        Ok(json!({
+            "contents": { "kind": "markdown", "value": def.to_markdown(self.examples) },
+            "range": {
+                "start": { "line": line, "character": utf16_len(text, start) },
+                "end": { "line": line, "character": utf16_len(text, end) },
+            },
+        }))
+    }
+
+    /// The entry for `word`, or for the first of its lemmas that has one
+    fn lookup(&self, word: &str) -> Result<Option<Definition>> {
+        if let Some(def) = self.store.lookup(word)? {
+            return Ok(Some(def));
+        }
+        let candidates = lemma::candidates(word);
+        Ok(self
+            .store
+            .lookup_many(&candidates)?
+            .into_iter()
+            .flatten()
+            .next())
+    }
+
+    /// The next message, or `None` once the client has closed the stream
+    fn read(&mut self) -> Result<Option<Value>> {
+        let mut length = None;
+        loop {
+            let mut header = String::new();
+            if self.input.read_line(&mut header)? == 0 {
+                return Ok(None);
+            }
+            let header = header.trim_end();
+            if header.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = header.split_once(':')
+                && name.eq_ignore_ascii_case("Content-Length")
+            {
+                length = Some(value.trim().parse::<usize>()?);
+            }
+        }
+
+        let length = length.context("Message without a Content-Length header")?;
+        let mut body = vec![0; length];
+        self.input.read_exact(&mut body)?;
+        Ok(Some(
+            serde_json::from_slice(&body).context("Invalid message")?,
+        ))
+    }
+
+    fn write(&mut self, message: &Value) -> Result<()> {
+        let body = serde_json::to_string(message)?;
+        write!(
+            self.output,
+            "Content-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        )?;
+        self.output.flush()?;
+        Ok(())
+    }
+}
+
+/// The span of the word at `character` in `line`, in chars, where
+/// `character` counts UTF-16 code units as the protocol does. Apostrophes and
+/// hyphens count as part of a word when they're inside one.
+fn word_at(line: &str, character: usize) -> Option<(usize, usize)> {
+    let chars: Vec<char> = line.chars().collect();
+    let is_inner = |c: char| c == '\'' || c == '’' || c == '-';
+
+    let mut units = 0;
+    let mut cursor = chars.len();
+    for (idx, c) in chars.iter().enumerate() {
+        units += c.len_utf16();
+        if units > character {
+            cursor = idx;
+            break;
+        }
+    }
+
+    let mut start = cursor;
+    while start > 0 && (chars[start - 1].is_alphabetic() || is_inner(chars[start - 1])) {
+        start -= 1;
+    }
+    let mut end = cursor;
+    while end < chars.len() && (chars[end].is_alphabetic() || is_inner(chars[end])) {
+        end += 1;
+    }
+    while start < end && is_inner(chars[start]) {
+        start += 1;
+    }
+    while end > start && is_inner(chars[end - 1]) {
+        end -= 1;
+    }
+
+    (start < end).then_some((start, end))
+}
+
+/// The length in UTF-16 code units of the first `chars` chars of `line`
+fn utf16_len(line: &str, chars: usize) -> usize {
+    line.chars().take(chars).map(char::len_utf16).sum()
+}
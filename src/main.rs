@@ -1,176 +1,3165 @@
-use anyhow::Result;
-use clap::{ArgAction, Parser};
-use color_print::cprintln;
-use directories::ProjectDirs;
-use serde::{Deserialize, Serialize};
-use serde_json::from_str;
+use anyhow::{Context, Result};
+use clap::{
+    ArgAction, ArgMatches, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum,
+    parser::ValueSource,
+};
+use clap_complete::{
+    CompleteEnv,
+    engine::{ArgValueCompleter, CompletionCandidate},
+    env::EnvCompleter,
+};
+use color_print::cformat;
+#[cfg(feature = "sqlite")]
+use dibble::store::sqlite::SqliteStore;
+use dibble::{
+    Definition, Dictionary, Etymology, PartOfSpeech, Sense,
+    archive::Archive,
+    cache::RecentCache,
+    config::Config,
+    cprintln,
+    dictionary::{DEFAULT_LANG, DICT_PATH_VAR, RootSource, is_valid_word, search_roots},
+    error::DibbleError,
+    history::Visit,
+    hunspell::Hunspell,
+    import::{ShardWriter, csv::Columns},
+    index::{anagram::AnagramIndex, fulltext::FulltextIndex, phonetic::PhoneticIndex},
+    library::{DEFAULT_NAME, Library},
+    overlay::{Overlay, UserDictionary},
+    remote::dict::DictClient,
+    shard_cache::ShardCache,
+    sharding::ShardScheme,
+    stars::{Star, Stars},
+    store::Store,
+    study::Deck,
+    template::Template,
+    theme::{Theme, ThemeSpec},
+};
+use regex::Regex;
 use std::{
-    collections::hash_map::HashMap,
+    collections::HashSet,
     fs::File,
-    io::Read,
+    io::{BufRead, BufWriter, Write},
     path::{Path, PathBuf},
+    process::ExitCode,
+    sync::Arc,
 };
 
+/// The exit codes from [`DibbleError`], shown at the end of `--help`
+const EXIT_STATUS: &str = "\
+Exit status:
+  0  success
+  1  any other error, or with --exists, a word isn't defined
+  2  invalid input
+  3  a word wasn't found
+  4  no dictionary is installed
+  5  a dictionary or data file didn't parse
+  6  reading or writing a file failed";
+
 #[derive(Parser)]
 #[command(name = "dibble")]
 #[command(version = "0.1.4")]
 #[command(about = "Quick and local word definitions", long_about = None)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+#[command(after_help = EXIT_STATUS)]
 struct Cli {
-    /// The word to define
-    word: String,
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// The words to define (use `dibble -- <word>` for words that clash with a subcommand),
+    /// or `-` to read words from stdin
+    #[arg(add = ArgValueCompleter::new(complete_word))]
+    #[cfg_attr(feature = "clipboard", arg(required_unless_present_any = ["stdin", "clip"]))]
+    #[cfg_attr(not(feature = "clipboard"), arg(required_unless_present = "stdin"))]
+    words: Vec<String>,
+
+    /// Read newline-separated words from stdin and define each of them
+    #[arg(action = ArgAction::SetTrue, long)]
+    stdin: bool,
+
+    /// Define the word in the clipboard, or on Linux the highlighted text
+    #[cfg(feature = "clipboard")]
+    #[arg(action = ArgAction::SetTrue, long, conflicts_with_all = ["stdin", "words"])]
+    clip: bool,
 
     /// Don't show example sentences
     #[arg(action = ArgAction::SetTrue, long, short)]
     no_examples: bool,
+
+    /// Show each sense's synonyms and antonyms
+    #[arg(action = ArgAction::SetTrue, long)]
+    relations: bool,
+
+    /// Only show these parts of speech, e.g. `noun` or `adj,adv`
+    #[arg(long, value_delimiter = ',', value_name = "POS")]
+    pos: Vec<String>,
+
+    /// Only show the etymology with this number, for words like `bank` with several
+    #[arg(long, value_name = "N")]
+    etymology: Option<usize>,
+
+    /// Only show senses still in use in or after this year (undated senses always show)
+    #[arg(long, value_name = "YEAR", allow_negative_numbers = true)]
+    since: Option<i32>,
+
+    /// Only show senses already in use before this year (undated senses always show)
+    #[arg(long, value_name = "YEAR", allow_negative_numbers = true)]
+    before: Option<i32>,
+
+    /// Hide senses that fell out of use before 1900, like `--since 1900`
+    #[arg(action = ArgAction::SetTrue, long, conflicts_with = "since")]
+    modern: bool,
+
+    /// Hide senses labelled archaic, obsolete or dated
+    #[arg(action = ArgAction::SetTrue, long)]
+    no_archaic: bool,
+
+    /// Show at most this many senses for each part of speech
+    #[arg(long, value_name = "N")]
+    max_senses: Option<usize>,
+
+    /// Show just the first sense, on one line
+    #[arg(action = ArgAction::SetTrue, long)]
+    short: bool,
+
+    /// Print nothing; exit with 0 if every word is defined and 1 if any isn't
+    #[arg(action = ArgAction::SetTrue, long)]
+    exists: bool,
+
+    /// Output format
+    #[arg(long, short, value_enum, default_value_t = Format::Text)]
+    format: Format,
+
+    /// Lay entries out with a saved template from the config dir instead of --format
+    #[arg(long, short, value_name = "NAME")]
+    template: Option<String>,
+
+    /// Where to read entries from
+    #[arg(long, value_enum, global = true, default_value_t = Backend::Json)]
+    store: Backend,
+
+    /// Language to look words up in, e.g. es; imports and installs are filed by language too
+    #[arg(long, global = true, default_value = DEFAULT_LANG)]
+    lang: String,
+
+    /// Only look words up in this installed dictionary ("default" for the usual search roots)
+    #[arg(long, global = true, value_name = "NAME")]
+    dict: Option<String>,
+
+    /// Named dictionaries to search first, from the config file
+    #[arg(skip)]
+    dict_order: Vec<String>,
+
+    /// Hunspell dictionary (.aff/.dic pair, without the extension) to find the stems of inflected words with
+    #[arg(long, global = true, value_name = "PATH")]
+    hunspell: Option<PathBuf>,
+
+    /// DICT server to ask for words missing locally, e.g. dict://dict.org
+    #[arg(long, global = true, value_name = "URL")]
+    remote: Option<String>,
+
+    /// Ask an online dictionary for words missing locally, saving the answers for next time
+    #[cfg(feature = "net")]
+    #[arg(action = ArgAction::SetTrue, long, global = true)]
+    online: bool,
+
+    /// Never send long output through $PAGER
+    #[arg(action = ArgAction::SetTrue, long, global = true)]
+    no_pager: bool,
+
+    /// Color theme: default, light-terminal, high-contrast, monochrome, or a theme file's name
+    #[arg(long, global = true, default_value = "default")]
+    theme: String,
+
+    /// Theme overrides from the config file
+    #[arg(skip)]
+    colors: ThemeSpec,
+
+    /// When to color the output
+    #[arg(long, value_enum, global = true, default_value_t = ColorMode::Auto)]
+    color: ColorMode,
+
+    /// Skip the recent-lookup cache, reading every entry from the store
+    #[arg(action = ArgAction::SetTrue, long, global = true)]
+    no_cache: bool,
 }
 
-fn main() -> Result<()> {
-    let cli = Cli::parse();
+#[derive(Subcommand)]
+enum Command {
+    /// List headwords starting with a stem
+    Prefix {
+        /// The stem to search for
+        stem: String,
 
-    if !&cli.word.chars().all(|c| c.is_alphabetic()) {
-        cprintln!("<red>Invalid input: Word must contain only alphabetic characters.</red>");
-        std::process::exit(1);
-    }
+        /// Show the first sense of each word
+        #[arg(action = ArgAction::SetTrue, long, short)]
+        sense: bool,
+    },
 
-    let word = cli.word.to_lowercase();
-    let mut chars = word.chars();
-    let first = chars.next().unwrap();
+    /// List headwords matching a glob pattern (`?` for one letter, `*` for any run)
+    Search {
+        /// The pattern to match, e.g. 'c?t*'
+        pattern: String,
+    },
 
-    let target: PathBuf = if let Some(second) = chars.next() {
-        let mut path = PathBuf::from(String::from(first));
-        path.push(format!("{}{}", String::from(first), String::from(second)));
-        path
-    } else {
-        let mut path = PathBuf::from(String::from(first));
-        path.push(String::from(first));
-        path.into()
-    };
+    /// Show the entry for a random headword
+    Random {
+        /// Only pick words with this part of speech, e.g. noun
+        #[arg(long)]
+        pos: Option<String>,
 
-    let contents = read_data(target.into())?;
+        /// Only pick words at least this many letters long
+        #[arg(long, default_value_t = 0)]
+        min_len: usize,
+    },
 
-    let data: DictionaryFile = from_str(&contents)?;
+    /// Star a word, optionally with a note, so lookups point it out
+    Star {
+        /// The word to star
+        word: String,
 
-    if let Some(f) = data.get(&cli.word) {
-        f.print_colored(!cli.no_examples);
-    } else {
-        cprintln!("<red>Word not found: {}</red>", cli.word);
-    }
+        /// A note to keep with it
+        #[arg(long, short)]
+        note: Option<String>,
 
-    Ok(())
+        /// Unstar the word instead
+        #[arg(action = ArgAction::SetTrue, long, conflicts_with = "note")]
+        remove: bool,
+    },
+
+    /// List the starred words and their notes
+    Starred,
+
+    /// Write your own entry for a word, shown in place of any installed one
+    Add {
+        /// The word to define
+        word: String,
+
+        /// Read the entry from a JSON file in the shard format instead of asking for it (`-` for stdin)
+        #[arg(long, value_name = "FILE")]
+        from_json: Option<PathBuf>,
+    },
+
+    /// Change your entry for a word in your editor, starting from the installed one if you have none
+    Edit {
+        /// The word to edit
+        word: String,
+    },
+
+    /// Delete your entry for a word, bringing back the installed one
+    Remove {
+        /// The word to delete
+        word: String,
+    },
+
+    /// Define each single word copied to the clipboard, until interrupted
+    #[cfg(feature = "clipboard")]
+    WatchClip {
+        /// Show each definition in a desktop notification instead of printing it
+        #[arg(action = ArgAction::SetTrue, long)]
+        notify: bool,
+
+        /// How often to check the clipboard, in milliseconds
+        #[arg(long, default_value_t = 500)]
+        interval: u64,
+    },
+
+    /// Pick a headword in rofi, dmenu or fuzzel and define it
+    Menu {
+        /// The launcher to pick with
+        #[arg(long, value_enum, default_value_t = MenuBackend::Rofi)]
+        backend: MenuBackend,
+
+        /// Show the definition in a desktop notification instead of printing it
+        #[arg(action = ArgAction::SetTrue, long)]
+        notify: bool,
+    },
+
+    /// Summarize the lookup history: favourite words, misses, busy days and coverage
+    Stats {
+        /// How many words and days to list
+        #[arg(long, default_value_t = 10)]
+        top: usize,
+    },
+
+    /// Pick the headword that fits a definition, keeping score
+    Quiz {
+        /// Where the words asked about come from
+        #[arg(long, value_enum, default_value_t = QuizSource::Dictionary)]
+        from: QuizSource,
+
+        /// How many questions to ask
+        #[arg(long, default_value_t = 10)]
+        rounds: usize,
+
+        /// How many headwords to choose from
+        #[arg(long, default_value_t = 4, value_parser = clap::value_parser!(u8).range(2..=9))]
+        choices: u8,
+    },
+
+    /// Count the headwords, senses and examples in each dictionary root and named dictionary
+    DictStats,
+
+    /// Learn words with spaced-repetition flashcards
+    Study {
+        #[command(subcommand)]
+        command: StudyCommand,
+    },
+
+    /// List past lookups, newest first
+    History {
+        /// Only show this many of the most recent lookups
+        #[arg(long)]
+        last: Option<usize>,
+
+        /// Delete the history
+        #[arg(action = ArgAction::SetTrue, long)]
+        clear: bool,
+
+        /// Look up the word at this position in the list again
+        #[arg(long, conflicts_with = "clear")]
+        open: Option<usize>,
+    },
+
+    /// Show the word of the day, the same for everyone with the same dictionary
+    Wotd {
+        /// The day to pick for, as YYYY-MM-DD (defaults to today in UTC)
+        #[arg(long)]
+        date: Option<String>,
+
+        /// Print just the word and its first sense on one line, e.g. for a login message
+        #[arg(action = ArgAction::SetTrue, long)]
+        oneline: bool,
+    },
+
+    /// List headwords fitting a crossword pattern, `_` marking each unknown letter
+    Fill {
+        /// The pattern to fill, e.g. 'c_t__n'
+        pattern: String,
+    },
+
+    /// List headwords matching a regular expression
+    Grep {
+        /// The regex to match against each headword
+        regex: String,
+
+        /// Match case-insensitively
+        #[arg(action = ArgAction::SetTrue, long, short)]
+        ignore_case: bool,
+
+        /// Print the full entry for each match
+        #[arg(action = ArgAction::SetTrue, long, short)]
+        define: bool,
+    },
+
+    /// List every headword within a number of edits of a word, closest first
+    Fuzzy {
+        /// The word to look around
+        word: String,
+
+        /// Most letters inserted, deleted or changed to get from the word to a match
+        #[arg(long, short, default_value_t = 2)]
+        distance: usize,
+    },
+
+    /// List headwords in order from FROM up to, but not including, TO
+    Range {
+        /// The first headword to list, or where to start if it isn't one
+        from: String,
+
+        /// Where to stop; without it, every headword after FROM is listed
+        to: Option<String>,
+    },
+
+    /// List the most frequent headwords, as ranked by an imported frequency list
+    List {
+        /// How many words to list
+        #[arg(long, default_value_t = 100)]
+        top: usize,
+    },
+
+    /// Find words by their meaning: headwords with a sense mentioning a phrase
+    Reverse {
+        /// What the word means, e.g. "fear of heights"
+        phrase: String,
+
+        /// Only list this many words
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+    },
+
+    /// List the words of a text file that aren't in the dictionary, with their line numbers
+    Check {
+        /// The file to check, or `-` for standard input
+        file: PathBuf,
+    },
+
+    /// List the dictionary roots that are searched, in precedence order, and the named dictionaries
+    Dicts,
+
+    /// Print the effective dictionary search order and where each path comes from
+    Paths,
+
+    /// Write the whole dictionary (or the words matching a pattern) to one file
+    Export {
+        /// Format to write
+        #[arg(long, short, value_enum)]
+        format: ExportFormat,
+
+        /// Only export headwords matching this glob pattern
+        #[arg(long, short)]
+        pattern: Option<String>,
+
+        /// File to write to (defaults to stdout)
+        #[arg(long, short)]
+        out: Option<PathBuf>,
+    },
+
+    /// Convert a dictionary in another format into dibble's shard layout
+    Import {
+        /// The file to import
+        path: PathBuf,
+
+        /// Format of the file being imported
+        #[arg(long, short, value_enum)]
+        format: ImportFormat,
+
+        /// Dictionary root to write into (defaults to the --lang folder in the user data directory)
+        #[arg(long, short)]
+        out: Option<PathBuf>,
+
+        /// Header names to read each field from when importing csv, e.g. word=Term,sense=Meaning
+        #[arg(long, value_name = "MAP")]
+        columns: Option<String>,
+    },
+
+    /// Combine two dictionaries into a new shard tree, reporting where they disagree
+    Merge {
+        /// The dictionary that wins conflicts: a shard tree or `.dibble` archive
+        first: PathBuf,
+
+        /// The dictionary merged into it
+        second: PathBuf,
+
+        /// Directory to write the merged shard tree to
+        #[arg(long, short)]
+        out: PathBuf,
+    },
+
+    /// Print the JSON Schema of the shard format, for checking dictionary data against
+    Schema {
+        /// Describe a single entry rather than a whole shard
+        #[arg(action = ArgAction::SetTrue, long)]
+        entry: bool,
+    },
+
+    /// Check every shard of a dictionary for entries that don't parse or are filed wrongly
+    Validate {
+        /// A shard tree or `.dibble` archive (defaults to every dictionary root searched)
+        path: Option<PathBuf>,
+    },
+
+    /// Upgrade a shard tree written by an older dibble to the current format, in place
+    Migrate {
+        /// A shard tree (defaults to every dictionary root searched)
+        path: Option<PathBuf>,
+
+        /// Re-shard by `prefix:LETTERS` or `hash:BUCKETS` too, to split up huge shards
+        #[arg(long)]
+        scheme: Option<String>,
+    },
+
+    /// Pack the installed shards into a single `.dibble` archive
+    Pack {
+        /// The archive to write
+        out: PathBuf,
+    },
+
+    /// Manage the precompiled lookup index
+    Index {
+        #[command(subcommand)]
+        command: IndexCommand,
+    },
+
+    /// Manage the recent-lookup cache
+    Cache {
+        #[command(subcommand)]
+        command: CacheCommand,
+    },
+
+    /// Serve the dictionary to network clients
+    Serve {
+        /// Speak the DICT protocol (RFC 2229) understood by dict(1) and other dictionary clients
+        #[arg(action = ArgAction::SetTrue, long)]
+        dict_protocol: bool,
+
+        /// Serve a JSON API over HTTP on this address: /define/{word}, /search?q= and /random
+        #[arg(
+            long,
+            value_name = "ADDR",
+            num_args = 0..=1,
+            default_missing_value = dibble::server::http::DEFAULT_ADDR,
+            conflicts_with_all = ["dict_protocol", "bind", "port"]
+        )]
+        http: Option<String>,
+
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1")]
+        bind: String,
+
+        /// Port to listen on (defaults to the protocol's standard port)
+        #[arg(long, short)]
+        port: Option<u16>,
+    },
+
+    /// Print a shell completion script
+    Completions {
+        /// The shell to complete for
+        #[arg(value_enum)]
+        shell: CompletionShell,
+
+        /// Only complete subcommands and flags, without calling back into dibble for headwords
+        #[arg(action = ArgAction::SetTrue, long = "static")]
+        static_only: bool,
+    },
+
+    /// Keep shards parsed in memory and answer lookups over a Unix socket
+    #[cfg(unix)]
+    Daemon {
+        /// Socket to listen on (defaults to the user runtime directory)
+        #[arg(long, short)]
+        socket: Option<PathBuf>,
+    },
+
+    /// Run a Language Server on stdin and stdout that shows definitions on hover
+    Lsp {
+        /// Don't show example sentences
+        #[arg(action = ArgAction::SetTrue, long, short)]
+        no_examples: bool,
+    },
+
+    /// Build the SQLite store from the installed shards
+    #[cfg(feature = "sqlite")]
+    BuildDb {
+        /// Database file to write (defaults to the user data directory)
+        #[arg(long, short)]
+        out: Option<PathBuf>,
+    },
+
+    /// Download and install a dictionary into the user data directory
+    #[cfg(feature = "net")]
+    Install {
+        /// The name of the dictionary, as listed in the index
+        name: String,
+
+        /// URL of the dictionary index to install from
+        #[arg(long, default_value = dibble::install::DEFAULT_INDEX)]
+        index: String,
+
+        /// Also download the pronunciation recordings its entries link to
+        #[arg(action = ArgAction::SetTrue, long)]
+        download_audio: bool,
+
+        /// Install even if the bundle isn't signed by a trusted key
+        #[arg(action = ArgAction::SetTrue, long)]
+        allow_unsigned: bool,
+    },
+
+    /// Update installed dictionaries from where they were installed from
+    #[cfg(feature = "net")]
+    Update {
+        /// The dictionary to update (defaults to every one in --lang)
+        name: Option<String>,
+
+        /// Update even if the new bundle isn't signed by a trusted key
+        #[arg(action = ArgAction::SetTrue, long)]
+        allow_unsigned: bool,
+    },
+
+    /// Start an interactive lookup session
+    #[cfg(feature = "repl")]
+    Repl {
+        /// Don't show example sentences
+        #[arg(action = ArgAction::SetTrue, long, short)]
+        no_examples: bool,
+    },
+
+    /// Browse the dictionary interactively
+    #[cfg(feature = "tui")]
+    Tui,
+
+    /// List the synonyms of a word, grouped by sense
+    Syn {
+        /// The word to find synonyms for
+        word: String,
+    },
+
+    /// List the headwords that sound like a word, for one only heard spoken
+    Soundslike {
+        /// The word as it sounds, e.g. 'nefew'
+        word: String,
+    },
+
+    /// List the headwords spelled with exactly the given letters
+    Anagram {
+        /// The letters to rearrange
+        letters: String,
+
+        /// Also list words that use only some of the letters
+        #[arg(action = ArgAction::SetTrue, long)]
+        partial: bool,
+    },
+
+    /// Score a word in Scrabble and check it's in the dictionary, or find the best words for a rack
+    Scrabble {
+        /// The word to score
+        #[arg(required_unless_present = "rack")]
+        word: Option<String>,
+
+        /// List the highest-scoring words that can be played from these tiles
+        #[arg(long, conflicts_with = "word")]
+        rack: Option<String>,
+
+        /// How many words to list with --rack
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+    },
+
+    /// List the words that rhyme with a word, grouped by syllable count
+    Rhyme {
+        /// The word to rhyme with
+        word: String,
+    },
+
+    /// List the inflected forms of a word, generated from the --hunspell dictionary
+    Forms {
+        /// The word to inflect
+        word: String,
+    },
+
+    /// Play a recording of a word being said
+    #[cfg(feature = "audio")]
+    Say {
+        /// The word to say
+        word: String,
+
+        /// Prefer the recording in this accent, e.g. UK or US
+        #[arg(long)]
+        accent: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum IndexCommand {
+    /// Compile the installed shards into the binary index, headword filter and folded spelling index used for lookups, the headword index used for prefix and pattern searches, and the anagram and sounds-like indexes
+    Build {
+        /// Also build the full-text index that makes `dibble reverse` fast
+        #[arg(action = ArgAction::SetTrue, long)]
+        fulltext: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum StudyCommand {
+    /// Add words to the study deck
+    Add {
+        /// The words to learn
+        #[arg(required_unless_present = "starred")]
+        words: Vec<String>,
+
+        /// Add every starred word too
+        #[arg(action = ArgAction::SetTrue, long)]
+        starred: bool,
+    },
+
+    /// Drop a word from the study deck
+    Remove {
+        /// The word to drop
+        word: String,
+    },
+
+    /// Go through the cards due today
+    Review {
+        /// Stop after this many cards
+        #[arg(long)]
+        limit: Option<usize>,
+    },
+
+    /// List the cards in the deck and when each is due
+    List,
+}
+
+#[derive(Subcommand)]
+enum CacheCommand {
+    /// Forget every cached entry
+    Clear,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum MenuBackend {
+    Rofi,
+    Dmenu,
+    /// Fuzzel, for Wayland
+    Fuzzel,
 }
 
-fn read_data(path: PathBuf) -> Result<String> {
-    let dirs: ProjectDirs = ProjectDirs::from("com.taranathan.dibble", "taran", "dibble").unwrap();
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum QuizSource {
+    /// Any headword
+    Dictionary,
+    /// The starred words
+    Starred,
+    /// Words looked up before
+    History,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ExportFormat {
+    /// One JSON object keyed by headword
+    Json,
+    /// Rows of word, pos, sense, example
+    Csv,
+    /// Headings and ordered lists
+    Markdown,
+    /// A standalone HTML page
+    Html,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ImportFormat {
+    /// A StarDict `.ifo` file, with its `.idx` and `.dict(.dz)` alongside
+    Stardict,
+    /// A dictd `.index` file, with its `.dict(.dz)` alongside
+    Dictd,
+    /// A kaikki.org wiktextract JSONL dump, optionally gzipped
+    Wiktextract,
+    /// A CSV file with a header row (tab separated if named `.tsv`)
+    Csv,
+    /// The Moby Thesaurus `mthesaur.txt`, adding synonyms to the entries already in the output root
+    Moby,
+    /// The CMU Pronouncing Dictionary, adding US pronunciations to the entries already in the output root
+    Cmudict,
+    /// A word frequency list, most frequent first, adding ranks to the entries already in the output root
+    Frequency,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ColorMode {
+    /// Color when writing to a terminal and `NO_COLOR` isn't set
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum CompletionShell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Backend {
+    /// The sharded JSON dictionary tree
+    Json,
+    /// The database written by `dibble build-db`
+    #[cfg(feature = "sqlite")]
+    Sqlite,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    /// Colored, human readable output
+    Text,
+    /// The matched entry serialized as JSON. Errors go to stderr as JSON too, e.g.
+    /// {"error": "not_found", "message": "...", "word": "...", "suggestions": [...]}
+    Json,
+    /// A man page, e.g. for `dibble word --format roff | man -l -`
+    Roff,
+    /// Headings and ordered lists, for pasting into notes
+    Markdown,
+    /// An HTML fragment with a CSS class on each element
+    Html,
+    /// Uncolored tab-separated fields in a fixed order, for editor plugins to parse.
+    /// A miss prints `missing<TAB>word`, then `<TAB>suggestion<TAB>word` for each suggestion
+    Plain,
+}
+
+fn main() -> ExitCode {
+    // answers the shell when it asks for completions through the `completions` script
+    CompleteEnv::with_factory(Cli::command).complete();
 
-    let mut user_target = dirs.data_dir().to_path_buf();
-    user_target.push(Path::new("dict"));
-    user_target.push(&path);
-    user_target.set_extension("json");
+    let matches = Cli::command().get_matches();
+    let mut cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    let result = Config::load()
+        .and_then(|config| cli.apply_config(config, &matches))
+        .and_then(|()| {
+            #[cfg(feature = "clipboard")]
+            if matches!(cli.command, Some(Command::WatchClip { .. })) {
+                // a pager would hold up the watch until it was closed
+                cli.no_pager = true;
+            }
+            if cli.format == Format::Plain {
+                // the output is for a program to read, not a person
+                cli.no_pager = true;
+                cli.color = ColorMode::Never;
+            }
+            run(&cli)
+        });
 
-    let mut system_target = PathBuf::from("/usr/share/dibble/dict");
-    system_target.push(&path);
-    system_target.set_extension("json");
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            if cli.format == Format::Json {
+                eprintln!("{}", dibble::error::to_json(&e));
+            } else if !matches!(
+                e.downcast_ref(),
+                Some(DibbleError::WordNotFound { .. } | DibbleError::Undefined(_))
+            ) {
+                eprintln!("Error: {:?}", e);
+            }
+            ExitCode::from(dibble::error::exit_code(&e))
+        }
+    }
+}
 
-    let mut local = PathBuf::from("./dict");
-    local.push(&path);
-    local.set_extension("json");
+fn run(cli: &Cli) -> Result<()> {
+    match cli.color {
+        ColorMode::Auto => {}
+        ColorMode::Always => anstream::ColorChoice::Always.write_global(),
+        ColorMode::Never => anstream::ColorChoice::Never.write_global(),
+    }
+    // opening refuses trees migrating is meant to fix, like ones sharded differently
+    if let Some(Command::Migrate { path, scheme }) = &cli.command {
+        return migrate(&cli.lang, path.as_deref(), scheme.as_deref());
+    }
+    let mut dict = Dictionary::open_lang(&cli.lang)?;
+    if cli.looks_up_repeatedly() {
+        dict = dict.with_shard_cache(ShardCache::default());
+    }
+    let store = open_store(&dict, cli)?;
 
-    if let Ok(mut file) = File::open(&local) {
-        let mut contents = String::new();
-        file.read_to_string(&mut contents)?;
-        return Ok(contents);
+    match &cli.command {
+        Some(Command::Prefix { stem, sense }) => prefix(store.as_ref(), stem, *sense),
+        Some(Command::Search { pattern }) => {
+            dibble::render::print_columns(&dibble::search::glob(&dict, pattern)?);
+            Ok(())
+        }
+        Some(Command::Random { pos, min_len }) => {
+            random(&dict, store.as_ref(), cli, pos.as_deref(), *min_len)
+        }
+        Some(Command::Star { word, note, remove }) => {
+            star(store.as_ref(), cli, word, note.clone(), *remove)
+        }
+        Some(Command::Starred) => starred(),
+        Some(Command::Add { word, from_json }) => {
+            add(store.as_ref(), cli, word, from_json.as_deref())
+        }
+        Some(Command::Edit { word }) => edit(store.as_ref(), cli, word),
+        Some(Command::Remove { word }) => remove(cli, word),
+        #[cfg(feature = "clipboard")]
+        Some(Command::WatchClip { notify, interval }) => {
+            watch_clip(&dict, store.as_ref(), cli, *notify, *interval)
+        }
+        Some(Command::Menu { backend, notify }) => {
+            menu(&dict, store.as_ref(), cli, *backend, *notify)
+        }
+        Some(Command::Stats { top }) => stats(&dict, *top),
+        Some(Command::Quiz {
+            from,
+            rounds,
+            choices,
+        }) => quiz(&dict, *from, *rounds, usize::from(*choices)),
+        Some(Command::DictStats) => dict_stats(&dict, &cli.lang),
+        Some(Command::Study { command }) => study(store.as_ref(), cli, command),
+        Some(Command::History { last, clear, open }) => {
+            history(&dict, store.as_ref(), cli, *last, *clear, *open)
+        }
+        Some(Command::Wotd { date, oneline }) => {
+            wotd(&dict, store.as_ref(), cli, date.as_deref(), *oneline)
+        }
+        Some(Command::Fill { pattern }) => {
+            dibble::render::print_columns(&dibble::search::fill(&dict, pattern)?);
+            Ok(())
+        }
+        Some(Command::Grep {
+            regex,
+            ignore_case,
+            define,
+        }) => grep(&dict, cli, regex, *ignore_case, *define),
+        Some(Command::Fuzzy { word, distance }) => fuzzy(&dict, word, *distance),
+        Some(Command::Range { from, to }) => {
+            dibble::render::print_columns(&dibble::search::range(&dict, from, to.as_deref())?);
+            Ok(())
+        }
+        Some(Command::List { top }) => list(&dict, *top),
+        Some(Command::Reverse { phrase, limit }) => reverse(&dict, cli, phrase, *limit),
+        Some(Command::Check { file }) => check(store.as_ref(), cli, file),
+        Some(Command::Dicts) => dicts(&dict, cli),
+        Some(Command::Paths) => paths(&cli.lang),
+        Some(Command::Export {
+            format,
+            pattern,
+            out,
+        }) => export(&dict, *format, pattern.as_deref(), out.as_deref()),
+        Some(Command::Import {
+            path,
+            format,
+            out,
+            columns,
+        }) => import(path, *format, out.as_deref(), columns.as_deref(), &cli.lang),
+        Some(Command::Merge { first, second, out }) => merge(first, second, out),
+        Some(Command::Schema { entry }) => {
+            let schema = dibble::schema::json_schema(*entry);
+            println!("{}", serde_json::to_string_pretty(&schema)?);
+            Ok(())
+        }
+        Some(Command::Validate { path }) => validate(&dict, path.as_deref()),
+        Some(Command::Migrate { .. }) => {
+            unreachable!("migrate runs before the dictionary is opened")
+        }
+        Some(Command::Pack { out }) => {
+            let count = Archive::build(&dict, out)?;
+            cprintln!(
+                "Packed <bold>{}</bold> shards into {}",
+                count,
+                out.display()
+            );
+            Ok(())
+        }
+        Some(Command::Index {
+            command: IndexCommand::Build { fulltext },
+        }) => {
+            if cli.lang != DEFAULT_LANG {
+                anyhow::bail!(
+                    "The index only covers the default language ({})",
+                    DEFAULT_LANG
+                );
+            }
+            // the one place lookups look for it
+            let dir = dibble::index::default_dir()?;
+            let count = dibble::index::build(&dict, &dir, *fulltext)?;
+            cprintln!(
+                "Indexed <bold>{}</bold> headwords into {}",
+                count,
+                dir.display()
+            );
+            Ok(())
+        }
+        Some(Command::Cache {
+            command: CacheCommand::Clear,
+        }) => {
+            dibble::cache::clear()?;
+            cprintln!("Cleared the lookup cache");
+            Ok(())
+        }
+        Some(Command::Serve {
+            dict_protocol,
+            http,
+            bind,
+            port,
+        }) => {
+            if let Some(addr) = http {
+                cprintln!("Serving HTTP on {}", addr);
+                return dibble::server::http::run(&dict, Arc::from(store), addr.as_str());
+            }
+            if !dict_protocol {
+                anyhow::bail!("Choose a protocol to serve, e.g. --dict-protocol or --http");
+            }
+            let port = port.unwrap_or(dibble::server::dict::DEFAULT_PORT);
+            cprintln!("Serving DICT on {}:{}", bind, port);
+            dibble::server::dict::run(&dict, Arc::from(store), (bind.as_str(), port))
+        }
+        Some(Command::Completions { shell, static_only }) => completions(*shell, *static_only),
+        #[cfg(unix)]
+        Some(Command::Daemon { socket }) => {
+            let socket = match socket {
+                Some(socket) => socket.clone(),
+                None => dibble::daemon::socket_path()?,
+            };
+            cprintln!("Listening on {}", socket.display());
+            dibble::daemon::run(&dict, &socket)
+        }
+        Some(Command::Lsp { no_examples }) => dibble::lsp::run(
+            store.as_ref(),
+            !no_examples,
+            std::io::stdin().lock(),
+            std::io::stdout().lock(),
+        ),
+        #[cfg(feature = "sqlite")]
+        Some(Command::BuildDb { out }) => {
+            let path = match out {
+                Some(out) => out.clone(),
+                None => SqliteStore::default_path()?,
+            };
+            let count = SqliteStore::build(&dict, &path)?;
+            cprintln!("Wrote <bold>{}</bold> entries to {}", count, path.display());
+            Ok(())
+        }
+        #[cfg(feature = "net")]
+        Some(Command::Update {
+            name,
+            allow_unsigned,
+        }) => update(&cli.lang, name.as_deref(), *allow_unsigned),
+        #[cfg(feature = "net")]
+        Some(Command::Install {
+            name,
+            index,
+            download_audio,
+            allow_unsigned,
+        }) => {
+            let path = dibble::install::install(name, index, *allow_unsigned)?;
+            dibble::cache::clear()?;
+            cprintln!("Installed <bold>{}</bold> to {}", name, path.display());
+            if *download_audio {
+                let count =
+                    dibble::audio::download_all(&Dictionary::with_roots(vec![path.clone()]))?;
+                cprintln!("Downloaded <bold>{}</bold> recordings", count);
+            }
+            Ok(())
+        }
+        #[cfg(feature = "repl")]
+        Some(Command::Repl { no_examples }) => {
+            dibble::repl::run(&dict, store.as_ref(), !no_examples)
+        }
+        #[cfg(feature = "tui")]
+        Some(Command::Tui) => dibble::tui::run(store.as_ref()),
+        Some(Command::Syn { word }) => synonyms(store.as_ref(), cli, word),
+        Some(Command::Forms { word }) => forms(cli, word),
+        Some(Command::Soundslike { word }) => sounds_like(&dict, cli, word),
+        Some(Command::Anagram { letters, partial }) => anagram(&dict, cli, letters, *partial),
+        Some(Command::Scrabble { word, rack, limit }) => match rack {
+            Some(rack) => scrabble_rack(&dict, cli, rack, *limit),
+            None => scrabble(store.as_ref(), cli, word.as_deref().unwrap_or_default()),
+        },
+        Some(Command::Rhyme { word }) => rhyme(&dict, store.as_ref(), cli, word),
+        #[cfg(feature = "audio")]
+        Some(Command::Say { word, accent }) => say(&dict, store.as_ref(), cli, word, accent),
+        None if cli.stdin || cli.words == ["-"] => {
+            let words = std::io::stdin()
+                .lines()
+                .map(|line| Ok(line?.trim().to_string()))
+                .filter(|line| !matches!(line, Ok(l) if l.is_empty()))
+                .collect::<Result<Vec<_>>>()?;
+            if cli.exists {
+                exists(store.as_ref(), cli, &words)
+            } else {
+                define_batch(store.as_ref(), cli, &words)
+            }
+        }
+        None if cli.exists => exists(store.as_ref(), cli, &cli.words),
+        #[cfg(feature = "clipboard")]
+        None if cli.clip => {
+            let text = dibble::clipboard::text()?;
+            let Some(word) = dibble::clipboard::word(&text) else {
+                anyhow::bail!("There's no word in the clipboard");
+            };
+            define(&dict, store.as_ref(), cli, &word)
+        }
+        None if cli.words.len() == 1 => define(&dict, store.as_ref(), cli, &cli.words[0]),
+        None => define_batch(store.as_ref(), cli, &cli.words),
     }
+}
 
-    if let Ok(mut file) = File::open(&user_target) {
-        let mut contents = String::new();
-        file.read_to_string(&mut contents)?;
-        return Ok(contents);
+impl Cli {
+    /// Whether this run looks words up over and over, so parsed shards are
+    /// worth keeping: sessions, servers and batches
+    fn looks_up_repeatedly(&self) -> bool {
+        match &self.command {
+            #[cfg(feature = "repl")]
+            Some(Command::Repl { .. }) => true,
+            #[cfg(feature = "tui")]
+            Some(Command::Tui) => true,
+            #[cfg(feature = "clipboard")]
+            Some(Command::WatchClip { .. }) => true,
+            #[cfg(unix)]
+            Some(Command::Daemon { .. }) => true,
+            Some(
+                Command::Serve { .. }
+                | Command::Lsp { .. }
+                | Command::Study { .. }
+                | Command::Quiz { .. },
+            ) => true,
+            Some(_) => false,
+            None => self.stdin || self.words.len() > 1 || self.words == ["-"],
+        }
     }
 
-    // system installation fallback
-    if let Ok(mut file) = File::open(&system_target) {
-        let mut contents = String::new();
-        file.read_to_string(&mut contents)?;
-        return Ok(contents);
+    /// Fills in everything not given on the command line from the config file
+    fn apply_config(&mut self, config: Config, matches: &ArgMatches) -> Result<()> {
+        let unset = |id: &str| !matches!(matches.value_source(id), Some(ValueSource::CommandLine));
+
+        self.no_examples |= config.no_examples;
+        self.relations |= config.relations;
+        self.no_archaic |= config.no_archaic;
+        self.no_pager |= config.no_pager;
+        self.no_cache |= config.no_cache;
+        #[cfg(feature = "net")]
+        {
+            self.online |= config.online;
+        }
+
+        if unset("format")
+            && let Some(format) = &config.format
+        {
+            self.format = Format::from_str(format, true)
+                .map_err(|_| anyhow::anyhow!("Unknown format '{}' in the config file", format))?;
+        }
+        if unset("color")
+            && let Some(color) = &config.color
+        {
+            self.color = ColorMode::from_str(color, true).map_err(|_| {
+                anyhow::anyhow!("Unknown color mode '{}' in the config file", color)
+            })?;
+        }
+        if unset("lang")
+            && let Some(lang) = config.lang
+        {
+            self.lang = lang;
+        }
+        if unset("theme")
+            && let Some(theme) = config.theme
+        {
+            self.theme = theme;
+        }
+        self.template = self.template.take().or(config.template);
+        self.remote = self.remote.take().or(config.remote);
+        self.dict = self.dict.take().or(config.dict);
+        self.hunspell = self.hunspell.take().or(config.hunspell);
+        self.dict_order = config.dict_order;
+        self.colors = config.colors;
+
+        Ok(())
     }
 
-    anyhow::bail!(
-        "Dictionary file not found. Searched:\n  - {}\n  - {}",
-        user_target.display(),
-        system_target.display()
-    )
+    /// The `--theme`, with the config file's overrides on top
+    fn load_theme(&self) -> Result<Theme> {
+        Theme::load(&self.theme)?.with_spec(&self.colors)
+    }
 }
 
-pub type DictionaryFile = HashMap<String, Definition>;
+fn completions(shell: CompletionShell, static_only: bool) -> Result<()> {
+    let mut out = std::io::stdout();
+
+    if static_only {
+        let shell = match shell {
+            CompletionShell::Bash => clap_complete::Shell::Bash,
+            CompletionShell::Zsh => clap_complete::Shell::Zsh,
+            CompletionShell::Fish => clap_complete::Shell::Fish,
+        };
+        clap_complete::generate(shell, &mut Cli::command(), "dibble", &mut out);
+        return Ok(());
+    }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Definition {
-    /// The word being defined
-    pub word: String,
-    /// Array of Etymology objects, representing different meanings or origins of the word
-    pub etymologies: Vec<Etymology>,
+    let completer: &dyn EnvCompleter = match shell {
+        CompletionShell::Bash => &clap_complete::env::Bash,
+        CompletionShell::Zsh => &clap_complete::env::Zsh,
+        CompletionShell::Fish => &clap_complete::env::Fish,
+    };
+    completer.write_registration("COMPLETE", "dibble", "dibble", "dibble", &mut out)?;
+    Ok(())
 }
 
-/// Represents a particular etymology or origin of a word
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Etymology {
-    /// Array of Part of Speech objects within this etymology
-    #[serde(rename = "partsOfSpeech")]
-    pub parts_of_speech: Vec<PartOfSpeech>,
+/// Completes the word argument from the installed headwords
+fn complete_word(current: &std::ffi::OsStr) -> Vec<CompletionCandidate> {
+    let Some(stem) = current.to_str().filter(|stem| !stem.is_empty()) else {
+        return Vec::new();
+    };
+
+    Dictionary::open()
+        .and_then(|dict| dict.prefix(stem))
+        .unwrap_or_default()
+        .into_iter()
+        .map(CompletionCandidate::new)
+        .collect()
 }
 
-/// Represents a specific part of speech for a word
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct PartOfSpeech {
-    /// The part of speech (e.g., "Noun", "Verb", "Adjective")
-    #[serde(rename = "partOfSpeech")]
-    pub part_of_speech: String,
-    /// Array of Sense objects representing different meanings
-    pub senses: Vec<Sense>,
+fn open_store(dict: &Dictionary, cli: &Cli) -> Result<Box<dyn Store>> {
+    let store = open_installed(dict, cli)?;
+    // --dict asks one installed dictionary alone
+    if cli.dict.is_some() {
+        return Ok(store);
+    }
+    let user = UserDictionary::open(&cli.lang)?;
+    Ok(if user.is_empty() {
+        store
+    } else {
+        Box::new(Overlay::new(user, store))
+    })
 }
 
-/// Represents a specific sense or meaning of a word
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Sense {
-    /// The specific sense or meaning
-    pub sense: String,
-    /// Optional: Time period or usage context
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub date: Option<String>,
-    #[serde(default)]
-    pub examples: Vec<String>,
+fn open_installed(dict: &Dictionary, cli: &Cli) -> Result<Box<dyn Store>> {
+    if matches!(cli.store, Backend::Json) {
+        let mut library = Library::open(dict, &cli.lang, &cli.dict_order)?;
+        if let Some(name) = &cli.dict {
+            library = library.only(name)?;
+        }
+        // the daemon only knows the default dictionary in the default language
+        if library.is_merged() || library.names().ne([DEFAULT_NAME]) || cli.lang != DEFAULT_LANG {
+            return Ok(Box::new(library));
+        }
+    } else if cli.dict.is_some() {
+        anyhow::bail!("--dict only applies to the JSON store");
+    }
+
+    Ok(match cli.store {
+        // a running daemon already has the shards parsed, so prefer it
+        #[cfg(unix)]
+        Backend::Json => match dibble::daemon::Client::connect(&dibble::daemon::socket_path()?) {
+            Some(client) => Box::new(client),
+            None => Box::new(dict.clone()),
+        },
+        #[cfg(not(unix))]
+        Backend::Json => Box::new(dict.clone()),
+        #[cfg(feature = "sqlite")]
+        Backend::Sqlite => Box::new(SqliteStore::open(&SqliteStore::default_path()?)?),
+    })
 }
 
-impl Definition {
-    pub fn print_colored(&self, examples: bool) {
-        //header
-        cprintln!("<bold><cyan>{}</cyan></bold>", self.word);
+fn define(dict: &Dictionary, store: &dyn Store, cli: &Cli, word: &str) -> Result<()> {
+    if !is_valid_word(word) {
+        return Err(invalid_word());
+    }
 
-        for (etym_idx, etymology) in self.etymologies.iter().enumerate() {
-            if self.etymologies.len() > 1 {
-                cprintln!("<bold><yellow>Etymology {}:</yellow></bold>", etym_idx + 1);
+    let mut results = lookup_cached(store, cli, &[word.to_string()])?;
+    lookup_lemmas(store, cli, &[word.to_string()], &mut results)?;
+    remember(&[word.to_string()], &results, cli);
+    if let Some(mut f) = results.pop().flatten() {
+        if let Err(problem) = narrow(&mut f, cli) {
+            anstream::print!("{}", problem);
+            return Ok(());
+        }
+        print_entry(f, cli, word)?;
+    } else {
+        let suggestions = dibble::suggest::suggestions(dict, word, 5)?;
+        // JSON output reports the miss as an error instead
+        if cli.format == Format::Plain {
+            print!("{}", plain_miss(word, &suggestions));
+        } else if cli.format != Format::Json {
+            cprintln!("<red>Word not found: {}</red>", word);
+            if !suggestions.is_empty() {
+                cprintln!("Did you mean: <bold>{}</bold>?", suggestions.join(", "));
             }
+        }
+        return Err(DibbleError::WordNotFound {
+            word: word.to_string(),
+            suggestions,
+        }
+        .into());
+    }
 
-            for pos in &etymology.parts_of_speech {
-                cprintln!("  <bold><green>{}</green></bold>", pos.part_of_speech);
+    Ok(())
+}
 
-                for (sense_idx, sense) in pos.senses.iter().enumerate() {
-                    cprintln!("    <bold>{}.</bold> {}", sense_idx + 1, sense.sense);
+/// Exits with 1, printing nothing, unless every one of `words` is defined
+fn exists(store: &dyn Store, cli: &Cli, words: &[String]) -> Result<()> {
+    // a predicate for scripts, so a miss is just the status rather than a lookup error
+    if let Some(word) = words.iter().find(|word| !is_valid_word(word)) {
+        return Err(DibbleError::Undefined(word.clone()).into());
+    }
+    let results = lookup_store(store, cli, words)?;
+    match words.iter().zip(&results).find(|(_, def)| def.is_none()) {
+        Some((word, _)) => Err(DibbleError::Undefined(word.clone()).into()),
+        None => Ok(()),
+    }
+}
 
-                    if let Some(date) = &sense.date {
-                        if date.len() > 0 {
-                            cprintln!("       <italic><dim>[{}]</dim></italic>", date);
-                        }
-                    }
+fn invalid_word() -> anyhow::Error {
+    DibbleError::InvalidInput(
+        "Words may only contain letters, hyphens, apostrophes and spaces".to_string(),
+    )
+    .into()
+}
 
-                    if examples {
-                        for example in &sense.examples {
-                            cprintln!("       <dim>\"{}\"</dim>", example);
-                        }
-                    }
+/// Adds the entries shown to the lookup history. The source is the
+/// dictionaries a merged entry came from, or the one picked with --dict.
+fn remember(words: &[String], results: &[Option<Definition>], cli: &Cli) {
+    let visits: Vec<Visit> = words
+        .iter()
+        .zip(results)
+        .map(|(word, def)| {
+            let Some(def) = def else {
+                return Visit::miss(word);
+            };
+            let mut sources: Vec<&str> = Vec::new();
+            for source in def.etymologies.iter().filter_map(|e| e.source.as_deref()) {
+                if !sources.contains(&source) {
+                    sources.push(source);
                 }
-                cprintln!();
             }
+            let source = if sources.is_empty() {
+                cli.dict.clone()
+            } else {
+                Some(sources.join(", "))
+            };
+            Visit::now(&def.word, source)
+        })
+        .collect();
+    // a history that can't be written shouldn't stop the lookup from printing
+    let _ = dibble::history::record(&visits);
+}
+
+fn history(
+    dict: &Dictionary,
+    store: &dyn Store,
+    cli: &Cli,
+    last: Option<usize>,
+    clear: bool,
+    open: Option<usize>,
+) -> Result<()> {
+    if clear {
+        dibble::history::clear()?;
+        cprintln!("Cleared the lookup history");
+        return Ok(());
+    }
+
+    // newest first, numbered from 1 so --open can refer back to them
+    let mut visits = dibble::history::load()?;
+    visits.reverse();
+
+    if let Some(n) = open {
+        let Some(visit) = n.checked_sub(1).and_then(|idx| visits.get(idx)) else {
+            anyhow::bail!("The history has {} lookups, not {}", visits.len(), n);
+        };
+        return define(dict, store, cli, &visit.word.clone());
+    }
+
+    if visits.is_empty() {
+        cprintln!("<dim>No lookups yet</dim>");
+        return Ok(());
+    }
+    visits.truncate(last.unwrap_or(visits.len()));
+    let width = visits.len().to_string().len();
+    for (idx, visit) in visits.iter().enumerate() {
+        let source = match &visit.source {
+            Some(source) => cformat!("  <dim>({})</dim>", source),
+            None => String::new(),
+        };
+        cprintln!(
+            "<dim>{:>1$}  {2}</dim>  {3}{4}",
+            idx + 1,
+            width,
+            visit.time_string(),
+            visit.word,
+            source
+        );
+    }
+    Ok(())
+}
+
+/// Cuts an entry down to the parts asked for with `--etymology`, `--pos`,
+/// `--since`, `--before`, `--modern`, `--no-archaic`, `--max-senses` and
+/// `--short`. When nothing is left of it, the error says
+/// why, ready to print.
+fn narrow(def: &mut Definition, cli: &Cli) -> Result<(), String> {
+    if let Some(n) = cli.etymology {
+        let Some(etymology) = n
+            .checked_sub(1)
+            .and_then(|idx| def.etymologies.get(idx))
+            .cloned()
+        else {
+            return Err(etymologies(def, n));
+        };
+        def.etymologies = vec![etymology];
+    }
+    if !cli.pos.is_empty() {
+        def.retain_parts_of_speech(|pos| {
+            cli.pos
+                .iter()
+                .any(|query| dibble::pos::matches(query, &pos.part_of_speech))
+        });
+    }
+    let since = cli
+        .since
+        .or(cli.modern.then_some(dibble::period::MODERN_YEAR));
+    if since.is_some() || cli.before.is_some() {
+        def.retain_senses(|sense| {
+            sense.period().is_none_or(|period| {
+                since.is_none_or(|year| period.in_use_since(year))
+                    && cli.before.is_none_or(|year| period.in_use_before(year))
+            })
+        });
+    }
+    if cli.no_archaic {
+        def.retain_senses(|sense| !sense.is_archaic());
+    }
+    if let Some(max) = cli.max_senses {
+        def.truncate_senses(max);
+    }
+    if cli.short {
+        def.keep_first_sense();
+    }
+    if def.etymologies.is_empty() {
+        let pos = match cli.pos.is_empty() {
+            true => String::new(),
+            false => format!("{} ", cli.pos.join(" or ")),
+        };
+        let mut when = Vec::new();
+        if let Some(year) = since {
+            when.push(format!(" in use since {}", year));
+        }
+        if let Some(year) = cli.before {
+            when.push(format!(" in use before {}", year));
+        }
+        if cli.no_archaic {
+            when.push(String::from(" that aren't archaic"));
+        }
+        return Err(cformat!(
+            "<red>{} has no {}senses{}</red>\n",
+            def.word,
+            pos,
+            when.join(" and")
+        ));
+    }
+    Ok(())
+}
+
+/// Says `--etymology n` is out of range and lists the etymologies there are,
+/// each by its parts of speech and first sense
+fn etymologies(def: &Definition, n: usize) -> String {
+    let mut out = match def.etymologies.len() {
+        1 => cformat!(
+            "<red>{} has only one etymology, not {}</red>\n",
+            def.word,
+            n
+        ),
+        len => cformat!(
+            "<red>{} has {} etymologies, not {}</red>\n",
+            def.word,
+            len,
+            n
+        ),
+    };
+    for (idx, etymology) in def.etymologies.iter().enumerate() {
+        let parts: Vec<&str> = etymology
+            .parts_of_speech
+            .iter()
+            .map(|pos| pos.part_of_speech.as_str())
+            .collect();
+        let sense = etymology
+            .parts_of_speech
+            .iter()
+            .find_map(|pos| pos.senses.first())
+            .map_or("", |sense| sense.sense.as_str());
+        out += &cformat!(
+            "  <bold>{}</bold>. <dim>{}:</dim> {}\n",
+            idx + 1,
+            parts.join(", "),
+            sense
+        );
+    }
+    out
+}
+
+/// Prints one entry in the chosen format, noting the lemma if it was found
+/// for an inflection of `word`
+fn print_entry(mut f: Definition, cli: &Cli, word: &str) -> Result<()> {
+    match cli.format {
+        Format::Json => {
+            if cli.no_examples {
+                f.strip_examples();
+            }
+            println!("{}", serde_json::to_string_pretty(&f)?);
+        }
+        _ => {
+            let template = load_template(cli)?;
+            let theme = cli.load_theme()?;
+            let stars = Stars::open()?;
+            let mut out = lemma_note(word, &f, cli, template.as_ref());
+            out.push_str(&star_note(&f, &stars, cli, template.as_ref()));
+            out.push_str(&render_entry(&f, cli, template.as_ref(), &theme)?);
+            show(&out, cli)?
         }
     }
+    Ok(())
+}
+
+/// Prints entries found by something other than their headword, like
+/// [`print_entry`] does one, narrowed and separated as a batch lookup would
+fn print_entries(mut defs: Vec<Definition>, cli: &Cli) -> Result<()> {
+    if cli.format == Format::Json {
+        defs.retain_mut(|f| narrow(f, cli).is_ok());
+        if cli.no_examples {
+            defs.iter_mut().for_each(Definition::strip_examples);
+        }
+        println!("{}", serde_json::to_string_pretty(&defs)?);
+        return Ok(());
+    }
+
+    let template = load_template(cli)?;
+    let theme = cli.load_theme()?;
+    let stars = Stars::open()?;
+    let mut out = String::new();
+    for (idx, mut f) in defs.into_iter().enumerate() {
+        if idx > 0 && cli.format == Format::Text && template.is_none() && !cli.short {
+            out.push_str(&dibble::render::separator());
+            out.push('\n');
+        }
+        match narrow(&mut f, cli) {
+            Ok(()) => {
+                out.push_str(&star_note(&f, &stars, cli, template.as_ref()));
+                out.push_str(&render_entry(&f, cli, template.as_ref(), &theme)?);
+            }
+            Err(problem) => {
+                out.push_str(&problem);
+                out.push('\n');
+            }
+        }
+    }
+    show(&out, cli)
+}
+
+/// Shows a random headword of `dict`, with its entry as `store` has it, so
+/// it reads the way looking the word up would
+fn random(
+    dict: &Dictionary,
+    store: &dyn Store,
+    cli: &Cli,
+    pos: Option<&str>,
+    min_len: usize,
+) -> Result<()> {
+    let mut words = dibble::search::headwords(dict)?;
+    words.retain(|word| word.chars().count() >= min_len);
+
+    // draw until one has the part of speech asked for; drawing without
+    // replacement keeps every match equally likely
+    while let Some(word) = dibble::search::take_random(&mut words) {
+        let Some(def) = store.lookup(&word)? else {
+            continue;
+        };
+        let matches = pos.is_none_or(|pos| {
+            def.etymologies
+                .iter()
+                .flat_map(|etym| &etym.parts_of_speech)
+                .any(|p| dibble::pos::matches(pos, &p.part_of_speech))
+        });
+        if matches {
+            return print_entry(def, cli, &word);
+        }
+    }
+
+    cprintln!("<red>No headword fits</red>");
+    Ok(())
+}
+
+/// Shows the word of the day picked from the headwords of `dict`, with its
+/// entry as `store` has it
+fn wotd(
+    dict: &Dictionary,
+    store: &dyn Store,
+    cli: &Cli,
+    date: Option<&str>,
+    oneline: bool,
+) -> Result<()> {
+    let date = match date {
+        Some(date) => {
+            dibble::wotd::check_date(date)?;
+            date.to_string()
+        }
+        None => dibble::date::today(),
+    };
+
+    let words = dibble::search::headwords(dict)?;
+    let Some(def) = dibble::wotd::pick(&words, &date)
+        .map(|word| store.lookup(word))
+        .transpose()?
+        .flatten()
+    else {
+        cprintln!("<red>No words in the dictionary</red>");
+        return Ok(());
+    };
+
+    if oneline {
+        match def.first_sense() {
+            Some(sense) => cprintln!("<bold>{}</bold>: {}", def.word, sense.sense),
+            None => cprintln!("<bold>{}</bold>", def.word),
+        }
+        return Ok(());
+    }
+    let word = def.word.clone();
+    print_entry(def, cli, &word)
+}
+
+/// Looks up `words`, answering from the recent-lookup cache where possible
+/// and remembering whatever had to be read from the store
+fn lookup_cached(
+    store: &dyn Store,
+    cli: &Cli,
+    words: &[String],
+) -> Result<Vec<Option<Definition>>> {
+    // the cache holds entries from every dictionary in the default language,
+    // so it can't answer for just one or for another language
+    if cli.no_cache || cli.dict.is_some() || cli.lang != DEFAULT_LANG {
+        return lookup_store(store, cli, words);
+    }
+
+    let roots: Vec<PathBuf> = search_roots(&cli.lang)?
+        .into_iter()
+        .map(|(root, _)| root)
+        .collect();
+    let mut cache = RecentCache::open(&roots)?;
+    let mut results: Vec<Option<Definition>> = words.iter().map(|word| cache.get(word)).collect();
+
+    let missing: Vec<String> = words
+        .iter()
+        .zip(&results)
+        .filter(|(_, result)| result.is_none())
+        .map(|(word, _)| word.clone())
+        .collect();
+    if !missing.is_empty() {
+        let mut found = lookup_store(store, cli, &missing)?.into_iter();
+        for result in results.iter_mut().filter(|result| result.is_none()) {
+            *result = found.next().flatten();
+            if let Some(def) = result {
+                cache.insert(def.clone());
+            }
+        }
+    }
+
+    // a cache that can't be written shouldn't stop the lookup from printing
+    let _ = cache.save();
+    Ok(results)
+}
+
+/// Whether words missing locally can be looked up elsewhere
+fn has_fallback(cli: &Cli) -> bool {
+    #[cfg(feature = "net")]
+    if cli.online {
+        return true;
+    }
+    cli.remote.is_some()
+}
+
+/// Looks up `words` in the store, asking the `--remote` server and then the
+/// online dictionary for any it lacks
+fn lookup_store(store: &dyn Store, cli: &Cli, words: &[String]) -> Result<Vec<Option<Definition>>> {
+    let mut results = match store.lookup_many(words) {
+        Ok(results) => results,
+        // a shard missing locally is just another miss when a fallback can fill it in
+        Err(_) if has_fallback(cli) => words
+            .iter()
+            .map(|word| store.lookup(word).ok().flatten())
+            .collect(),
+        Err(e) => return Err(e),
+    };
+
+    if let Some(url) = &cli.remote {
+        let remote = DictClient::parse(url)?;
+        for (word, result) in words.iter().zip(&mut results) {
+            if result.is_none() {
+                *result = remote.lookup(word)?;
+            }
+        }
+    }
+
+    #[cfg(feature = "net")]
+    if cli.online {
+        let online = dibble::remote::online::OnlineClient::open()?;
+        for (word, result) in words.iter().zip(&mut results) {
+            if result.is_none() {
+                *result = online.lookup(word)?;
+            }
+        }
+    }
+
+    Ok(results)
+}
+
+/// Fills in the words that weren't found with the entry for their lemma, if
+/// the store has one: `running` gets the entry for `run`. The lemmas come
+/// from the `--hunspell` dictionary if there is one, and from dibble's own
+/// English rules otherwise.
+fn lookup_lemmas(
+    store: &dyn Store,
+    cli: &Cli,
+    words: &[String],
+    results: &mut [Option<Definition>],
+) -> Result<()> {
+    if results.iter().all(Option::is_some) {
+        return Ok(());
+    }
+    let hunspell = match &cli.hunspell {
+        Some(path) => Some(Hunspell::open(path)?),
+        None => None,
+    };
+
+    for (word, result) in words.iter().zip(results) {
+        if result.is_some() {
+            continue;
+        }
+        let candidates = match &hunspell {
+            Some(hunspell) => hunspell.stems(&word.to_lowercase()),
+            None => dibble::lemma::candidates(word),
+        };
+        if candidates.is_empty() {
+            continue;
+        }
+        *result = store.lookup_many(&candidates)?.into_iter().flatten().next();
+    }
+    Ok(())
+}
+
+/// The `running → run` line shown above an entry found through its lemma
+fn lemma_note(word: &str, def: &Definition, cli: &Cli, template: Option<&Template>) -> String {
+    if cli.format != Format::Text
+        || template.is_some()
+        || cli.short
+        || def.word.to_lowercase() == word.to_lowercase()
+    {
+        return String::new();
+    }
+    cformat!("<dim>{} → {}</dim>\n", word, def.word)
+}
+
+/// A line pointing out a starred word and its note, shown above the entry in
+/// plain text output
+fn star_note(def: &Definition, stars: &Stars, cli: &Cli, template: Option<&Template>) -> String {
+    if cli.format != Format::Text || template.is_some() || cli.short {
+        return String::new();
+    }
+    match stars.get(&def.word) {
+        Some(Star { note: Some(note) }) => {
+            cformat!("<yellow>★ starred</yellow> <dim>{}</dim>\n", note)
+        }
+        Some(Star { note: None }) => cformat!("<yellow>★ starred</yellow>\n"),
+        None => String::new(),
+    }
+}
+
+fn star(
+    store: &dyn Store,
+    cli: &Cli,
+    word: &str,
+    note: Option<String>,
+    remove: bool,
+) -> Result<()> {
+    let mut stars = Stars::open()?;
+    if remove {
+        if !stars.remove(word) {
+            anyhow::bail!("'{}' isn't starred", word);
+        }
+        stars.save()?;
+        cprintln!("Unstarred <bold>{}</bold>", word);
+        return Ok(());
+    }
+
+    // star the headword itself, so a lookup in any case finds the star
+    let Some(def) = lookup_cached(store, cli, &[word.to_string()])?
+        .pop()
+        .flatten()
+    else {
+        cprintln!("<red>Word not found: {}</red>", word);
+        return Ok(());
+    };
+    stars.add(&def.word, note);
+    stars.save()?;
+    cprintln!("Starred <bold>{}</bold>", def.word);
+    Ok(())
+}
+
+fn starred() -> Result<()> {
+    let stars = Stars::open()?;
+    let mut any = false;
+    for (word, star) in stars.iter() {
+        any = true;
+        match &star.note {
+            Some(note) => cprintln!("<bold>{}</bold>  <dim>{}</dim>", word, note),
+            None => cprintln!("<bold>{}</bold>", word),
+        }
+    }
+    if !any {
+        cprintln!("<dim>No starred words yet; star one with `dibble star WORD`</dim>");
+    }
+    Ok(())
+}
+
+fn study(store: &dyn Store, cli: &Cli, command: &StudyCommand) -> Result<()> {
+    let mut deck = Deck::open()?;
+    let today = dibble::date::today_number();
+    match command {
+        StudyCommand::Add { words, starred } => {
+            let mut words = words.clone();
+            if *starred {
+                words.extend(Stars::open()?.iter().map(|(word, _)| word.clone()));
+            }
+            for (word, def) in words.iter().zip(lookup_cached(store, cli, &words)?) {
+                // study the headword itself, so the card shows it as the dictionary spells it
+                let Some(def) = def else {
+                    cprintln!("<red>Word not found: {}</red>", word);
+                    continue;
+                };
+                if deck.add(&def.word, today) {
+                    cprintln!("Added <bold>{}</bold>", def.word);
+                } else {
+                    cprintln!("<dim>{} is already in the deck</dim>", def.word);
+                }
+            }
+            deck.save()
+        }
+        StudyCommand::Remove { word } => {
+            if !deck.remove(word) {
+                anyhow::bail!("'{}' isn't in the study deck", word);
+            }
+            deck.save()?;
+            cprintln!("Removed <bold>{}</bold>", word);
+            Ok(())
+        }
+        StudyCommand::Review { limit } => review(store, cli, &mut deck, today, *limit),
+        StudyCommand::List => {
+            let mut any = false;
+            for (word, card) in deck.iter() {
+                any = true;
+                let due = if card.due <= today {
+                    cformat!("<green>due now</green>")
+                } else {
+                    cformat!("<dim>due {}</dim>", dibble::date::format_day(card.due))
+                };
+                cprintln!("<bold>{}</bold>  {}", word, due);
+            }
+            if !any {
+                cprintln!(
+                    "<dim>The study deck is empty; add words with `dibble study add WORD`</dim>"
+                );
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(feature = "clipboard")]
+fn watch_clip(
+    dict: &Dictionary,
+    store: &dyn Store,
+    cli: &Cli,
+    notify: bool,
+    interval: u64,
+) -> Result<()> {
+    let mut watcher = dibble::clipboard::Watcher::new(std::time::Duration::from_millis(interval))?;
+    cprintln!("<dim>Watching the clipboard for words; press Ctrl-C to stop</dim>");
+    loop {
+        let word = watcher.next_word();
+        if !is_valid_word(&word) {
+            continue;
+        }
+        if notify {
+            notify_entry(store, cli, &word)?;
+        } else {
+            define(dict, store, cli, &word)?;
+        }
+    }
+}
+
+/// Looks `word` up and shows the first few senses in a desktop notification
+#[cfg(feature = "clipboard")]
+fn notify_entry(store: &dyn Store, cli: &Cli, word: &str) -> Result<()> {
+    let word = word.to_string();
+    let mut results = lookup_cached(store, cli, std::slice::from_ref(&word))?;
+    lookup_lemmas(store, cli, std::slice::from_ref(&word), &mut results)?;
+    remember(std::slice::from_ref(&word), &results, cli);
+    let (summary, body) = match results.pop().flatten() {
+        Some(def) => {
+            // the first sense of each part of speech is about all a notification fits
+            let senses: Vec<String> = def
+                .etymologies
+                .iter()
+                .flat_map(|etym| &etym.parts_of_speech)
+                .filter_map(|pos| {
+                    let sense = pos.senses.first()?;
+                    Some(format!("{}: {}", pos.part_of_speech, sense.sense))
+                })
+                .take(3)
+                .collect();
+            (def.word, senses.join("\n"))
+        }
+        None => (word, "Not found".to_string()),
+    };
+    // a missing notification daemon shouldn't end a watch
+    if let Err(e) = dibble::clipboard::notify(&summary, &body) {
+        cprintln!("<red>{:#}</red>", e);
+    }
+    Ok(())
+}
+
+fn menu(
+    dict: &Dictionary,
+    store: &dyn Store,
+    cli: &Cli,
+    backend: MenuBackend,
+    notify: bool,
+) -> Result<()> {
+    let (program, args): (&str, &[&str]) = match backend {
+        MenuBackend::Rofi => ("rofi", &["-dmenu", "-i", "-p", "dibble"]),
+        MenuBackend::Dmenu => ("dmenu", &["-i", "-p", "dibble"]),
+        MenuBackend::Fuzzel => ("fuzzel", &["--dmenu", "--prompt", "dibble: "]),
+    };
+    let mut words = dibble::search::headwords(dict)?;
+    words.sort();
+    words.dedup();
+    let Some(word) = dibble::menu::choose(program, args, &words)? else {
+        return Ok(());
+    };
+
+    if notify {
+        #[cfg(feature = "clipboard")]
+        return notify_entry(store, cli, &word);
+        #[cfg(not(feature = "clipboard"))]
+        anyhow::bail!("This build of dibble can't show notifications");
+    }
+    define(dict, store, cli, &word)
+}
+
+fn stats(dict: &Dictionary, top: usize) -> Result<()> {
+    let usage = dibble::stats::Usage::from_visits(&dibble::history::load_all()?, top);
+    if usage.lookups == 0 {
+        cprintln!("<dim>No lookups yet</dim>");
+        return Ok(());
+    }
+
+    let headwords: HashSet<String> = dibble::search::headwords(dict)?.into_iter().collect();
+    let covered = usage
+        .words
+        .iter()
+        .filter(|word| headwords.contains(*word))
+        .count();
+    let percent = |part: usize, whole: usize| 100.0 * part as f64 / whole.max(1) as f64;
+
+    cprintln!("<bold>Lookups</bold>      {}", usage.lookups);
+    cprintln!(
+        "<bold>Misses</bold>       {} <dim>({:.1}%)</dim>",
+        usage.misses,
+        100.0 * usage.miss_rate()
+    );
+    cprintln!("<bold>Words</bold>        {}", usage.words.len());
+    cprintln!(
+        "<bold>Coverage</bold>     {} of {} headwords <dim>({:.2}%)</dim>",
+        covered,
+        headwords.len(),
+        percent(covered, headwords.len())
+    );
+
+    for (title, counts) in [
+        ("Most looked up", &usage.top_words),
+        ("Busiest days", &usage.busiest_days),
+    ] {
+        if counts.is_empty() {
+            continue;
+        }
+        cprintln!("\n<bold>{}</bold>", title);
+        let width = counts[0].0.to_string().len();
+        for (count, label) in counts {
+            cprintln!("  <dim>{:>1$}</dim>  {2}", count, width, label);
+        }
+    }
+    Ok(())
+}
+
+fn dict_stats(dict: &Dictionary, lang: &str) -> Result<()> {
+    let mut dictionaries: Vec<(String, PathBuf)> = dict
+        .roots()
+        .iter()
+        .filter(|root| root.exists())
+        .map(|root| (root.display().to_string(), root.clone()))
+        .collect();
+    dictionaries.extend(dibble::library::installed(lang)?);
+    if dictionaries.is_empty() {
+        cprintln!("<dim>No dictionaries installed</dim>");
+        return Ok(());
+    }
+
+    for (idx, (name, path)) in dictionaries.iter().enumerate() {
+        let contents =
+            dibble::stats::Contents::collect(&Dictionary::with_roots(vec![path.clone()]))?;
+        if idx > 0 {
+            cprintln!();
+        }
+        cprintln!("<bold>{}</bold>", name);
+        cprintln!("  Headwords      {}", contents.headwords);
+        cprintln!(
+            "  Senses         {} <dim>({:.2} per word)</dim>",
+            contents.senses,
+            contents.senses_per_word()
+        );
+        cprintln!(
+            "  With examples  {} <dim>({:.1}%)</dim>",
+            contents.with_examples,
+            100.0 * contents.example_rate()
+        );
+        let size = |fraction| dibble::stats::format_bytes(contents.shard_size(fraction));
+        cprintln!(
+            "  Shards         {} <dim>(smallest {}, median {}, 90th percentile {}, largest {})</dim>",
+            contents.shard_sizes.len(),
+            size(0.0),
+            size(0.5),
+            size(0.9),
+            size(1.0)
+        );
+    }
+    Ok(())
+}
+
+fn add(store: &dyn Store, cli: &Cli, word: &str, from_json: Option<&Path>) -> Result<()> {
+    if !is_valid_word(word) {
+        return Err(invalid_word());
+    }
+    let mut user = UserDictionary::open(&cli.lang)?;
+    if let Some(existing) = user.get(word) {
+        anyhow::bail!(
+            "You already have an entry for '{}'; change it with `dibble edit {}`",
+            existing.word,
+            existing.word
+        );
+    }
+
+    let mut def = match from_json {
+        Some(path) => {
+            let contents = if path == Path::new("-") {
+                std::io::read_to_string(std::io::stdin())?
+            } else {
+                std::fs::read_to_string(path)
+                    .with_context(|| format!("Failed to read {}", path.display()))?
+            };
+            serde_json::from_str(&contents)
+                .with_context(|| format!("{} isn't a dictionary entry", path.display()))?
+        }
+        None => {
+            if store.lookup(word)?.is_some() {
+                cprintln!(
+                    "<dim>{} is in the dictionary already; yours will be shown instead</dim>",
+                    word
+                );
+            }
+            match ask_entry(word)? {
+                Some(def) => def,
+                None => {
+                    cprintln!("<dim>Nothing added</dim>");
+                    return Ok(());
+                }
+            }
+        }
+    };
+    def.word = word.to_string();
+
+    user.insert(def);
+    user.save()?;
+    // the cache may still hold the installed entry
+    dibble::cache::clear()?;
+    cprintln!("Added <bold>{}</bold> to {}", word, user.path().display());
+    Ok(())
+}
+
+/// Asks for a part of speech and its senses on stdin. `None` if no sense was given.
+fn ask_entry(word: &str) -> Result<Option<Definition>> {
+    let Some(part_of_speech) = prompt("Part of speech: ")?.filter(|pos| !pos.is_empty()) else {
+        return Ok(None);
+    };
+
+    let mut senses = Vec::new();
+    loop {
+        let text = if senses.is_empty() {
+            "Meaning: "
+        } else {
+            "Another meaning (leave empty to finish): "
+        };
+        let Some(sense) = prompt(text)?.filter(|sense| !sense.is_empty()) else {
+            break;
+        };
+        let example = prompt("Example (optional): ")?.unwrap_or_default();
+        senses.push(Sense {
+            sense,
+            date: None,
+            labels: Vec::new(),
+            examples: if example.is_empty() {
+                Vec::new()
+            } else {
+                vec![example]
+            },
+            synonyms: Vec::new(),
+            antonyms: Vec::new(),
+        });
+    }
+    if senses.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(Definition {
+        word: word.to_string(),
+        etymologies: vec![Etymology {
+            parts_of_speech: vec![PartOfSpeech {
+                part_of_speech: dibble::pos::canonical(&part_of_speech),
+                senses,
+            }],
+            origin: None,
+            source: None,
+        }],
+        pronunciations: Vec::new(),
+        frequency: None,
+    }))
+}
+
+fn edit(store: &dyn Store, cli: &Cli, word: &str) -> Result<()> {
+    let mut user = UserDictionary::open(&cli.lang)?;
+    let def = match user.get(word) {
+        Some(def) => def.clone(),
+        None => match store.lookup(word)? {
+            Some(def) => def,
+            None => anyhow::bail!(
+                "'{}' isn't in the dictionary; write an entry with `dibble add {}`",
+                word,
+                word
+            ),
+        },
+    };
+    let before = serde_json::to_string_pretty(&def)?;
+
+    let after = dibble::overlay::edit_text(&before)?;
+    if after.trim() == before.trim() {
+        cprintln!("<dim>No changes</dim>");
+        return Ok(());
+    }
+    let mut edited: Definition = serde_json::from_str(&after)
+        .context("The edited entry isn't a dictionary entry; nothing was saved")?;
+
+    // the headword is what the entry is filed under, so it stays as it was
+    edited.word = def.word;
+    let headword = edited.word.clone();
+    user.insert(edited);
+    user.save()?;
+    dibble::cache::clear()?;
+    cprintln!("Saved <bold>{}</bold>", headword);
+    Ok(())
+}
+
+fn remove(cli: &Cli, word: &str) -> Result<()> {
+    let mut user = UserDictionary::open(&cli.lang)?;
+    let Some(def) = user.remove(word) else {
+        anyhow::bail!("You have no entry for '{}'", word);
+    };
+    user.save()?;
+    dibble::cache::clear()?;
+    cprintln!("Removed your entry for <bold>{}</bold>", def.word);
+    Ok(())
+}
+
+/// Prints `text` and reads a line from stdin, trimmed. `None` at the end of input.
+fn prompt(text: &str) -> Result<Option<String>> {
+    anstream::print!("{}", text);
+    std::io::stdout().flush()?;
+    let mut line = String::new();
+    if std::io::stdin().lock().read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(line.trim().to_string()))
+}
+
+fn quiz(dict: &Dictionary, from: QuizSource, rounds: usize, choices: usize) -> Result<()> {
+    let mut words = match from {
+        QuizSource::Dictionary => dibble::search::headwords(dict)?,
+        QuizSource::Starred => Stars::open()?
+            .iter()
+            .map(|(word, _)| word.clone())
+            .collect(),
+        QuizSource::History => {
+            let mut words: Vec<String> = dibble::history::load()?
+                .into_iter()
+                .map(|visit| visit.word)
+                .collect();
+            words.sort();
+            words.dedup();
+            words
+        }
+    };
+
+    let (mut asked, mut right) = (0, 0);
+    let mut stopped = false;
+    while asked < rounds
+        && let Some(word) = dibble::search::take_random(&mut words)
+    {
+        let Some(def) = dict.lookup(&word)? else {
+            continue;
+        };
+        let Some(question) = dibble::quiz::Question::new(dict, &def, choices)? else {
+            continue;
+        };
+
+        asked += 1;
+        cprintln!("<dim>[{}/{}]</dim> {}", asked, rounds, question.clue);
+        for (idx, choice) in question.choices.iter().enumerate() {
+            cprintln!("  <bold>{}</bold>. {}", idx + 1, choice);
+        }
+        let picked = loop {
+            let Some(answer) = prompt(&cformat!("Which is it? <dim>(q to stop)</dim> "))? else {
+                break None;
+            };
+            match answer.as_str() {
+                "q" => break None,
+                _ => match answer.parse::<usize>() {
+                    Ok(n) if (1..=question.choices.len()).contains(&n) => break Some(n - 1),
+                    _ => cprintln!(
+                        "<red>Enter a number from 1 to {}</red>",
+                        question.choices.len()
+                    ),
+                },
+            }
+        };
+        let Some(picked) = picked else {
+            asked -= 1;
+            stopped = true;
+            break;
+        };
+
+        if picked == question.answer() {
+            right += 1;
+            cprintln!(
+                "<green>Right!</green> <dim>Score {}/{}</dim>\n",
+                right,
+                asked
+            );
+        } else {
+            cprintln!(
+                "<red>No, it was <bold>{}</bold></red> <dim>Score {}/{}</dim>\n",
+                question.word,
+                right,
+                asked
+            );
+        }
+    }
+
+    if asked == 0 && !stopped {
+        cprintln!("<dim>No words to ask about</dim>");
+    } else if asked > 0 {
+        cprintln!("Final score: <bold>{}/{}</bold>", right, asked);
+    }
+    Ok(())
+}
+
+fn review(
+    store: &dyn Store,
+    cli: &Cli,
+    deck: &mut Deck,
+    today: u64,
+    limit: Option<usize>,
+) -> Result<()> {
+    let mut due = deck.due(today);
+    if due.is_empty() {
+        cprintln!("<dim>Nothing to review today</dim>");
+        return Ok(());
+    }
+    due.truncate(limit.unwrap_or(due.len()));
+
+    let count = due.len();
+    for (idx, word) in due.iter().enumerate() {
+        cprintln!("<dim>[{}/{}]</dim> <bold>{}</bold>", idx + 1, count, word);
+        if prompt(&cformat!("<dim>Press Enter to show the definition</dim> "))?.is_none() {
+            break;
+        }
+        match lookup_cached(store, cli, std::slice::from_ref(word))?
+            .pop()
+            .flatten()
+        {
+            Some(def) => print_entry(def, cli, word)?,
+            None => cprintln!("<red>Word not found: {}</red>", word),
+        }
+
+        let quality = loop {
+            let Some(answer) = prompt(&cformat!(
+                "How well did you know it? <dim>(0 forgot, 5 perfect, q to stop)</dim> "
+            ))?
+            else {
+                break None;
+            };
+            match answer.as_str() {
+                "q" => break None,
+                _ => match answer.parse::<u8>() {
+                    Ok(quality) if quality <= 5 => break Some(quality),
+                    _ => cprintln!("<red>Enter a number from 0 to 5</red>"),
+                },
+            }
+        };
+        let Some(quality) = quality else {
+            break;
+        };
+        if let Some(card) = deck.get_mut(word) {
+            card.review(quality, today);
+            cprintln!(
+                "<dim>Next review {}</dim>\n",
+                dibble::date::format_day(card.due)
+            );
+        }
+        // save as we go, so stopping halfway keeps the grades given so far
+        deck.save()?;
+    }
+    Ok(())
+}
+
+fn define_batch(store: &dyn Store, cli: &Cli, words: &[String]) -> Result<()> {
+    // words that can't be looked up are reported in their place, like misses
+    let valid: Vec<String> = words.iter().filter(|w| is_valid_word(w)).cloned().collect();
+    let mut found = lookup_cached(store, cli, &valid)?;
+    lookup_lemmas(store, cli, &valid, &mut found)?;
+    remember(&valid, &found, cli);
+    let mut found = found.into_iter();
+    let mut results: Vec<Option<Definition>> = words
+        .iter()
+        .map(|word| {
+            if is_valid_word(word) {
+                found.next().flatten()
+            } else {
+                None
+            }
+        })
+        .collect();
+    let problems: Vec<Option<String>> = results
+        .iter_mut()
+        .map(|f| narrow(f.as_mut()?, cli).err())
+        .collect();
+    // every word is shown before the first miss decides the exit code
+    let missed = match words
+        .iter()
+        .zip(&results)
+        .find(|(_, result)| result.is_none())
+    {
+        Some((word, _)) if !is_valid_word(word) => {
+            Err(invalid_word().context(format!("Can't look up {:?}", word)))
+        }
+        Some((word, _)) => Err(DibbleError::WordNotFound {
+            word: word.clone(),
+            suggestions: Vec::new(),
+        }
+        .into()),
+        None => Ok(()),
+    };
+
+    if cli.format == Format::Json {
+        let mut entries = Vec::with_capacity(results.len());
+        for mut f in results {
+            if cli.no_examples
+                && let Some(f) = &mut f
+            {
+                f.strip_examples();
+            }
+            entries.push(f);
+        }
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+        return missed;
+    }
+
+    let template = load_template(cli)?;
+    let theme = cli.load_theme()?;
+    let stars = Stars::open()?;
+    let mut out = String::new();
+    for (idx, ((word, result), problem)) in words.iter().zip(results).zip(problems).enumerate() {
+        if idx > 0 && cli.format == Format::Text && template.is_none() && !cli.short {
+            out.push_str(&dibble::render::separator());
+            out.push('\n');
+        }
+
+        match (result, problem) {
+            (Some(_), Some(problem)) => {
+                out.push_str(&problem);
+                out.push('\n');
+            }
+            (Some(f), None) => {
+                out.push_str(&lemma_note(word, &f, cli, template.as_ref()));
+                out.push_str(&star_note(&f, &stars, cli, template.as_ref()));
+                out.push_str(&render_entry(&f, cli, template.as_ref(), &theme)?)
+            }
+            (None, _) if !is_valid_word(word) => {
+                // --format plain leaves it to the error, like a single invalid word
+                if cli.format != Format::Plain {
+                    out.push_str(&cformat!("<red>Invalid word: {}</red>\n", word));
+                    if !cli.short {
+                        out.push('\n');
+                    }
+                }
+            }
+            (None, _) if cli.format == Format::Plain => out.push_str(&plain_miss(word, &[])),
+            (None, _) => {
+                out.push_str(&cformat!("<red>Word not found: {}</red>\n", word));
+                // one-line entries aren't spaced out, so neither are the misses
+                if !cli.short {
+                    out.push('\n');
+                }
+            }
+        }
+    }
+
+    show(&out, cli)?;
+    missed
+}
+
+/// Renders an entry in any of the non-JSON formats, or with `--template`
+fn render_entry(
+    def: &Definition,
+    cli: &Cli,
+    template: Option<&Template>,
+    theme: &Theme,
+) -> Result<String> {
+    if let Some(template) = template {
+        if cli.no_examples {
+            let mut def = def.clone();
+            def.strip_examples();
+            return template.render(&def);
+        }
+        return template.render(def);
+    }
+
+    let stripped;
+    let def = if cli.relations {
+        def
+    } else {
+        let mut def = def.clone();
+        def.strip_relations();
+        stripped = def;
+        &stripped
+    };
+
+    Ok(match cli.format {
+        Format::Text if cli.short => def.to_short(theme),
+        Format::Roff => def.to_roff(!cli.no_examples),
+        Format::Markdown => def.to_markdown(!cli.no_examples),
+        Format::Html => def.to_html(!cli.no_examples),
+        Format::Plain => def.to_plain(!cli.no_examples),
+        _ => def.to_themed(!cli.no_examples, theme),
+    })
+}
+
+/// What `--format plain` prints for a word that isn't in the dictionary
+fn plain_miss(word: &str, suggestions: &[String]) -> String {
+    let mut out = String::new();
+    dibble::render::plain_field(&mut out, 0, "missing", &[word]);
+    for suggestion in suggestions {
+        dibble::render::plain_field(&mut out, 1, "suggestion", &[suggestion]);
+    }
+    out
+}
+
+fn load_template(cli: &Cli) -> Result<Option<Template>> {
+    cli.template.as_deref().map(Template::load).transpose()
+}
+
+/// Prints rendered entries, through the pager if they're too long for the screen
+fn show(text: &str, cli: &Cli) -> Result<()> {
+    if cli.no_pager {
+        anstream::print!("{}", text);
+        Ok(())
+    } else {
+        dibble::pager::page(text)
+    }
+}
+
+fn prefix(store: &dyn Store, stem: &str, sense: bool) -> Result<()> {
+    let words = store.prefix(stem)?;
+
+    if sense {
+        for def in store.lookup_many(&words)?.into_iter().flatten() {
+            match def.first_sense() {
+                Some(first) => cprintln!("<bold>{}</bold>  <dim>{}</dim>", def.word, first.sense),
+                None => cprintln!("<bold>{}</bold>", def.word),
+            }
+        }
+    } else {
+        for word in words {
+            println!("{}", word);
+        }
+    }
+
+    Ok(())
+}
+
+fn grep(dict: &Dictionary, cli: &Cli, regex: &str, ignore_case: bool, define: bool) -> Result<()> {
+    // the flag goes in the pattern so the headword index sees it too
+    let re = if ignore_case {
+        Regex::new(&format!("(?i){}", regex))?
+    } else {
+        Regex::new(regex)?
+    };
+    let words = dibble::search::grep(dict, &re)?;
+
+    if define {
+        let defs = dict.lookup_many(&words)?.into_iter().flatten().collect();
+        print_entries(defs, cli)?;
+    } else {
+        dibble::render::print_columns(&words);
+    }
+
+    Ok(())
+}
+
+fn fuzzy(dict: &Dictionary, word: &str, distance: usize) -> Result<()> {
+    let label = |edits| if edits == 1 { "edit" } else { "edits" };
+    let found = dibble::suggest::fuzzy(dict, word, distance)?;
+    if found.is_empty() {
+        cprintln!(
+            "<dim>No headwords within {} {} of {}</dim>",
+            distance,
+            label(distance),
+            word
+        );
+        return Ok(());
+    }
+    for group in found.chunk_by(|a, b| a.0 == b.0) {
+        let edits = group[0].0;
+        cprintln!("<green,bold>{} {}</green,bold>", edits, label(edits));
+        let words: Vec<String> = group.iter().map(|(_, word)| word.clone()).collect();
+        dibble::render::print_columns(&words);
+    }
+    Ok(())
+}
+
+fn reverse(dict: &Dictionary, cli: &Cli, phrase: &str, limit: usize) -> Result<()> {
+    let path = dibble::index::default_dir()?.join(dibble::index::fulltext::FILE_NAME);
+    let index = if cli.lang == DEFAULT_LANG && path.exists() {
+        Some(FulltextIndex::open(&path)?).filter(|index| index.covers(dict.roots()))
+    } else {
+        None
+    };
+    let mut found = match index {
+        Some(index) => index.reverse(dict, phrase)?,
+        None => dibble::search::reverse(dict, phrase)?,
+    };
+    if found.is_empty() {
+        cprintln!("<red>No senses mention \"{}\"</red>", phrase);
+        return Ok(());
+    }
+
+    found.truncate(limit);
+    for (word, sense) in found {
+        cprintln!("<bold>{}</bold>  <dim>{}</dim>", word, sense);
+    }
+    Ok(())
+}
+
+/// Lists the words of `file` missing from the dictionary, even as an
+/// inflection of a word that's there
+fn check(store: &dyn Store, cli: &Cli, file: &Path) -> Result<()> {
+    let text = if file == Path::new("-") {
+        std::io::read_to_string(std::io::stdin())?
+    } else {
+        std::fs::read_to_string(file)
+            .with_context(|| format!("Failed to read {}", file.display()))?
+    };
+    let words = dibble::spellcheck::words(&text);
+
+    let list: Vec<String> = words.iter().map(|word| word.word.clone()).collect();
+    let mut results = lookup_store(store, cli, &list)?;
+    lookup_lemmas(store, cli, &list, &mut results)?;
+    let mut unknown = Vec::new();
+    for (word, result) in words.into_iter().zip(results) {
+        if result.is_some() || compound_known(store, cli, &word.word)? {
+            continue;
+        }
+        unknown.push(word);
+    }
+
+    if unknown.is_empty() {
+        cprintln!("<dim>Every word is in the dictionary</dim>");
+    } else {
+        let width = unknown
+            .iter()
+            .map(|word| word.word.chars().count())
+            .max()
+            .unwrap_or(0);
+        for word in &unknown {
+            let lines: Vec<String> = word.lines.iter().map(usize::to_string).collect();
+            cprintln!(
+                "<red>{:2$}</red>  <dim>line {}</dim>",
+                word.word,
+                lines.join(", "),
+                width
+            );
+        }
+    }
+
+    match unknown.into_iter().next() {
+        Some(word) => Err(DibbleError::WordNotFound {
+            word: word.word,
+            suggestions: Vec::new(),
+        }
+        .into()),
+        None => Ok(()),
+    }
+}
+
+/// Whether `word` is made of words joined by hyphens that are all in the
+/// dictionary, like `well-known`, which most dictionaries don't list
+fn compound_known(store: &dyn Store, cli: &Cli, word: &str) -> Result<bool> {
+    let parts: Vec<String> = word.split('-').map(str::to_string).collect();
+    if parts.len() < 2 || parts.iter().any(String::is_empty) {
+        return Ok(false);
+    }
+    let mut results = lookup_store(store, cli, &parts)?;
+    lookup_lemmas(store, cli, &parts, &mut results)?;
+    Ok(results.iter().all(Option::is_some))
+}
+
+fn list(dict: &Dictionary, top: usize) -> Result<()> {
+    let ranked = dibble::search::most_frequent(dict, top)?;
+    if ranked.is_empty() {
+        cprintln!(
+            "<dim>No words have a frequency rank; import a list with --format frequency</dim>"
+        );
+        return Ok(());
+    }
+
+    let width = ranked.last().map_or(1, |(rank, _)| rank.to_string().len());
+    for (rank, word) in ranked {
+        cprintln!("<dim>{:>1$}</dim> {2}", rank, width, word);
+    }
+    Ok(())
+}
+
+fn synonyms(store: &dyn Store, cli: &Cli, word: &str) -> Result<()> {
+    let words = [word.to_string()];
+    let mut results = lookup_cached(store, cli, &words)?;
+    lookup_lemmas(store, cli, &words, &mut results)?;
+    let Some(def) = results.pop().flatten() else {
+        cprintln!("<red>Word not found: {}</red>", word);
+        return Ok(());
+    };
+
+    let mut out = lemma_note(word, &def, cli, None);
+    out.push_str(&cformat!("<bold>{}</bold>\n", def.word));
+    let mut found = false;
+    for etymology in &def.etymologies {
+        for pos in &etymology.parts_of_speech {
+            let senses: Vec<_> = pos
+                .senses
+                .iter()
+                .enumerate()
+                .filter(|(_, sense)| !sense.synonyms.is_empty())
+                .collect();
+            if senses.is_empty() {
+                continue;
+            }
+
+            out.push_str(&cformat!(
+                "  <green,bold>{}</green,bold>\n",
+                pos.part_of_speech
+            ));
+            for (idx, sense) in senses {
+                out.push_str(&cformat!(
+                    "    <bold>{}.</bold> <dim>{}</dim>\n",
+                    idx + 1,
+                    sense.sense
+                ));
+                out.push_str(&format!("       {}\n", sense.synonyms.join(", ")));
+            }
+            found = true;
+        }
+    }
+
+    if !found {
+        cprintln!("No synonyms for <bold>{}</bold>", def.word);
+        return Ok(());
+    }
+    show(&out, cli)
+}
+
+/// Lists the headwords that sound like `word`, from the sounds-like index of
+/// `dibble index build` when it covers the dictionary's roots. Without it,
+/// the headwords are grouped on the spot.
+fn sounds_like(dict: &Dictionary, cli: &Cli, word: &str) -> Result<()> {
+    let path = dibble::index::default_dir()?.join(dibble::index::phonetic::FILE_NAME);
+    let built = if cli.lang == DEFAULT_LANG && path.exists() {
+        Some(PhoneticIndex::open(&path)?).filter(|index| index.covers(dict.roots()))
+    } else {
+        None
+    };
+    let index = match built {
+        Some(index) => index,
+        None => PhoneticIndex::from_dict(dict)?,
+    };
+
+    let words = index.sounds_like(word);
+    if words.is_empty() {
+        cprintln!("<dim>Nothing sounds like {}</dim>", word);
+    } else {
+        dibble::render::print_columns(&words);
+    }
+    Ok(())
+}
+
+/// The anagram index from `dibble index build`, which only covers the
+/// default language and the roots it was built from. Without it, the
+/// headwords are grouped on the spot.
+fn anagram_index(dict: &Dictionary, cli: &Cli) -> Result<AnagramIndex> {
+    let path = dibble::index::default_dir()?.join(dibble::index::anagram::FILE_NAME);
+    if cli.lang == DEFAULT_LANG && path.exists() {
+        let index = AnagramIndex::open(&path)?;
+        if index.covers(dict.roots()) {
+            return Ok(index);
+        }
+    }
+    AnagramIndex::from_dict(dict)
+}
+
+fn anagram(dict: &Dictionary, cli: &Cli, letters: &str, partial: bool) -> Result<()> {
+    let index = anagram_index(dict, cli)?;
+    let mut words = if partial {
+        index.partial(letters)
+    } else {
+        index.anagrams(letters)
+    };
+    let lower = letters.to_lowercase();
+    words.retain(|word| word.to_lowercase() != lower);
+
+    if words.is_empty() {
+        cprintln!("<dim>No anagrams of {}</dim>", letters);
+    } else {
+        dibble::render::print_columns(&words);
+    }
+    Ok(())
+}
+
+fn scrabble(store: &dyn Store, cli: &Cli, word: &str) -> Result<()> {
+    let Some(points) = dibble::scrabble::score(word) else {
+        anyhow::bail!("'{}' has letters that aren't Scrabble tiles", word);
+    };
+    cprintln!(
+        "<bold>{}</bold>  {} {}",
+        word.to_uppercase(),
+        points,
+        if points == 1 { "point" } else { "points" }
+    );
+
+    let words = [word.to_lowercase()];
+    let found = lookup_cached(store, cli, &words)?
+        .pop()
+        .flatten()
+        .is_some_and(|def| def.word == words[0]);
+    if found && dibble::scrabble::is_playable(&words[0]) {
+        cprintln!("<green>Playable</green>");
+    } else if found {
+        cprintln!("<red>In the dictionary, but too short to play</red>");
+    } else {
+        cprintln!("<red>Not in the dictionary</red>");
+    }
+    Ok(())
+}
+
+fn scrabble_rack(dict: &Dictionary, cli: &Cli, rack: &str, limit: usize) -> Result<()> {
+    let index = anagram_index(dict, cli)?;
+    let words = dibble::scrabble::best_words(&index, rack);
+    if words.is_empty() {
+        cprintln!("<dim>Nothing playable from {}</dim>", rack.to_uppercase());
+        return Ok(());
+    }
+
+    let width = words[0].0.to_string().len();
+    for (points, word) in words.into_iter().take(limit) {
+        cprintln!("<dim>{:>1$}</dim> {2}", points, width, word);
+    }
+    Ok(())
+}
+
+fn rhyme(dict: &Dictionary, store: &dyn Store, cli: &Cli, word: &str) -> Result<()> {
+    let words = [word.to_string()];
+    let Some(def) = lookup_cached(store, cli, &words)?.pop().flatten() else {
+        cprintln!("<red>Word not found: {}</red>", word);
+        return Ok(());
+    };
+    if def.pronunciation_line().is_none() {
+        anyhow::bail!(
+            "No pronunciation for '{}'; import one with --format cmudict",
+            def.word
+        );
+    }
+
+    let groups = dibble::rhyme::rhymes(dict, &def)?;
+    if groups.is_empty() {
+        cprintln!("<dim>Nothing rhymes with {}</dim>", def.word);
+        return Ok(());
+    }
+    for (count, words) in groups {
+        let label = if count == 1 { "syllable" } else { "syllables" };
+        cprintln!("<green,bold>{} {}</green,bold>", count, label);
+        dibble::render::print_columns(&words);
+    }
+    Ok(())
+}
+
+fn forms(cli: &Cli, word: &str) -> Result<()> {
+    let Some(path) = &cli.hunspell else {
+        anyhow::bail!(
+            "Word forms come from a Hunspell dictionary; pass --hunspell or set it in the config file"
+        );
+    };
+    let hunspell = Hunspell::open(path)?;
+
+    // accept an inflected form too, and show the forms of its stem
+    let stem = if hunspell.contains(word) {
+        word.to_string()
+    } else {
+        match hunspell.stems(word).into_iter().next() {
+            Some(stem) => stem,
+            None => {
+                cprintln!("<red>Word not found: {}</red>", word);
+                return Ok(());
+            }
+        }
+    };
+
+    cprintln!("<bold>{}</bold>", stem);
+    dibble::render::print_columns(&hunspell.forms(&stem));
+    Ok(())
+}
+
+#[cfg(feature = "audio")]
+fn say(
+    dict: &Dictionary,
+    store: &dyn Store,
+    cli: &Cli,
+    word: &str,
+    accent: &Option<String>,
+) -> Result<()> {
+    let words = [word.to_string()];
+    let mut results = lookup_cached(store, cli, &words)?;
+    lookup_lemmas(store, cli, &words, &mut results)?;
+    let Some(def) = results.pop().flatten() else {
+        cprintln!("<red>Word not found: {}</red>", word);
+        return Ok(());
+    };
+    let Some(audio) = dibble::audio::pick(&def, accent.as_deref()).and_then(|p| p.audio.as_deref())
+    else {
+        anyhow::bail!("No recording of '{}' in the dictionary", def.word);
+    };
+
+    // bundled recordings can be in the default dictionary or any named one
+    let mut roots = dict.roots().to_vec();
+    roots.extend(
+        dibble::library::installed(&cli.lang)?
+            .into_iter()
+            .map(|(_, path)| path),
+    );
+    let path = dibble::audio::locate(audio, &roots)?;
+    dibble::audio::play(&path)
+}
+
+fn dicts(dict: &Dictionary, cli: &Cli) -> Result<()> {
+    let mut active = false;
+
+    for root in dict.roots() {
+        if !root.exists() {
+            cprintln!("  {}  <dim>not found</dim>", root.display());
+            continue;
+        }
+
+        let (shards, words) = dict_size(&Dictionary::with_roots(vec![root.clone()]))?;
+        let marker = if !active && shards > 0 {
+            active = true;
+            "*"
+        } else {
+            " "
+        };
+        cprintln!(
+            "<bold>{}</bold> {}  <dim>{} shards, {} headwords</dim>",
+            marker,
+            root.display(),
+            shards,
+            words
+        );
+    }
+
+    cprintln!("<dim>* is searched first; missing shards fall through to the next root</dim>");
+
+    let library = Library::open(dict, &cli.lang, &cli.dict_order)?;
+    if !library.is_merged() {
+        return Ok(());
+    }
+
+    cprintln!();
+    cprintln!("Named dictionaries, merged in this order:");
+    for (idx, (name, source)) in library.sources().iter().enumerate() {
+        if name == DEFAULT_NAME {
+            cprintln!(
+                "{:>2}. <bold>{}</bold>  <dim>the roots above</dim>",
+                idx + 1,
+                name
+            );
+            continue;
+        }
+        let (shards, words) = dict_size(source)?;
+        cprintln!(
+            "{:>2}. <bold>{}</bold> {}  <dim>{} shards, {} headwords</dim>",
+            idx + 1,
+            name,
+            source.roots()[0].display(),
+            shards,
+            words
+        );
+    }
+
+    Ok(())
+}
+
+/// Counts the shards and headwords in a dictionary
+fn dict_size(dict: &Dictionary) -> Result<(usize, usize)> {
+    let shards = dict.shards()?;
+    let mut words = 0;
+    for shard in &shards {
+        words += dict.shard_words(shard)?.len();
+    }
+    Ok((shards.len(), words))
+}
+
+fn paths(lang: &str) -> Result<()> {
+    for (idx, (root, source)) in search_roots(lang)?.iter().enumerate() {
+        let source = match source {
+            RootSource::Env => DICT_PATH_VAR,
+            RootSource::Config => "config",
+            RootSource::Default => "default",
+        };
+        let exists = if root.exists() { "" } else { " (missing)" };
+        cprintln!(
+            "{:>2}. {}  <dim>{}{}</dim>",
+            idx + 1,
+            root.display(),
+            source,
+            exists
+        );
+    }
+
+    Ok(())
+}
+
+fn export(
+    dict: &Dictionary,
+    format: ExportFormat,
+    pattern: Option<&str>,
+    out: Option<&Path>,
+) -> Result<()> {
+    let mut out: Box<dyn Write> = match out {
+        Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+        None => Box::new(BufWriter::new(std::io::stdout().lock())),
+    };
+
+    match format {
+        ExportFormat::Json => dibble::export::json(dict, pattern, &mut out)?,
+        ExportFormat::Csv => dibble::export::csv(dict, pattern, &mut out)?,
+        ExportFormat::Markdown => dibble::export::markdown(dict, pattern, &mut out)?,
+        ExportFormat::Html => dibble::export::html(dict, pattern, &mut out)?,
+    }
+
+    out.flush()?;
+    Ok(())
+}
+
+fn merge(first: &Path, second: &Path, out: &Path) -> Result<()> {
+    for path in [first, second] {
+        if !path.exists() {
+            anyhow::bail!("{} doesn't exist", path.display());
+        }
+    }
+    // writing over a dictionary would mix its entries into the merge
+    if out.exists() {
+        anyhow::bail!(
+            "{} already exists; merge into a new directory",
+            out.display()
+        );
+    }
+
+    let first = Dictionary::with_roots(vec![first.to_path_buf()]);
+    let second = Dictionary::with_roots(vec![second.to_path_buf()]);
+    let mut writer = ShardWriter::new().with_scheme(first.scheme());
+    let report = dibble::merge::merge(&first, &second, &mut writer)?;
+    writer.write(out)?;
+
+    for conflict in &report.conflicts {
+        cprintln!("<yellow>{}</yellow>: {}", conflict.word, conflict.detail);
+    }
+    cprintln!(
+        "Merged <bold>{}</bold> headwords into {} ({} in both, {} duplicate senses dropped, {} conflicts)",
+        report.entries,
+        out.display(),
+        report.combined,
+        report.duplicate_senses,
+        report.conflicts.len()
+    );
+    Ok(())
+}
+
+fn migrate(lang: &str, path: Option<&Path>, scheme: Option<&str>) -> Result<()> {
+    let scheme = scheme.map(ShardScheme::parse).transpose()?;
+    let roots: Vec<PathBuf> = match path {
+        Some(path) if !path.exists() => anyhow::bail!("{} doesn't exist", path.display()),
+        Some(path) => vec![path.to_path_buf()],
+        // archives are packed from a tree, so they're upgraded by packing it again
+        None => search_roots(lang)?
+            .into_iter()
+            .map(|(root, _)| root)
+            .filter(|root| root.is_dir())
+            .collect(),
+    };
+
+    for root in roots {
+        let report = dibble::migrate::migrate(&root, scheme)?;
+        if report.from_version == report.to_version && report.from_scheme == report.to_scheme {
+            cprintln!(
+                "{} is already in format version {}, sharded by {}",
+                root.display(),
+                report.to_version,
+                report.to_scheme
+            );
+        } else {
+            cprintln!(
+                "<green>Migrated {} from format version {} to {}</green>, sharded by {}: {} entries, {} refiled",
+                root.display(),
+                report.from_version,
+                report.to_version,
+                report.to_scheme,
+                report.entries,
+                report.refiled
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn validate(dict: &Dictionary, path: Option<&Path>) -> Result<()> {
+    let roots: Vec<PathBuf> = match path {
+        Some(path) if !path.exists() => anyhow::bail!("{} doesn't exist", path.display()),
+        Some(path) => vec![path.to_path_buf()],
+        None => dict
+            .roots()
+            .iter()
+            .filter(|root| root.exists())
+            .cloned()
+            .collect(),
+    };
+
+    let mut failed = false;
+    for root in roots {
+        let report = dibble::validate::validate(&Dictionary::with_roots(vec![root.clone()]))?;
+        for problem in &report.problems {
+            cprintln!(
+                "<bold>{}:{}:{}</bold>: {}",
+                root.join(&problem.shard).display(),
+                problem.line,
+                problem.column,
+                problem.message
+            );
+        }
+        let summary = format!(
+            "{}: {} shards, {} entries",
+            root.display(),
+            report.shards,
+            report.entries
+        );
+        match report.problems.len() {
+            0 => cprintln!("<green>{}, no problems</green>", summary),
+            count => {
+                failed = true;
+                cprintln!("<red>{}, {} problems</red>", summary, count);
+            }
+        }
+    }
+
+    if failed {
+        return Err(DibbleError::ParseError("The dictionary has problems".to_string()).into());
+    }
+    Ok(())
+}
+
+#[cfg(feature = "net")]
+fn update(lang: &str, name: Option<&str>, allow_unsigned: bool) -> Result<()> {
+    let installed = dibble::library::installed(lang)?;
+    let targets: Vec<&(String, PathBuf)> = match name {
+        Some(name) => match installed.iter().find(|(n, _)| n == name) {
+            Some(target) => vec![target],
+            None => anyhow::bail!("No dictionary named '{}' is installed", name),
+        },
+        // dictionaries installed some other way have nowhere to update from
+        None => installed
+            .iter()
+            .filter(|(_, path)| path.join(dibble::install::SOURCE_FILE).exists())
+            .collect(),
+    };
+    if targets.is_empty() {
+        cprintln!("<dim>Nothing installed with `dibble install` to update</dim>");
+        return Ok(());
+    }
+
+    let mut updated = false;
+    for (name, path) in targets {
+        match dibble::install::update(name, path, allow_unsigned)? {
+            dibble::install::Update::UpToDate => {
+                cprintln!("<bold>{}</bold> <dim>is up to date</dim>", name)
+            }
+            dibble::install::Update::Updated(changes) => {
+                updated = true;
+                let versions = match (&changes.from_version, &changes.to_version) {
+                    (Some(from), Some(to)) => format!(" from {} to {}", from, to),
+                    (None, Some(to)) => format!(" to {}", to),
+                    _ => String::new(),
+                };
+                cprintln!(
+                    "Updated <bold>{}</bold>{}: <green>{} added</green>, <yellow>{} changed</yellow>, <red>{} removed</red>",
+                    name,
+                    versions,
+                    changes.added,
+                    changes.changed,
+                    changes.removed
+                );
+            }
+        }
+    }
+    if updated {
+        dibble::cache::clear()?;
+    }
+    Ok(())
+}
+
+fn import(
+    path: &Path,
+    format: ImportFormat,
+    out: Option<&Path>,
+    columns: Option<&str>,
+    lang: &str,
+) -> Result<()> {
+    let root = match out {
+        Some(out) => out.to_path_buf(),
+        None => dibble::import::default_target(lang)?,
+    };
+
+    let mut writer = ShardWriter::new();
+    match format {
+        ImportFormat::Stardict => dibble::import::stardict::import(path, &mut writer)?,
+        ImportFormat::Dictd => dibble::import::dictd::import(path, &mut writer)?,
+        ImportFormat::Wiktextract => dibble::import::wiktextract::import(path, &mut writer)?,
+        ImportFormat::Csv => {
+            let columns = match columns {
+                Some(spec) => Columns::parse(spec)?,
+                None => Columns::default(),
+            };
+            dibble::import::csv::import(path, &columns, &mut writer)?
+        }
+        ImportFormat::Moby => {
+            let existing = Dictionary::with_roots(vec![root.clone()]);
+            dibble::import::moby::import(path, &existing, &mut writer)?
+        }
+        ImportFormat::Cmudict => {
+            let existing = Dictionary::with_roots(vec![root.clone()]);
+            dibble::import::cmudict::import(path, &existing, &mut writer)?
+        }
+        ImportFormat::Frequency => {
+            let existing = Dictionary::with_roots(vec![root.clone()]);
+            dibble::import::frequency::import(path, &existing, &mut writer)?
+        }
+    }
+    let (count, skipped) = (writer.len(), writer.skipped());
+    writer.write(&root)?;
+    dibble::cache::clear()?;
+
+    cprintln!(
+        "Imported <bold>{}</bold> headwords into {}",
+        count,
+        root.display()
+    );
+    if skipped > 0 {
+        cprintln!(
+            "<yellow>Skipped {} entries with unusable headwords or nothing to add to</yellow>",
+            skipped
+        );
+    }
+    Ok(())
 }
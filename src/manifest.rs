@@ -0,0 +1,147 @@
+use crate::{Dictionary, archive::is_archive, error::DibbleError, sharding::ShardScheme};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Name of the manifest at the top of a shard tree
+pub const FILE_NAME: &str = "manifest.json";
+
+/// The version of the shard format this build reads and writes:
+///
+/// 1. Shard trees from before manifests, whose headwords may not be in NFC
+///    or may be filed by their first two bytes rather than letters
+/// 2. Headwords in NFC, filed by their first two Unicode letters
+/// 3. Headwords filed by the [`ShardScheme`] in the manifest
+pub const FORMAT_VERSION: u32 = 3;
+
+/// What a shard tree says about itself
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub format_version: u32,
+    /// Absent before format version 3, when every tree used the default
+    #[serde(default)]
+    pub shard_scheme: ShardScheme,
+}
+
+impl Manifest {
+    /// The manifest of a shard tree written by this build with `shard_scheme`
+    pub fn current(shard_scheme: ShardScheme) -> Self {
+        Self {
+            format_version: FORMAT_VERSION,
+            shard_scheme,
+        }
+    }
+
+    /// Reads the manifest of the shard tree at `root`. A tree without one is
+    /// from before manifests, so format version 1.
+    pub fn read(root: &Path) -> Result<Self> {
+        let path = root.join(FILE_NAME);
+        match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents)
+                .with_context(|| format!("Invalid manifest {}", path.display())),
+            Err(_) => Ok(Self {
+                format_version: 1,
+                shard_scheme: ShardScheme::default(),
+            }),
+        }
+    }
+
+    /// Writes the manifest into the shard tree at `root`, replacing the file
+    /// in one step
+    pub fn write(&self, root: &Path) -> Result<()> {
+        let path = root.join(FILE_NAME);
+        let partial = path.with_extension("partial");
+        fs::write(&partial, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", partial.display()))?;
+        fs::rename(&partial, &path).with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+/// Fails if the shard tree at `root` is in a format newer than this build
+/// can read. Older formats still read, though `dibble migrate` brings them
+/// up to date. Archives and missing roots are left alone.
+pub fn check(root: &Path) -> Result<()> {
+    if is_archive(root) || !root.is_dir() {
+        return Ok(());
+    }
+    let manifest = Manifest::read(root)?;
+    if manifest.format_version > FORMAT_VERSION {
+        return Err(DibbleError::ParseError(format!(
+            "{} is in format version {}, but this dibble only reads up to {}; update dibble",
+            root.display(),
+            manifest.format_version,
+            FORMAT_VERSION
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+/// The scheme of the first of `roots` with a manifest, or the default if
+/// none has one. A manifest that can't be read counts as none here; opening
+/// the dictionary with [`check`] reports it.
+pub fn scheme(roots: &[PathBuf]) -> ShardScheme {
+    roots
+        .iter()
+        .filter(|root| !is_archive(root) && root.join(FILE_NAME).is_file())
+        .find_map(|root| Manifest::read(root).ok())
+        .map(|manifest| manifest.shard_scheme)
+        .unwrap_or_default()
+}
+
+/// Fails if the roots that exist in `roots` don't all file headwords the
+/// same way, since a word is looked for under one shard in every root.
+/// Archives and trees without a manifest use the default scheme.
+pub fn check_schemes(roots: &[PathBuf]) -> Result<()> {
+    let mut first: Option<(&PathBuf, ShardScheme)> = None;
+    for root in roots.iter().filter(|root| root.exists()) {
+        let scheme = if is_archive(root) {
+            ShardScheme::default()
+        } else {
+            Manifest::read(root)?.shard_scheme
+        };
+        match first {
+            Some((other, other_scheme)) if other_scheme != scheme => anyhow::bail!(
+                "{} is sharded by {} but {} by {}; run `dibble migrate --scheme` on one to match",
+                other.display(),
+                other_scheme,
+                root.display(),
+                scheme
+            ),
+            Some(_) => {}
+            None => first = Some((root, scheme)),
+        }
+    }
+    Ok(())
+}
+
+/// Fails unless the shard tree at `root` is in the current format, or is
+/// new, in which case it's marked as current and sharded by `scheme`.
+/// Returns the scheme the tree is sharded by. Anything writing shards calls
+/// this first, so new entries never land in a tree of another format.
+pub fn prepare_for_write(root: &Path, scheme: ShardScheme) -> Result<ShardScheme> {
+    let manifest = Manifest::read(root)?;
+    let is_new = || -> Result<bool> {
+        Ok(Dictionary::with_roots(vec![root.to_path_buf()])
+            .shards()?
+            .is_empty())
+    };
+    if !root.join(FILE_NAME).exists() && is_new()? {
+        fs::create_dir_all(root)?;
+        Manifest::current(scheme).write(root)?;
+        return Ok(scheme);
+    }
+    match manifest.format_version {
+        FORMAT_VERSION => Ok(manifest.shard_scheme),
+        version if version < FORMAT_VERSION => anyhow::bail!(
+            "{} is in format version {}; run `dibble migrate {}` before writing to it",
+            root.display(),
+            version,
+            root.display()
+        ),
+        _ => check(root).map(|()| manifest.shard_scheme),
+    }
+}
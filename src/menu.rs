@@ -0,0 +1,36 @@
+use anyhow::{Context, Result};
+use std::{
+    io::{Read, Write},
+    process::{Command, Stdio},
+};
+
+/// Offers `items` in a dmenu-style launcher run as `program args..`, which
+/// reads the choices one per line on stdin and prints the one picked. `None`
+/// if nothing was picked.
+pub fn choose(program: &str, args: &[&str], items: &[String]) -> Result<Option<String>> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Could not run {}; is it installed?", program))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        let mut lines = items.join("\n");
+        lines.push('\n');
+        // the user picking something before every item is written closes the
+        // pipe; that's not an error
+        let _ = stdin.write_all(lines.as_bytes());
+    }
+    let mut picked = String::new();
+    if let Some(mut stdout) = child.stdout.take() {
+        stdout.read_to_string(&mut picked)?;
+    }
+
+    // launchers exit with a failure status when dismissed
+    if !child.wait()?.success() {
+        return Ok(None);
+    }
+    let picked = picked.trim();
+    Ok((!picked.is_empty()).then(|| picked.to_string()))
+}
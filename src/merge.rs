@@ -0,0 +1,189 @@
+use crate::{Definition, Dictionary, Etymology, PartOfSpeech, Sense, import::ShardWriter, pos};
+use anyhow::Result;
+use std::collections::BTreeSet;
+
+/// Something two dictionaries disagree on about a headword, settled in
+/// favour of the first
+#[derive(Debug, Clone)]
+pub struct Conflict {
+    pub word: String,
+    /// What differed and which side was kept
+    pub detail: String,
+}
+
+/// What a merge did
+#[derive(Debug, Default)]
+pub struct Report {
+    /// Headwords in the merged dictionary
+    pub entries: usize,
+    /// Headwords both dictionaries had, which were combined into one
+    pub combined: usize,
+    /// Senses dropped because the first dictionary already had them
+    pub duplicate_senses: usize,
+    pub conflicts: Vec<Conflict>,
+}
+
+/// Combines every entry in `first` and `second` into `writer`. A headword in
+/// both keeps the etymologies of `first` and gains those of `second` with a
+/// different origin; where the origins agree, its parts of speech are merged
+/// and a sense worded the same way in both is kept once. Where the two
+/// disagree on a frequency rank or the date of a shared sense, `first` wins
+/// and the difference is reported.
+pub fn merge(first: &Dictionary, second: &Dictionary, writer: &mut ShardWriter) -> Result<Report> {
+    // the same headword must come from the same shard of both to be combined
+    if first.scheme() != second.scheme() {
+        anyhow::bail!(
+            "The dictionaries are sharded differently ({} and {}); migrate one to the other's scheme first",
+            first.scheme(),
+            second.scheme()
+        );
+    }
+    let first_shards: BTreeSet<_> = first.shards()?.into_iter().collect();
+    let second_shards: BTreeSet<_> = second.shards()?.into_iter().collect();
+
+    let mut report = Report::default();
+    for shard in first_shards.union(&second_shards) {
+        let mut entries = if first_shards.contains(shard) {
+            first.load_shard(shard)?
+        } else {
+            Default::default()
+        };
+        if second_shards.contains(shard) {
+            for (word, def) in second.load_shard(shard)? {
+                match entries.get_mut(&word) {
+                    Some(existing) => {
+                        report.combined += 1;
+                        combine(existing, def, &mut report);
+                    }
+                    None => {
+                        entries.insert(word, def);
+                    }
+                }
+            }
+        }
+        report.entries += entries.len();
+        for (_, def) in entries {
+            writer.insert(def);
+        }
+    }
+
+    Ok(report)
+}
+
+/// Folds `other` into `def`, which has the same headword
+fn combine(def: &mut Definition, other: Definition, report: &mut Report) {
+    let word = def.word.clone();
+    let mut conflict = |detail: String| {
+        report.conflicts.push(Conflict {
+            word: word.clone(),
+            detail,
+        })
+    };
+
+    match (def.frequency, other.frequency) {
+        (Some(kept), Some(dropped)) if kept != dropped => conflict(format!(
+            "frequency rank {} against {}; kept {}",
+            kept, dropped, kept
+        )),
+        (None, theirs) => def.frequency = theirs,
+        _ => {}
+    }
+    for pronunciation in other.pronunciations {
+        if !def.pronunciations.contains(&pronunciation) {
+            def.pronunciations.push(pronunciation);
+        }
+    }
+
+    for etymology in other.etymologies {
+        match def
+            .etymologies
+            .iter_mut()
+            .find(|ours| same_origin(ours, &etymology))
+        {
+            Some(ours) => {
+                for part in etymology.parts_of_speech {
+                    combine_part(ours, part, &mut conflict, &mut report.duplicate_senses);
+                }
+            }
+            None => def.etymologies.push(etymology),
+        }
+    }
+}
+
+/// Folds the senses of `part` into the same part of speech of `etymology`
+fn combine_part(
+    etymology: &mut Etymology,
+    part: PartOfSpeech,
+    conflict: &mut impl FnMut(String),
+    duplicates: &mut usize,
+) {
+    let Some(ours) = etymology
+        .parts_of_speech
+        .iter_mut()
+        .find(|ours| pos::matches(&ours.part_of_speech, &part.part_of_speech))
+    else {
+        etymology.parts_of_speech.push(part);
+        return;
+    };
+
+    for sense in part.senses {
+        let Some(same) = ours
+            .senses
+            .iter_mut()
+            .find(|ours| same_text(&ours.sense, &sense.sense))
+        else {
+            ours.senses.push(sense);
+            continue;
+        };
+        *duplicates += 1;
+        combine_sense(same, sense, conflict);
+    }
+}
+
+/// Keeps the examples, labels and related words of `other` that `sense`,
+/// worded the same, lacks
+fn combine_sense(sense: &mut Sense, other: Sense, conflict: &mut impl FnMut(String)) {
+    match (&sense.date, other.date) {
+        (Some(kept), Some(dropped)) if kept != &dropped => conflict(format!(
+            "\"{}\" dated {} against {}; kept {}",
+            sense.sense, kept, dropped, kept
+        )),
+        (None, theirs) => sense.date = theirs,
+        _ => {}
+    }
+    for (ours, theirs) in [
+        (&mut sense.labels, other.labels),
+        (&mut sense.examples, other.examples),
+        (&mut sense.synonyms, other.synonyms),
+        (&mut sense.antonyms, other.antonyms),
+    ] {
+        for item in theirs {
+            if !ours.contains(&item) {
+                ours.push(item);
+            }
+        }
+    }
+}
+
+/// Whether two etymologies tell the same story: the same origin, or neither
+/// giving one
+fn same_origin(a: &Etymology, b: &Etymology) -> bool {
+    match (&a.origin, &b.origin) {
+        (Some(a), Some(b)) => same_text(a, b),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+/// Whether two pieces of text say the same thing, ignoring case, spacing
+/// and a trailing full stop
+fn same_text(a: &str, b: &str) -> bool {
+    let words = |text: &str| -> Vec<String> {
+        text.trim()
+            .trim_end_matches('.')
+            .split_whitespace()
+            .map(str::to_lowercase)
+            .collect()
+    };
+    words(a) == words(b)
+}
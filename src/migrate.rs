@@ -0,0 +1,246 @@
+use crate::{
+    Dictionary, DictionaryFile,
+    archive::is_archive,
+    dictionary::{SHARD_EXTENSIONS, letter_dirs, normalize},
+    import::ShardWriter,
+    manifest::{self, FORMAT_VERSION, Manifest},
+    sharding::ShardScheme,
+};
+use anyhow::{Context, Result};
+use std::{
+    collections::{BTreeSet, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// What a migration did
+#[derive(Debug)]
+pub struct Report {
+    pub from_version: u32,
+    pub to_version: u32,
+    pub from_scheme: ShardScheme,
+    pub to_scheme: ShardScheme,
+    /// Headwords read, if any shards had to be rewritten
+    pub entries: usize,
+    /// Headwords that were renamed into NFC or moved to another shard
+    pub refiled: usize,
+}
+
+/// Upgrades the shard tree at `root` to the current format in place, one
+/// version at a time, and marks it with a manifest. Given a `scheme`, the
+/// tree is also re-sharded by it. A tree already in the current format and
+/// scheme is left as it is.
+pub fn migrate(root: &Path, scheme: Option<ShardScheme>) -> Result<Report> {
+    if is_archive(root) {
+        anyhow::bail!(
+            "{} is an archive; migrate the shard tree it was packed from and pack it again",
+            root.display()
+        );
+    }
+    if !root.is_dir() {
+        anyhow::bail!("{} is not a shard tree", root.display());
+    }
+    manifest::check(root)?;
+
+    let manifest = Manifest::read(root)?;
+    let mut report = Report {
+        from_version: manifest.format_version,
+        to_version: manifest.format_version,
+        from_scheme: manifest.shard_scheme,
+        to_scheme: scheme.unwrap_or(manifest.shard_scheme),
+        entries: 0,
+        refiled: 0,
+    };
+    let mut refiled = false;
+    while report.to_version < FORMAT_VERSION {
+        match report.to_version {
+            1 => {
+                refile(root, &mut report)?;
+                refiled = true;
+            }
+            // the manifest only gained the scheme, which it's written with below
+            2 => {}
+            version => anyhow::bail!("No migration from format version {}", version),
+        }
+        report.to_version += 1;
+    }
+    if !refiled && report.to_scheme != report.from_scheme {
+        refile(root, &mut report)?;
+    }
+    Manifest::current(report.to_scheme).write(root)?;
+
+    Ok(report)
+}
+
+/// Puts every headword in NFC and files it under the shard the new scheme
+/// says, combining entries that turn out to be the same word. This takes a
+/// version 1 tree to 2, and re-shards any tree. The new shards are written
+/// next to the tree first, then moved in over the old ones, so an
+/// interrupted migration leaves every entry readable.
+fn refile(root: &Path, report: &mut Report) -> Result<()> {
+    let dict = Dictionary::with_roots(vec![root.to_path_buf()]);
+    // a shard kept both plain and compressed is read once, the way lookups read it
+    let shards: BTreeSet<PathBuf> = shard_files(root)?
+        .into_iter()
+        .map(|(shard, _)| shard)
+        .collect();
+
+    let mut writer = ShardWriter::new().with_scheme(report.to_scheme);
+    for shard in shards {
+        let entries: DictionaryFile = serde_json::from_str(&dict.read_shard(&shard)?)
+            .with_context(|| format!("Failed to parse shard {}", shard.display()))?;
+        for (key, def) in entries {
+            report.entries += 1;
+            let word = normalize(&def.word);
+            if word != key || report.to_scheme.path(&word).as_deref() != Some(shard.as_path()) {
+                report.refiled += 1;
+            }
+            writer.insert(def);
+        }
+    }
+
+    let staging = root.with_extension("migrating");
+    let _ = fs::remove_dir_all(&staging);
+    // the staged tree is new, so it gets a manifest rather than being refused
+    writer.write(&staging)?;
+
+    let mut written = HashSet::new();
+    for (shard, file) in shard_files(&staging)? {
+        let target = root.join(&shard).with_extension("json");
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::rename(&file, &target)
+            .with_context(|| format!("Failed to move {} into place", target.display()))?;
+        written.insert(target);
+    }
+    for (_, file) in shard_files(root)? {
+        if !written.contains(&file) {
+            fs::remove_file(&file)?;
+        }
+    }
+    // letter folders the old layout needed that the new one doesn't
+    for entry in fs::read_dir(root)? {
+        let path = entry?.path();
+        if path.is_dir() && fs::read_dir(&path)?.next().is_none() {
+            fs::remove_dir(&path)?;
+        }
+    }
+    fs::remove_dir_all(&staging)?;
+
+    Ok(())
+}
+
+/// Every shard file under `root` as it's named on disk, compressed or not,
+/// with the shard it holds. Language trees inside it are left to be
+/// migrated on their own.
+fn shard_files(root: &Path) -> Result<Vec<(PathBuf, PathBuf)>> {
+    let mut files = Vec::new();
+    for dir in letter_dirs(root)? {
+        for file in fs::read_dir(dir.path())? {
+            let file = file?.path();
+            let name = file.file_name().unwrap_or_default().to_string_lossy();
+            if let Some(stem) = SHARD_EXTENSIONS
+                .iter()
+                .find_map(|ext| name.strip_suffix(&format!(".{}", ext)))
+            {
+                files.push((PathBuf::from(dir.file_name()).join(stem), file.clone()));
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::simple_definition;
+
+    /// Writes a shard the way a version 1 tree has it: keyed by headword as
+    /// given, without a manifest
+    fn v1_shard(root: &Path, shard: &str, words: &[&str]) {
+        let entries: DictionaryFile = words
+            .iter()
+            .map(|word| {
+                let def = simple_definition(word, "Noun", vec![format!("A {}", word)]);
+                (word.to_string(), def)
+            })
+            .collect();
+        let path = root.join(shard).with_extension("json");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, serde_json::to_string(&entries).unwrap()).unwrap();
+    }
+
+    /// The headword `word` finds in the tree at `root`
+    fn headword(root: &Path, word: &str) -> Option<String> {
+        let def = Dictionary::with_roots(vec![root.to_path_buf()])
+            .lookup(word)
+            .unwrap()?;
+        Some(def.word)
+    }
+
+    #[test]
+    fn round_trips_from_version_1_through_both_schemes() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().join("dict");
+        // a decomposed headword, which version 2 puts in NFC
+        v1_shard(&root, "c/ca", &["cat", "cafe\u{301}"]);
+        v1_shard(&root, "z/ze", &["zebra"]);
+
+        let report = migrate(&root, None).unwrap();
+        assert_eq!(
+            (report.from_version, report.to_version),
+            (1, FORMAT_VERSION)
+        );
+        assert_eq!((report.entries, report.refiled), (3, 1));
+        assert_eq!(
+            Manifest::read(&root).unwrap(),
+            Manifest::current(ShardScheme::default())
+        );
+        assert_eq!(headword(&root, "café").as_deref(), Some("café"));
+
+        let hash = ShardScheme::Hash { buckets: 16 };
+        let report = migrate(&root, Some(hash)).unwrap();
+        assert_eq!(report.to_scheme, hash);
+        assert_eq!(report.entries, 3);
+        assert!(!root.join("c").exists() && !root.join("z").exists());
+        let shards = Dictionary::with_roots(vec![root.clone()]).shards().unwrap();
+        assert!(
+            shards.iter().all(|shard| shard.starts_with("00")),
+            "{:?}",
+            shards
+        );
+
+        migrate(&root, Some(ShardScheme::default())).unwrap();
+        assert!(root.join("c/ca.json").is_file() && root.join("z/ze.json").is_file());
+        assert!(!root.join("00").exists());
+        for word in ["cat", "café", "zebra"] {
+            assert_eq!(headword(&root, word).as_deref(), Some(word));
+        }
+
+        // already current, so nothing is rewritten
+        let report = migrate(&root, None).unwrap();
+        assert_eq!(report.entries, 0);
+    }
+
+    #[test]
+    fn leaves_language_trees_inside_the_root_alone() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().join("dict");
+        v1_shard(&root, "c/ca", &["cat"]);
+        v1_shard(&root.join("es"), "h/ho", &["hola"]);
+        migrate(&root.join("es"), None).unwrap();
+
+        migrate(&root, None).unwrap();
+        assert!(root.join("es").join(manifest::FILE_NAME).is_file());
+        assert_eq!(headword(&root.join("es"), "hola").as_deref(), Some("hola"));
+        assert_eq!(headword(&root, "cat").as_deref(), Some("cat"));
+        assert_eq!(headword(&root, "hola"), None);
+
+        // and again under another scheme, which rewrites every shard of the root
+        migrate(&root, Some(ShardScheme::Hash { buckets: 4 })).unwrap();
+        assert!(root.join("es/h/ho.json").is_file());
+        assert_eq!(headword(&root.join("es"), "hola").as_deref(), Some("hola"));
+    }
+}
@@ -0,0 +1,219 @@
+use crate::{
+    Definition,
+    dictionary::{case_variants, check_lang, normalize},
+    paths::project_dirs,
+    store::Store,
+};
+use anyhow::{Context, Result};
+use std::{
+    collections::BTreeMap,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// Where the entries written with `dibble add` in language `lang` live: one
+/// JSON file per language in `user` in the user data dir
+pub fn default_path(lang: &str) -> Result<PathBuf> {
+    check_lang(lang)?;
+    let dirs = project_dirs().context("Could not determine the user data directory")?;
+    Ok(dirs.data_dir().join("user").join(format!("{}.json", lang)))
+}
+
+/// The user's own entries: jargon, names and anything else the installed
+/// dictionaries are missing or get wrong. Shaped like a shard, but kept in a
+/// single file since it's only ever a handful of words.
+#[derive(Debug, Default)]
+pub struct UserDictionary {
+    path: PathBuf,
+    entries: BTreeMap<String, Definition>,
+}
+
+impl UserDictionary {
+    /// Loads the user dictionary for `lang` at the default path
+    pub fn open(lang: &str) -> Result<Self> {
+        Self::open_at(&default_path(lang)?)
+    }
+
+    /// Loads the user dictionary at `path`. A missing file is an empty
+    /// dictionary; one that doesn't parse is an error, so saving can't wipe
+    /// out the entries.
+    pub fn open_at(path: &Path) -> Result<Self> {
+        let entries = match fs::read_to_string(path) {
+            Ok(contents) => serde_json::from_str(&contents)
+                .with_context(|| format!("Invalid user dictionary {}", path.display()))?,
+            Err(_) => BTreeMap::new(),
+        };
+        Ok(Self {
+            path: path.to_path_buf(),
+            entries,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The entry for `word`, trying the same spellings as a dictionary lookup
+    pub fn get(&self, word: &str) -> Option<&Definition> {
+        case_variants(&normalize(word))
+            .iter()
+            .find_map(|variant| self.entries.get(variant))
+    }
+
+    /// Adds `def` under its headword, replacing any entry already there.
+    /// Returns whether one was.
+    pub fn insert(&mut self, mut def: Definition) -> bool {
+        def.word = normalize(&def.word);
+        self.entries.insert(def.word.clone(), def).is_some()
+    }
+
+    /// Drops the entry for `word`, returning it if there was one
+    pub fn remove(&mut self, word: &str) -> Option<Definition> {
+        let key = self.get(word)?.word.clone();
+        self.entries.remove(&key)
+    }
+
+    /// Every headword, alphabetically
+    pub fn words(&self) -> impl Iterator<Item = &String> {
+        self.entries.keys()
+    }
+
+    /// Writes the entries back, replacing the file in one step so a crash
+    /// can't leave half of it behind
+    pub fn save(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let partial = self.path.with_extension("partial");
+        fs::write(&partial, serde_json::to_string_pretty(&self.entries)?)
+            .with_context(|| format!("Failed to write {}", partial.display()))?;
+        fs::rename(&partial, &self.path)
+            .with_context(|| format!("Failed to write {}", self.path.display()))
+    }
+}
+
+/// A store with the user's entries in front of it. A word in the user
+/// dictionary is answered from there alone, so an entry written over an
+/// installed one replaces it rather than being merged into it.
+pub struct Overlay {
+    user: UserDictionary,
+    inner: Box<dyn Store>,
+}
+
+impl Overlay {
+    pub fn new(user: UserDictionary, inner: Box<dyn Store>) -> Self {
+        Self { user, inner }
+    }
+}
+
+impl Store for Overlay {
+    fn lookup(&self, word: &str) -> Result<Option<Definition>> {
+        match self.user.get(word) {
+            Some(def) => Ok(Some(def.clone())),
+            None => self.inner.lookup(word),
+        }
+    }
+
+    fn lookup_many(&self, words: &[String]) -> Result<Vec<Option<Definition>>> {
+        let missing: Vec<String> = words
+            .iter()
+            .filter(|word| self.user.get(word).is_none())
+            .cloned()
+            .collect();
+        let mut found = self.inner.lookup_many(&missing)?.into_iter();
+
+        Ok(words
+            .iter()
+            .map(|word| match self.user.get(word) {
+                Some(def) => Some(def.clone()),
+                None => found.next().flatten(),
+            })
+            .collect())
+    }
+
+    fn prefix(&self, stem: &str) -> Result<Vec<String>> {
+        let mut words = self.inner.prefix(stem)?;
+        words.extend(
+            self.user
+                .words()
+                .filter(|word| word.starts_with(stem))
+                .cloned(),
+        );
+        words.sort();
+        words.dedup();
+        Ok(words)
+    }
+}
+
+/// Opens `path` in the user's editor, `$VISUAL` or `$EDITOR`, falling back to
+/// `vi`, and waits for it to be closed
+pub fn edit_file(path: &Path) -> Result<()> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+
+    // the editor may come with arguments of its own, like `code --wait`
+    #[cfg(unix)]
+    let mut cmd = {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c")
+            .arg(format!("{} \"$1\"", editor))
+            .arg("sh")
+            .arg(path);
+        cmd
+    };
+    #[cfg(not(unix))]
+    let mut cmd = {
+        let mut parts = editor.split_whitespace();
+        let mut cmd = Command::new(parts.next().unwrap_or("notepad"));
+        cmd.args(parts).arg(path);
+        cmd
+    };
+
+    let status = cmd
+        .status()
+        .with_context(|| format!("Could not run the editor {}", editor))?;
+    if !status.success() {
+        anyhow::bail!("The editor {} exited with {}", editor, status);
+    }
+    Ok(())
+}
+
+/// Opens `text` in the user's editor, like [`edit_file`], and returns it as
+/// saved. It goes through a new file in the temp dir that only the user can
+/// read, under a name nobody can guess, so nothing planted in a shared temp
+/// dir is written through; the file is removed however editing ends.
+pub fn edit_text(text: &str) -> Result<String> {
+    let name: String = std::iter::repeat_with(fastrand::alphanumeric)
+        .take(16)
+        .collect();
+    let path = std::env::temp_dir().join(format!("dibble-{}.json", name));
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options
+        .open(&path)
+        .with_context(|| format!("Failed to create {}", path.display()))?;
+    let temp = TempFile(path);
+    file.write_all(text.as_bytes())?;
+    drop(file);
+
+    edit_file(&temp.0)?;
+    fs::read_to_string(&temp.0).with_context(|| format!("Failed to read {}", temp.0.display()))
+}
+
+/// A file that's removed when this goes out of scope
+struct TempFile(PathBuf);
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
@@ -0,0 +1,70 @@
+use crate::render::{terminal_height, terminal_width};
+use anstream::{AutoStream, ColorChoice};
+use anyhow::Result;
+use std::{
+    io::{IsTerminal, Write},
+    process::{Command, Stdio},
+};
+
+/// Pager used when `$PAGER` isn't set; `-R` keeps the colors
+const DEFAULT_PAGER: &str = "less -R";
+
+/// Prints `text`, sending it through `$PAGER` instead when stdout is a
+/// terminal and the text wouldn't fit on one screen
+pub fn page(text: &str) -> Result<()> {
+    if !std::io::stdout().is_terminal() || fits(text) {
+        anstream::print!("{}", text);
+        return Ok(());
+    }
+
+    let pager = std::env::var("PAGER").unwrap_or_else(|_| DEFAULT_PAGER.to_string());
+    if pager.trim().is_empty() || pager.trim() == "cat" {
+        anstream::print!("{}", text);
+        return Ok(());
+    }
+
+    let Ok(mut child) = shell(&pager).stdin(Stdio::piped()).spawn() else {
+        // no usable pager is no reason to withhold the definition
+        anstream::print!("{}", text);
+        return Ok(());
+    };
+
+    // the pager's stdin isn't a terminal, so decide on color from our own stdout
+    let text = match AutoStream::choice(&std::io::stdout()) {
+        ColorChoice::Never => anstream::adapter::strip_str(text).to_string(),
+        _ => text.to_string(),
+    };
+    if let Some(mut stdin) = child.stdin.take() {
+        // the user quitting the pager early closes the pipe; that's not an error
+        let _ = stdin.write_all(text.as_bytes());
+    }
+    child.wait()?;
+
+    Ok(())
+}
+
+/// Whether `text` fits on the screen, counting lines that the terminal wraps
+fn fits(text: &str) -> bool {
+    let width = terminal_width().max(1);
+    let rows: usize = anstream::adapter::strip_str(text)
+        .to_string()
+        .lines()
+        .map(|line| line.chars().count().div_ceil(width).max(1))
+        .sum();
+    rows < terminal_height()
+}
+
+#[cfg(unix)]
+fn shell(command: &str) -> Command {
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c").arg(command);
+    cmd
+}
+
+#[cfg(not(unix))]
+fn shell(command: &str) -> Command {
+    let mut parts = command.split_whitespace();
+    let mut cmd = Command::new(parts.next().unwrap_or(command));
+    cmd.args(parts);
+    cmd
+}
@@ -0,0 +1,45 @@
+use directories::ProjectDirs;
+use std::path::{Path, PathBuf};
+
+/// The per-user directories dibble stores its data, config and cache in
+pub fn project_dirs() -> Option<ProjectDirs> {
+    ProjectDirs::from("com.taranathan.dibble", "taran", "dibble")
+}
+
+/// The machine-wide directories a system installation of dibble is read
+/// from, shared by every user: `/usr/share/dibble` on Linux and other Unixes,
+/// `/Library/Application Support/dibble` on macOS and `%ProgramData%\dibble`
+/// on Windows
+#[derive(Debug, Clone)]
+pub struct SystemPaths {
+    data_dir: PathBuf,
+}
+
+impl SystemPaths {
+    /// The directories for the platform dibble was built for
+    pub fn new() -> Self {
+        let base = if cfg!(windows) {
+            std::env::var_os("ProgramData")
+                .filter(|dir| !dir.is_empty())
+                .map_or_else(|| PathBuf::from(r"C:\ProgramData"), PathBuf::from)
+        } else if cfg!(target_os = "macos") {
+            PathBuf::from("/Library/Application Support")
+        } else {
+            PathBuf::from("/usr/share")
+        };
+        Self {
+            data_dir: base.join("dibble"),
+        }
+    }
+
+    /// Where a system installation keeps its dictionaries
+    pub fn data_dir(&self) -> &Path {
+        &self.data_dir
+    }
+}
+
+impl Default for SystemPaths {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,115 @@
+/// Senses whose use ended before this year are historical as far as
+/// `--modern` is concerned
+pub const MODERN_YEAR: i32 = 1900;
+
+/// The years a sense was in use, as far as its date says
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Period {
+    /// The first year it's attested
+    pub start: i32,
+    /// The last year it was in use, or `None` if it still is
+    pub end: Option<i32>,
+}
+
+impl Period {
+    /// Reads the free-form dates Wiktionary gives senses: `from 16th c.`,
+    /// `14th–17th c.`, `from mid-19th c.`, `First attested around 1350 to
+    /// 1470.`, `from 1920s`. A sense only counts as out of use when its date
+    /// is a span that doesn't start with "from" or "first attested". `None`
+    /// when there's no year or century in it.
+    pub fn parse(date: &str) -> Option<Self> {
+        let text = strip_tags(date).to_lowercase();
+        let mut points: Vec<(i32, i32)> = Vec::new();
+        let mut part = Part::Whole;
+        for token in text.split(|c: char| !c.is_alphanumeric()) {
+            match token {
+                "early" => part = Part::Early,
+                "mid" => part = Part::Mid,
+                "late" => part = Part::Late,
+                _ => {
+                    if let Some(point) = century(token, part).or_else(|| year(token)) {
+                        points.push(point);
+                    }
+                    if !token.is_empty() {
+                        part = Part::Whole;
+                    }
+                }
+            }
+        }
+
+        let (start, _) = *points.first()?;
+        let open = text.starts_with("from")
+            || text.starts_with("since")
+            || text.starts_with("first attested");
+        let end = match points.last() {
+            Some((_, end)) if !open && points.len() > 1 => Some(*end),
+            _ => None,
+        };
+        Some(Self { start, end })
+    }
+
+    /// Whether the sense was still in use in or after `year`
+    pub fn in_use_since(&self, year: i32) -> bool {
+        self.end.is_none_or(|end| end >= year)
+    }
+
+    /// Whether the sense was in use before `year`
+    pub fn in_use_before(&self, year: i32) -> bool {
+        self.start < year
+    }
+}
+
+/// Which part of a century a date means
+#[derive(Clone, Copy)]
+enum Part {
+    Whole,
+    Early,
+    Mid,
+    Late,
+}
+
+/// The first and last year of `token` if it's a century like `17th`
+fn century(token: &str, part: Part) -> Option<(i32, i32)> {
+    let digits = token
+        .strip_suffix("th")
+        .or_else(|| token.strip_suffix("st"))
+        .or_else(|| token.strip_suffix("nd"))
+        .or_else(|| token.strip_suffix("rd"))?;
+    let century: i32 = digits.parse().ok().filter(|c| (1..=21).contains(c))?;
+    let first = (century - 1) * 100;
+    Some(match part {
+        Part::Whole => (first, first + 99),
+        Part::Early => (first, first + 32),
+        Part::Mid => (first + 33, first + 66),
+        Part::Late => (first + 67, first + 99),
+    })
+}
+
+/// The first and last year of `token` if it's a year like `1350` or a
+/// decade like `1920s`
+fn year(token: &str) -> Option<(i32, i32)> {
+    let (digits, span) = match token.strip_suffix('s') {
+        Some(decade) => (decade, 9),
+        None => (token, 0),
+    };
+    if !(3..=4).contains(&digits.len()) {
+        return None;
+    }
+    let year: i32 = digits.parse().ok()?;
+    Some((year, year + span))
+}
+
+/// `text` without any HTML tags, which some dates use for superscripts
+fn strip_tags(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut in_tag = false;
+    for c in text.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            _ if !in_tag => out.push(c),
+            _ => {}
+        }
+    }
+    out
+}
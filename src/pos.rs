@@ -0,0 +1,56 @@
+/// The parts of speech dibble knows by name, with the abbreviations
+/// dictionaries and users write them as
+const PARTS_OF_SPEECH: &[(&str, &[&str])] = &[
+    ("noun", &["n", "nn"]),
+    ("proper noun", &["name", "prop", "propn"]),
+    ("verb", &["v", "vb", "vt", "vi"]),
+    ("adjective", &["a", "adj", "jj"]),
+    ("adverb", &["adv", "r", "rb"]),
+    ("pronoun", &["pron", "prn"]),
+    ("preposition", &["prep"]),
+    ("conjunction", &["conj", "cnj"]),
+    ("interjection", &["int", "interj", "intj"]),
+    ("determiner", &["det"]),
+    ("article", &["art"]),
+    ("numeral", &["num"]),
+    ("particle", &["part", "prt"]),
+    ("abbreviation", &["abbr", "abbrev"]),
+    ("phrase", &["phr"]),
+    ("prefix", &["pref"]),
+    ("suffix", &["suf", "suff"]),
+];
+
+/// The full name of a part of speech given as an abbreviation (`adj`), a
+/// prefix of the name (`prep`) or with a slip of a letter (`nuon`),
+/// lowercased. Anything else comes back lowercased as it was.
+pub fn canonical(name: &str) -> String {
+    let name = name.trim().trim_end_matches('.').to_lowercase();
+    if let Some((full, _)) = PARTS_OF_SPEECH
+        .iter()
+        .find(|(full, short)| *full == name || short.contains(&name.as_str()))
+    {
+        return full.to_string();
+    }
+
+    // only go by a prefix or a typo when exactly one name fits
+    let unique = |fits: &dyn Fn(&str) -> bool| {
+        let mut found = PARTS_OF_SPEECH
+            .iter()
+            .map(|(full, _)| *full)
+            .filter(|full| fits(full));
+        match (found.next(), found.next()) {
+            (Some(full), None) => Some(full),
+            _ => None,
+        }
+    };
+    let len = name.chars().count();
+    unique(&|full| len >= 3 && full.starts_with(&name))
+        .or_else(|| unique(&|full| len >= 4 && strsim::damerau_levenshtein(&name, full) == 1))
+        .map_or(name, str::to_string)
+}
+
+/// Whether the part of speech `pos` from an entry is the one the user asked
+/// for with `query`, however either is spelled
+pub fn matches(query: &str, pos: &str) -> bool {
+    canonical(query) == canonical(pos)
+}
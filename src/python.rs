@@ -0,0 +1,60 @@
+use crate::{Dictionary, dictionary::DEFAULT_LANG};
+use pyo3::{exceptions::PyRuntimeError, prelude::*};
+use std::path::PathBuf;
+
+/// A dictionary, for Python:
+///
+/// ```python
+/// import dibble
+///
+/// d = dibble.Dictionary()
+/// d.lookup("cat")["etymologies"][0]["partsOfSpeech"][0]["senses"][0]["sense"]
+/// d.search("cat")
+/// ```
+#[pyclass(name = "Dictionary", module = "dibble", frozen)]
+pub struct PyDictionary(Dictionary);
+
+#[pymethods]
+impl PyDictionary {
+    /// Opens the installed dictionaries for `lang`, or the shard tree or
+    /// `.dibble` archive at `path` if one is given
+    #[new]
+    #[pyo3(signature = (path = None, lang = DEFAULT_LANG))]
+    fn new(path: Option<PathBuf>, lang: &str) -> PyResult<Self> {
+        let dict = match path {
+            Some(path) => Dictionary::with_roots(vec![path]),
+            None => Dictionary::open_lang(lang).map_err(to_py)?,
+        };
+        Ok(Self(dict))
+    }
+
+    /// The entry for `word` as a dict shaped like an entry in a shard, or
+    /// `None` if it isn't in the dictionary
+    fn lookup<'py>(&self, py: Python<'py>, word: &str) -> PyResult<Option<Bound<'py, PyAny>>> {
+        let json = py.detach(|| -> anyhow::Result<_> {
+            match self.0.lookup(word)? {
+                Some(def) => Ok(Some(serde_json::to_string(&def)?)),
+                None => Ok(None),
+            }
+        });
+        match json.map_err(to_py)? {
+            Some(json) => Ok(Some(py.import("json")?.call_method1("loads", (json,))?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Every headword beginning with `prefix`, sorted
+    fn search(&self, py: Python<'_>, prefix: &str) -> PyResult<Vec<String>> {
+        py.detach(|| self.0.prefix(prefix)).map_err(to_py)
+    }
+}
+
+fn to_py(e: anyhow::Error) -> PyErr {
+    PyRuntimeError::new_err(format!("{:#}", e))
+}
+
+/// The `dibble` Python module
+#[pymodule]
+fn dibble(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyDictionary>()
+}
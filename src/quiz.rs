@@ -0,0 +1,92 @@
+use crate::{
+    Definition, Dictionary,
+    search::{headwords, take_random},
+};
+use anyhow::Result;
+use regex::RegexBuilder;
+
+/// One multiple-choice question: a definition and the headwords to pick from
+#[derive(Debug, Clone)]
+pub struct Question {
+    /// The headword being asked for
+    pub word: String,
+    /// Its first sense, with the word itself blanked out
+    pub clue: String,
+    /// The headwords to choose from, the answer among them
+    pub choices: Vec<String>,
+}
+
+impl Question {
+    /// A question asking for `def`, with up to `choices` headwords to pick
+    /// from. The wrong ones come from the same shard where it has enough
+    /// words, since words that look alike make for a harder quiz. `None` if
+    /// the entry has no sense to show.
+    pub fn new(dict: &Dictionary, def: &Definition, choices: usize) -> Result<Option<Self>> {
+        let Some(clue) = clue(def) else {
+            return Ok(None);
+        };
+
+        let is_other = |word: &String| !word.eq_ignore_ascii_case(&def.word);
+        let mut pool: Vec<String> = match dict.shard_path(&def.word) {
+            Some(shard) => dict.shard_words(&shard).unwrap_or_default(),
+            None => Vec::new(),
+        };
+        pool.retain(is_other);
+        let wanted = choices.saturating_sub(1);
+        let mut options = Vec::new();
+        draw(&mut pool, &mut options, wanted);
+        if options.len() < wanted {
+            let mut pool = headwords(dict)?;
+            pool.retain(|word| is_other(word) && !options.contains(word));
+            draw(&mut pool, &mut options, wanted);
+        }
+
+        let at = fastrand::usize(..=options.len());
+        options.insert(at, def.word.clone());
+        Ok(Some(Self {
+            word: def.word.clone(),
+            clue,
+            choices: options,
+        }))
+    }
+
+    /// The position of the answer among the choices
+    pub fn answer(&self) -> usize {
+        self.choices
+            .iter()
+            .position(|choice| *choice == self.word)
+            .unwrap_or_default()
+    }
+}
+
+/// Moves random words from `pool` into `options` until it has `wanted`,
+/// skipping any spelled the same as one already there apart from case
+fn draw(pool: &mut Vec<String>, options: &mut Vec<String>, wanted: usize) {
+    while options.len() < wanted
+        && let Some(word) = take_random(pool)
+    {
+        if !options.iter().any(|o| o.eq_ignore_ascii_case(&word)) {
+            options.push(word);
+        }
+    }
+}
+
+/// The first sense of `def` and its part of speech, with the headword and
+/// anything starting with it (plurals, past tenses) blanked out so the clue
+/// doesn't give the answer away
+pub fn clue(def: &Definition) -> Option<String> {
+    let (pos, sense) = def
+        .etymologies
+        .iter()
+        .flat_map(|etym| &etym.parts_of_speech)
+        .find_map(|pos| Some((&pos.part_of_speech, pos.senses.first()?)))?;
+    let blank = RegexBuilder::new(&format!(r"\b{}\w*", regex::escape(&def.word)))
+        .case_insensitive(true)
+        .build()
+        .ok()?;
+    Some(format!(
+        "({}) {}",
+        pos,
+        blank.replace_all(&sense.sense, "___")
+    ))
+}
@@ -0,0 +1,175 @@
+use crate::{Definition, Etymology, import::simple_definition, server::dict::DEFAULT_PORT};
+use anyhow::{Context, Result};
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::TcpStream,
+    time::Duration,
+};
+
+/// How long to wait on a DICT server before giving up on it
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A DICT protocol (RFC 2229) server to ask for words missing locally
+#[derive(Debug, Clone)]
+pub struct DictClient {
+    host: String,
+    port: u16,
+    database: String,
+}
+
+impl DictClient {
+    /// Parses a `dict://host[:port][/database]` URL. Without a database every
+    /// database on the server is searched.
+    pub fn parse(url: &str) -> Result<Self> {
+        let rest = url
+            .strip_prefix("dict://")
+            .with_context(|| format!("'{}' is not a dict:// URL", url))?;
+        let (authority, database) = rest.split_once('/').unwrap_or((rest, ""));
+
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (
+                host,
+                port.parse()
+                    .with_context(|| format!("Invalid port in '{}'", url))?,
+            ),
+            None => (authority, DEFAULT_PORT),
+        };
+        if host.is_empty() {
+            anyhow::bail!("'{}' has no host", url);
+        }
+
+        Ok(Self {
+            host: host.to_string(),
+            port,
+            database: match database.trim_matches('/') {
+                "" => String::from("*"),
+                database => database.to_string(),
+            },
+        })
+    }
+
+    /// Asks the server to define `word`, merging every database's answer into
+    /// one entry with an etymology per answer
+    pub fn lookup(&self, word: &str) -> Result<Option<Definition>> {
+        let stream = TcpStream::connect((self.host.as_str(), self.port))
+            .with_context(|| format!("Failed to connect to {}:{}", self.host, self.port))?;
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        let mut reader = BufReader::new(&stream);
+        let mut writer = &stream;
+
+        expect(&mut reader, "220")?;
+        write!(
+            writer,
+            "DEFINE {} \"{}\"\r\n",
+            self.database,
+            word.replace('\\', "\\\\").replace('"', "\\\"")
+        )?;
+
+        let status = read_line(&mut reader)?;
+        if status.starts_with("552") {
+            let _ = writer.write_all(b"QUIT\r\n");
+            return Ok(None);
+        }
+        if !status.starts_with("150") {
+            anyhow::bail!("DICT server refused the lookup: {}", status);
+        }
+
+        let mut answers = Vec::new();
+        loop {
+            let line = read_line(&mut reader)?;
+            if line.starts_with("250") {
+                break;
+            }
+            if !line.starts_with("151") {
+                anyhow::bail!("Unexpected reply from DICT server: {}", line);
+            }
+            answers.push(read_text(&mut reader)?);
+        }
+        let _ = writer.write_all(b"QUIT\r\n");
+
+        Ok(normalize(word, &answers))
+    }
+}
+
+fn read_line(reader: &mut impl BufRead) -> Result<String> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        anyhow::bail!("DICT server closed the connection");
+    }
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+fn expect(reader: &mut impl BufRead, code: &str) -> Result<()> {
+    let line = read_line(reader)?;
+    if !line.starts_with(code) {
+        anyhow::bail!("Unexpected reply from DICT server: {}", line);
+    }
+    Ok(())
+}
+
+/// Reads a dot-terminated text block, undoing dot-stuffing
+fn read_text(reader: &mut impl BufRead) -> Result<String> {
+    let mut text = String::new();
+    loop {
+        let line = read_line(reader)?;
+        if line == "." {
+            return Ok(text);
+        }
+        text.push_str(line.strip_prefix('.').unwrap_or(&line));
+        text.push('\n');
+    }
+}
+
+/// Turns free-form DICT answers into an entry. Servers don't agree on a
+/// layout, so each paragraph (or numbered item) becomes a sense, with the
+/// headword line most databases start with dropped.
+fn normalize(word: &str, answers: &[String]) -> Option<Definition> {
+    let etymologies: Vec<Etymology> = answers
+        .iter()
+        .map(|text| senses(word, text))
+        .filter(|senses| !senses.is_empty())
+        .flat_map(|senses| simple_definition(word, "Definition", senses).etymologies)
+        .collect();
+
+    (!etymologies.is_empty()).then(|| Definition {
+        word: word.to_string(),
+        etymologies,
+        pronunciations: Vec::new(),
+        frequency: None,
+    })
+}
+
+fn senses(word: &str, text: &str) -> Vec<String> {
+    let mut lines = text.lines().peekable();
+    if lines
+        .peek()
+        .is_some_and(|first| first.trim().eq_ignore_ascii_case(word))
+    {
+        lines.next();
+    }
+
+    let mut senses = Vec::new();
+    let mut current = String::new();
+    for line in lines {
+        let line = line.trim();
+        let numbered = line
+            .split_once([':', '.'])
+            .filter(|(n, _)| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()));
+        let line = numbered.map_or(line, |(_, rest)| rest.trim());
+
+        if (line.is_empty() || numbered.is_some()) && !current.is_empty() {
+            senses.push(std::mem::take(&mut current));
+        }
+        if !line.is_empty() {
+            if !current.is_empty() {
+                current.push(' ');
+            }
+            current.push_str(line);
+        }
+    }
+    if !current.is_empty() {
+        senses.push(current);
+    }
+
+    senses
+}
@@ -0,0 +1,3 @@
+pub mod dict;
+#[cfg(feature = "net")]
+pub mod online;
@@ -0,0 +1,181 @@
+use crate::{
+    Definition, Dictionary, Etymology, PartOfSpeech, Pronunciation, Sense, import::ShardWriter,
+    paths::project_dirs,
+};
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// The free dictionary API queried by `--online`; the word is appended to it
+pub const DEFAULT_API: &str = "https://api.dictionaryapi.dev/api/v2/entries/en/";
+
+/// One entry of the API response; a word may come back as several
+#[derive(Debug, Deserialize)]
+struct ApiEntry {
+    #[serde(default)]
+    origin: Option<String>,
+    #[serde(default)]
+    phonetics: Vec<ApiPhonetic>,
+    #[serde(default)]
+    meanings: Vec<ApiMeaning>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiPhonetic {
+    #[serde(default)]
+    text: Option<String>,
+    #[serde(default)]
+    audio: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ApiMeaning {
+    #[serde(default)]
+    part_of_speech: String,
+    #[serde(default)]
+    definitions: Vec<ApiDefinition>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiDefinition {
+    definition: String,
+    #[serde(default)]
+    example: Option<String>,
+    #[serde(default)]
+    synonyms: Vec<String>,
+    #[serde(default)]
+    antonyms: Vec<String>,
+}
+
+/// Where words fetched online are kept: a shard tree of their own, so they
+/// never shadow the shards of an installed dictionary
+pub fn saved_dir() -> Result<PathBuf> {
+    let dirs = project_dirs().context("Could not determine the user data directory")?;
+    Ok(dirs.data_dir().join("online"))
+}
+
+/// An HTTP dictionary API to ask for words missing locally. Every answer is
+/// saved, so each word only ever goes over the network once.
+#[derive(Debug, Clone)]
+pub struct OnlineClient {
+    api: String,
+    saved: PathBuf,
+}
+
+impl OnlineClient {
+    pub fn new(api: &str, saved: &Path) -> Self {
+        Self {
+            api: api.to_string(),
+            saved: saved.to_path_buf(),
+        }
+    }
+
+    /// Queries [`DEFAULT_API`], saving into [`saved_dir`]
+    pub fn open() -> Result<Self> {
+        Ok(Self::new(DEFAULT_API, &saved_dir()?))
+    }
+
+    /// Returns a saved answer for `word`, or asks the API and saves its answer
+    pub fn lookup(&self, word: &str) -> Result<Option<Definition>> {
+        if let Some(def) = self.saved(word)? {
+            return Ok(Some(def));
+        }
+
+        let Some(def) = self.fetch(word)? else {
+            return Ok(None);
+        };
+
+        let mut writer = ShardWriter::new();
+        writer.insert(def.clone());
+        writer.write(&self.saved)?;
+
+        Ok(Some(def))
+    }
+
+    fn saved(&self, word: &str) -> Result<Option<Definition>> {
+        let saved = Dictionary::with_roots(vec![self.saved.clone()]);
+        let Some(shard) = saved.shard_path(word) else {
+            return Ok(None);
+        };
+        if !self.saved.join(&shard).with_extension("json").is_file() {
+            return Ok(None);
+        }
+
+        saved.lookup(word)
+    }
+
+    fn fetch(&self, word: &str) -> Result<Option<Definition>> {
+        let url = format!("{}{}", self.api, word);
+        let body = match ureq::get(&url).call() {
+            Ok(mut response) => response.body_mut().read_to_string()?,
+            Err(ureq::Error::StatusCode(404)) => return Ok(None),
+            Err(e) => return Err(e).with_context(|| format!("Failed to query {}", url)),
+        };
+
+        let entries: Vec<ApiEntry> = serde_json::from_str(&body)
+            .with_context(|| format!("Unexpected response from {}", url))?;
+        Ok(convert(word, entries))
+    }
+}
+
+/// Maps the API's entries onto etymologies and its meanings onto parts of speech
+fn convert(word: &str, entries: Vec<ApiEntry>) -> Option<Definition> {
+    let mut pronunciations: Vec<Pronunciation> = Vec::new();
+    for phonetic in entries.iter().flat_map(|entry| &entry.phonetics) {
+        let pronunciation = Pronunciation {
+            ipa: phonetic.text.clone().unwrap_or_default(),
+            accent: None,
+            audio: phonetic.audio.clone().filter(|url| !url.is_empty()),
+        };
+        if (!pronunciation.ipa.is_empty() || pronunciation.audio.is_some())
+            && !pronunciations.contains(&pronunciation)
+        {
+            pronunciations.push(pronunciation);
+        }
+    }
+
+    let etymologies: Vec<Etymology> = entries
+        .into_iter()
+        .map(|entry| Etymology {
+            parts_of_speech: entry
+                .meanings
+                .into_iter()
+                .filter(|meaning| !meaning.definitions.is_empty())
+                .map(|meaning| PartOfSpeech {
+                    part_of_speech: capitalize(&meaning.part_of_speech),
+                    senses: meaning
+                        .definitions
+                        .into_iter()
+                        .map(|def| Sense {
+                            sense: def.definition,
+                            date: None,
+                            labels: Vec::new(),
+                            examples: def.example.into_iter().collect(),
+                            synonyms: def.synonyms,
+                            antonyms: def.antonyms,
+                        })
+                        .collect(),
+                })
+                .collect(),
+            origin: entry.origin.filter(|origin| !origin.is_empty()),
+            source: None,
+        })
+        .filter(|etymology: &Etymology| !etymology.parts_of_speech.is_empty())
+        .collect();
+
+    (!etymologies.is_empty()).then(|| Definition {
+        word: word.to_string(),
+        etymologies,
+        pronunciations,
+        frequency: None,
+    })
+}
+
+fn capitalize(pos: &str) -> String {
+    let mut chars = pos.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::from("Definition"),
+    }
+}
@@ -0,0 +1,577 @@
+use crate::{schema::Definition, theme::Theme};
+use anstyle::Style;
+use color_print::cformat;
+
+/// Like `color_print::cprintln!`, but the color codes are stripped when color
+/// is off: when stdout isn't a terminal, `NO_COLOR` is set or `--color never`
+/// was given
+#[macro_export]
+macro_rules! cprintln {
+    () => {
+        $crate::render::__anstream::println!()
+    };
+    ($($arg:tt)*) => {
+        $crate::render::__anstream::println!("{}", $crate::render::__cformat!($($arg)*))
+    };
+}
+
+#[doc(hidden)]
+pub use anstream as __anstream;
+#[doc(hidden)]
+pub use color_print::cformat as __cformat;
+
+impl Definition {
+    pub fn print_colored(&self, examples: bool) {
+        anstream::print!("{}", self.to_colored(examples));
+    }
+
+    /// Renders the entry with the color codes [`Definition::print_colored`] prints
+    pub fn to_colored(&self, examples: bool) -> String {
+        self.to_themed(examples, &Theme::default())
+    }
+
+    /// The headword and its first sense on one line, like `run (verb) To move swiftly on foot.`
+    pub fn to_short(&self, theme: &Theme) -> String {
+        let mut out = paint(theme.word, &self.word);
+        let first = self
+            .etymologies
+            .iter()
+            .flat_map(|etym| &etym.parts_of_speech)
+            .find_map(|pos| Some((&pos.part_of_speech, pos.senses.first()?)));
+        if let Some((pos, sense)) = first {
+            out += &format!(
+                " {} {}",
+                paint(theme.part_of_speech, &format!("({})", pos)),
+                sense.sense
+            );
+        }
+        out.push('\n');
+        out
+    }
+
+    /// Renders the entry like [`Definition::to_colored`], in the colors of `theme`
+    pub fn to_themed(&self, examples: bool, theme: &Theme) -> String {
+        let width = wrap_width();
+
+        //header
+        let mut out = paint(theme.word, &self.word);
+        if let Some(marker) = self.commonness() {
+            out += &format!("  {}", paint(theme.example, marker));
+        }
+        out.push('\n');
+        if let Some(line) = self.pronunciation_line() {
+            for line in wrap(&line, 2, width) {
+                out += &format!("  {}\n", paint(theme.example, &line));
+            }
+        }
+
+        for (etym_idx, etymology) in self.etymologies.iter().enumerate() {
+            if let Some(heading) = self.etymology_heading(etym_idx) {
+                out += &format!("{}\n", paint(theme.etymology, &format!("{}:", heading)));
+            }
+            if let Some(origin) = &etymology.origin {
+                for line in wrap(origin, 2, width) {
+                    out += &format!("  {}\n", paint(theme.origin, &line));
+                }
+            }
+
+            for pos in &etymology.parts_of_speech {
+                out += &format!("  {}\n", paint(theme.part_of_speech, &pos.part_of_speech));
+
+                for (sense_idx, sense) in pos.senses.iter().enumerate() {
+                    let number = format!("{}.", sense_idx + 1);
+                    // continuation lines hang under the start of the sense text
+                    let indent = 4 + number.chars().count() + 1;
+                    let mut lines = wrap(&sense.sense, indent, width).into_iter();
+                    out += &format!(
+                        "    {} {}\n",
+                        paint(theme.number, &number),
+                        lines.next().unwrap_or_default()
+                    );
+                    for line in lines {
+                        out += &format!("{:indent$}{}\n", "", line);
+                    }
+
+                    if let Some(date) = &sense.date
+                        && !date.is_empty()
+                    {
+                        for line in wrap(&format!("[{}]", date), 7, width) {
+                            out += &format!("       {}\n", paint(theme.date, &line));
+                        }
+                    }
+
+                    if examples {
+                        for example in &sense.examples {
+                            let quoted = format!("\"{}\"", example);
+                            for (idx, line) in wrap(&quoted, 8, width).into_iter().enumerate() {
+                                // the quote mark hangs out to the left of the wrapped lines
+                                let pad = if idx == 0 { 7 } else { 8 };
+                                out += &format!("{:pad$}{}\n", "", paint(theme.example, &line));
+                            }
+                        }
+                    }
+
+                    for (label, words) in sense.relations() {
+                        let label = format!("{}:", label);
+                        // the words hang under the first one, past the label
+                        let indent = 7 + label.chars().count() + 1;
+                        let mut lines = wrap(&words, indent, width).into_iter();
+                        out += &format!(
+                            "       {} {}\n",
+                            paint(theme.number, &label),
+                            lines.next().unwrap_or_default()
+                        );
+                        for line in lines {
+                            out += &format!("{:indent$}{}\n", "", line);
+                        }
+                    }
+                }
+                out.push('\n');
+            }
+        }
+
+        out
+    }
+}
+
+/// Wraps `text` in the escape codes for `style`
+fn paint(style: Style, text: &str) -> String {
+    format!("{}{}{}", style.render(), text, style.render_reset())
+}
+
+/// Height of the terminal in rows, from `$LINES`, the terminal itself or a
+/// default of 24
+pub fn terminal_height() -> usize {
+    std::env::var("LINES")
+        .ok()
+        .and_then(|lines| lines.parse().ok())
+        .or_else(|| terminal_size::terminal_size().map(|(_, height)| height.0 as usize))
+        .unwrap_or(24)
+}
+
+/// Width of the terminal in columns, from `$COLUMNS`, the terminal itself or
+/// a default of 80
+pub fn terminal_width() -> usize {
+    wrap_width().unwrap_or(80)
+}
+
+/// The width entries are wrapped to. Output that isn't going to a terminal is
+/// left unwrapped unless `$COLUMNS` asks for a width.
+fn wrap_width() -> Option<usize> {
+    std::env::var("COLUMNS")
+        .ok()
+        .and_then(|cols| cols.parse().ok())
+        .or_else(|| terminal_size::terminal_size().map(|(width, _)| width.0 as usize))
+}
+
+/// Breaks `text` into lines that fit in `width` columns once indented by
+/// `indent`, splitting at spaces. Words longer than a line are left whole.
+fn wrap(text: &str, indent: usize, width: Option<usize>) -> Vec<String> {
+    // too narrow a terminal to indent into is treated as no wrapping at all
+    let Some(room) = width
+        .and_then(|width| width.checked_sub(indent))
+        .filter(|&room| room >= 20)
+    else {
+        return vec![text.to_string()];
+    };
+
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    let mut len = 0;
+    for word in text.split_whitespace() {
+        let word_len = word.chars().count();
+        if len > 0 && len + 1 + word_len > room {
+            lines.push(std::mem::take(&mut line));
+            len = 0;
+        }
+        if len > 0 {
+            line.push(' ');
+            len += 1;
+        }
+        line.push_str(word);
+        len += word_len;
+    }
+    lines.push(line);
+
+    lines
+}
+
+/// Prints words in left-aligned columns filling the terminal width
+pub fn print_columns(words: &[String]) {
+    let Some(longest) = words.iter().map(|w| w.chars().count()).max() else {
+        return;
+    };
+    let width = longest + 2;
+    let columns = (terminal_width() / width).max(1);
+
+    for row in words.chunks(columns) {
+        let line: String = row.iter().map(|w| format!("{:width$}", w)).collect();
+        println!("{}", line.trim_end());
+    }
+}
+
+/// Prints a dim rule used between entries when defining several words
+pub fn print_separator() {
+    anstream::println!("{}", separator());
+}
+
+/// The rule printed by [`print_separator`]
+pub fn separator() -> String {
+    cformat!("<dim>{}</dim>", "─".repeat(terminal_width().min(40)))
+}
+
+impl Definition {
+    /// Renders the entry as Markdown: the headword as a heading, parts of speech
+    /// as subheadings and senses as ordered lists
+    pub fn to_markdown(&self, examples: bool) -> String {
+        let mut out = format!("## {}\n\n", self.word);
+        if let Some(marker) = self.commonness() {
+            out.push_str(&format!("_{}_\n\n", marker));
+        }
+        if let Some(line) = self.pronunciation_line() {
+            out.push_str(&format!("{}\n\n", line));
+        }
+        let pos_heading = if self.etymology_heading(0).is_some() {
+            "####"
+        } else {
+            "###"
+        };
+
+        for (etym_idx, etymology) in self.etymologies.iter().enumerate() {
+            if let Some(heading) = self.etymology_heading(etym_idx) {
+                out.push_str(&format!("### {}\n\n", heading));
+            }
+            if let Some(origin) = &etymology.origin {
+                out.push_str(&format!("*{}*\n\n", origin));
+            }
+
+            for pos in &etymology.parts_of_speech {
+                out.push_str(&format!("{} {}\n\n", pos_heading, pos.part_of_speech));
+
+                for (sense_idx, sense) in pos.senses.iter().enumerate() {
+                    out.push_str(&format!("{}. {}\n", sense_idx + 1, sense.sense));
+
+                    if let Some(date) = &sense.date
+                        && !date.is_empty()
+                    {
+                        out.push_str(&format!("   *[{}]*\n", date));
+                    }
+
+                    if examples {
+                        for example in &sense.examples {
+                            out.push_str(&format!("   > {}\n", example));
+                        }
+                    }
+
+                    for (label, words) in sense.relations() {
+                        out.push_str(&format!("   *{}:* {}\n", label, words));
+                    }
+                }
+                out.push('\n');
+            }
+        }
+
+        out
+    }
+}
+
+impl Definition {
+    /// Renders the entry as plain text, laid out like [`Definition::print_colored`]
+    pub fn to_text(&self, examples: bool) -> String {
+        let mut out = self.word.clone();
+        if let Some(marker) = self.commonness() {
+            out.push_str(&format!("  {}", marker));
+        }
+        out.push('\n');
+        if let Some(line) = self.pronunciation_line() {
+            out.push_str(&format!("  {}\n", line));
+        }
+
+        for (etym_idx, etymology) in self.etymologies.iter().enumerate() {
+            if let Some(heading) = self.etymology_heading(etym_idx) {
+                out.push_str(&format!("{}:\n", heading));
+            }
+            if let Some(origin) = &etymology.origin {
+                out.push_str(&format!("  {}\n", origin));
+            }
+
+            for pos in &etymology.parts_of_speech {
+                out.push_str(&format!("  {}\n", pos.part_of_speech));
+
+                for (sense_idx, sense) in pos.senses.iter().enumerate() {
+                    out.push_str(&format!("    {}. {}\n", sense_idx + 1, sense.sense));
+
+                    if let Some(date) = &sense.date
+                        && !date.is_empty()
+                    {
+                        out.push_str(&format!("       [{}]\n", date));
+                    }
+
+                    if examples {
+                        for example in &sense.examples {
+                            out.push_str(&format!("       \"{}\"\n", example));
+                        }
+                    }
+
+                    for (label, words) in sense.relations() {
+                        out.push_str(&format!("       {}: {}\n", label, words));
+                    }
+                }
+                out.push('\n');
+            }
+        }
+
+        out
+    }
+}
+
+impl Definition {
+    /// Renders the entry as a man page, for `man -l -` to typeset and page
+    pub fn to_roff(&self, examples: bool) -> String {
+        let mut out = format!(
+            ".TH \"{}\" 7 \"\" \"dibble\" \"Dictionary\"\n.SH NAME\n{}\n",
+            roff_escape(&self.word.to_uppercase()),
+            roff_escape(&self.word)
+        );
+        if let Some(line) = self.pronunciation_line() {
+            out.push_str(&format!(".SH PRONUNCIATION\n{}\n", roff_escape(&line)));
+        }
+
+        for (etym_idx, etymology) in self.etymologies.iter().enumerate() {
+            if let Some(heading) = self.etymology_heading(etym_idx) {
+                out.push_str(&format!(".SH {}\n", roff_escape(&heading.to_uppercase())));
+            } else {
+                out.push_str(".SH DEFINITION\n");
+            }
+            if let Some(origin) = &etymology.origin {
+                out.push_str(&format!(".PP\n\\fI{}\\fR\n", roff_escape(origin)));
+            }
+
+            for pos in &etymology.parts_of_speech {
+                out.push_str(&format!(".SS {}\n", roff_escape(&pos.part_of_speech)));
+
+                for (sense_idx, sense) in pos.senses.iter().enumerate() {
+                    out.push_str(&format!(
+                        ".IP {}. 4\n{}\n",
+                        sense_idx + 1,
+                        roff_escape(&sense.sense)
+                    ));
+
+                    if let Some(date) = &sense.date
+                        && !date.is_empty()
+                    {
+                        out.push_str(&format!(".br\n\\fI[{}]\\fR\n", roff_escape(date)));
+                    }
+
+                    if examples {
+                        for example in &sense.examples {
+                            out.push_str(&format!(".RS\n.PP\n\"{}\"\n.RE\n", roff_escape(example)));
+                        }
+                    }
+
+                    for (label, words) in sense.relations() {
+                        out.push_str(&format!(
+                            ".br\n\\fB{}:\\fR {}\n",
+                            label,
+                            roff_escape(&words)
+                        ));
+                    }
+                }
+            }
+        }
+
+        out
+    }
+}
+
+/// Escapes backslashes and keeps a leading `.` or `'` from being read as a request
+fn roff_escape(text: &str) -> String {
+    let text = text.replace('\\', "\\e").replace('\n', " ");
+    if text.starts_with(['.', '\'']) {
+        format!("\\&{}", text)
+    } else {
+        text
+    }
+}
+
+impl Definition {
+    /// Renders the entry as an HTML fragment, with a `dibble-` class on every
+    /// element so it can be styled from outside
+    pub fn to_html(&self, examples: bool) -> String {
+        let mut out = format!(
+            "<article class=\"dibble-entry\">\n<h2 class=\"dibble-word\">{}</h2>\n",
+            html_escape(&self.word)
+        );
+        if let Some(marker) = self.commonness() {
+            out.push_str(&format!(
+                "<p class=\"dibble-frequency\">{}</p>\n",
+                html_escape(marker)
+            ));
+        }
+        if let Some(line) = self.pronunciation_line() {
+            out.push_str(&format!(
+                "<p class=\"dibble-pronunciation\">{}</p>\n",
+                html_escape(&line)
+            ));
+        }
+
+        for (etym_idx, etymology) in self.etymologies.iter().enumerate() {
+            out.push_str("<section class=\"dibble-etymology\">\n");
+            if let Some(heading) = self.etymology_heading(etym_idx) {
+                out.push_str(&format!(
+                    "<h3 class=\"dibble-etymology-title\">{}</h3>\n",
+                    html_escape(&heading)
+                ));
+            }
+            if let Some(origin) = &etymology.origin {
+                out.push_str(&format!(
+                    "<p class=\"dibble-origin\"><em>{}</em></p>\n",
+                    html_escape(origin)
+                ));
+            }
+
+            for pos in &etymology.parts_of_speech {
+                out.push_str(&format!(
+                    "<h4 class=\"dibble-pos\">{}</h4>\n<ol class=\"dibble-senses\">\n",
+                    html_escape(&pos.part_of_speech)
+                ));
+
+                for sense in &pos.senses {
+                    out.push_str(&format!(
+                        "<li class=\"dibble-sense\"><span class=\"dibble-sense-text\">{}</span>",
+                        html_escape(&sense.sense)
+                    ));
+
+                    if let Some(date) = &sense.date
+                        && !date.is_empty()
+                    {
+                        out.push_str(&format!(
+                            " <span class=\"dibble-date\">[{}]</span>",
+                            html_escape(date)
+                        ));
+                    }
+
+                    if examples && !sense.examples.is_empty() {
+                        out.push_str("\n<ul class=\"dibble-examples\">\n");
+                        for example in &sense.examples {
+                            out.push_str(&format!(
+                                "<li class=\"dibble-example\">{}</li>\n",
+                                html_escape(example)
+                            ));
+                        }
+                        out.push_str("</ul>\n");
+                    }
+
+                    for (label, words) in sense.relations() {
+                        out.push_str(&format!(
+                            "\n<p class=\"dibble-{}\"><span class=\"dibble-relation\">{}:</span> {}</p>",
+                            label.to_lowercase(),
+                            label,
+                            html_escape(&words)
+                        ));
+                    }
+                    out.push_str("</li>\n");
+                }
+                out.push_str("</ol>\n");
+            }
+            out.push_str("</section>\n");
+        }
+
+        out.push_str("</article>\n");
+        out
+    }
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+impl Definition {
+    /// Renders the entry for other programs to read, like editor plugins. One
+    /// field per line: its name, then its values, separated by tabs and
+    /// indented with a tab for each level it's nested at. Fields always come
+    /// in this order, and a field with several values always has all of them,
+    /// empty if missing (`\t` is a tab):
+    ///
+    /// ```text
+    /// word\t<headword>
+    /// frequency\t<rank>
+    /// pronunciation\t<ipa>\t<accent>\t<audio>
+    /// etymology\t<number>\t<source>
+    /// \torigin\t<origin>
+    /// \tpos\t<part of speech>
+    /// \t\tsense\t<number>\t<sense>
+    /// \t\t\tdate\t<date>
+    /// \t\t\tlabel\t<label>
+    /// \t\t\texample\t<example>
+    /// \t\t\tsynonym\t<word>
+    /// \t\t\tantonym\t<word>
+    /// ```
+    ///
+    /// Tabs and line breaks inside a value become spaces.
+    pub fn to_plain(&self, examples: bool) -> String {
+        let mut out = String::new();
+        plain_field(&mut out, 0, "word", &[&self.word]);
+        if let Some(rank) = self.frequency {
+            plain_field(&mut out, 0, "frequency", &[&rank.to_string()]);
+        }
+        for p in &self.pronunciations {
+            let accent = p.accent.as_deref().unwrap_or_default();
+            let audio = p.audio.as_deref().unwrap_or_default();
+            plain_field(&mut out, 0, "pronunciation", &[&p.ipa, accent, audio]);
+        }
+
+        for (etym_idx, etymology) in self.etymologies.iter().enumerate() {
+            let number = (etym_idx + 1).to_string();
+            let source = etymology.source.as_deref().unwrap_or_default();
+            plain_field(&mut out, 0, "etymology", &[&number, source]);
+            if let Some(origin) = &etymology.origin {
+                plain_field(&mut out, 1, "origin", &[origin]);
+            }
+
+            for pos in &etymology.parts_of_speech {
+                plain_field(&mut out, 1, "pos", &[&pos.part_of_speech]);
+
+                for (sense_idx, sense) in pos.senses.iter().enumerate() {
+                    let number = (sense_idx + 1).to_string();
+                    plain_field(&mut out, 2, "sense", &[&number, &sense.sense]);
+                    if let Some(date) = &sense.date
+                        && !date.is_empty()
+                    {
+                        plain_field(&mut out, 3, "date", &[date]);
+                    }
+                    for label in &sense.labels {
+                        plain_field(&mut out, 3, "label", &[label]);
+                    }
+                    if examples {
+                        for example in &sense.examples {
+                            plain_field(&mut out, 3, "example", &[example]);
+                        }
+                    }
+                    for synonym in &sense.synonyms {
+                        plain_field(&mut out, 3, "synonym", &[synonym]);
+                    }
+                    for antonym in &sense.antonyms {
+                        plain_field(&mut out, 3, "antonym", &[antonym]);
+                    }
+                }
+            }
+        }
+
+        out
+    }
+}
+
+/// Appends one line of [`Definition::to_plain`] output to `out`
+pub fn plain_field(out: &mut String, depth: usize, name: &str, values: &[&str]) {
+    out.push_str(&"\t".repeat(depth));
+    out.push_str(name);
+    for value in values {
+        out.push('\t');
+        out.push_str(&value.replace(['\t', '\r', '\n'], " "));
+    }
+    out.push('\n');
+}
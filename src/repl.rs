@@ -0,0 +1,124 @@
+use crate::{
+    Definition, Dictionary, cprintln,
+    dictionary::is_valid_word,
+    history::{self, Visit},
+    lemma,
+    paths::project_dirs,
+    store::Store,
+    suggest,
+};
+use anyhow::Result;
+use rustyline::{DefaultEditor, error::ReadlineError};
+
+/// Runs an interactive lookup session until `:quit` or end of input, looking
+/// words up in `store` and suggesting others from `dict`. Give `dict` a
+/// [shard cache](Dictionary::with_shard_cache) so each shard is parsed only
+/// once.
+pub fn run(dict: &Dictionary, store: &dyn Store, examples: bool) -> Result<()> {
+    let mut editor = DefaultEditor::new()?;
+    let history = project_dirs().map(|dirs| dirs.data_dir().join("repl_history"));
+    if let Some(history) = &history {
+        // a missing history file just means this is the first session
+        let _ = editor.load_history(history);
+    }
+
+    let session = Session { dict, store };
+
+    cprintln!("<dim>Type a word to define it, or :help for commands.</dim>");
+
+    loop {
+        let line = match editor.readline("dibble> ") {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted | ReadlineError::Eof) => break,
+            Err(e) => return Err(e.into()),
+        };
+
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        editor.add_history_entry(line)?;
+
+        match line {
+            ":quit" | ":q" => break,
+            ":help" | ":h" => print_help(),
+            command if command.starts_with(':') => {
+                cprintln!("<red>Unknown command: {}</red> (try :help)", command);
+            }
+            word => session.define(word, examples)?,
+        }
+    }
+
+    if let Some(history) = &history {
+        if let Some(parent) = history.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        editor.save_history(history)?;
+    }
+
+    Ok(())
+}
+
+fn print_help() {
+    cprintln!("<bold>word</bold>    define a word");
+    cprintln!("<bold>:help</bold>   show this help");
+    cprintln!("<bold>:quit</bold>   leave the session");
+}
+
+/// What the session looks words up in
+struct Session<'a> {
+    dict: &'a Dictionary,
+    store: &'a dyn Store,
+}
+
+impl Session<'_> {
+    /// Looks `word` up, falling back on the entry for its lemma the way
+    /// `dibble define` does, so `running` finds `run`
+    fn lookup(&self, word: &str) -> Result<Option<Definition>> {
+        if let Some(def) = self.store.lookup(word)? {
+            return Ok(Some(def));
+        }
+        let candidates = lemma::candidates(word);
+        if candidates.is_empty() {
+            return Ok(None);
+        }
+        Ok(self
+            .store
+            .lookup_many(&candidates)?
+            .into_iter()
+            .flatten()
+            .next())
+    }
+
+    fn define(&self, word: &str, examples: bool) -> Result<()> {
+        if !is_valid_word(word) {
+            cprintln!(
+                "<red>Invalid input: Words may only contain letters, hyphens, apostrophes and spaces.</red>"
+            );
+            return Ok(());
+        }
+
+        match self.lookup(word) {
+            Ok(Some(def)) => {
+                if def.word.to_lowercase() != word.to_lowercase() {
+                    cprintln!("<dim>{} → {}</dim>", word, def.word);
+                }
+                def.print_colored(examples);
+                // a history that can't be written shouldn't end the session
+                let _ = history::record(&[Visit::now(&def.word, None)]);
+            }
+            Ok(None) => {
+                cprintln!("<red>Word not found: {}</red>", word);
+                let _ = history::record(&[Visit::miss(word)]);
+
+                let suggestions = suggest::suggestions(self.dict, word, 5)?;
+                if !suggestions.is_empty() {
+                    cprintln!("Did you mean: <bold>{}</bold>?", suggestions.join(", "));
+                }
+            }
+            Err(e) => cprintln!("<red>{}</red>", e),
+        }
+
+        Ok(())
+    }
+}
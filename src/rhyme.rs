@@ -0,0 +1,93 @@
+use crate::{Definition, Dictionary};
+use anyhow::Result;
+use std::collections::{BTreeMap, HashSet};
+
+/// IPA letters that are vowels. Anything else with a letter shape counts as a consonant.
+const VOWELS: &str = "aeiouyæɑɒɐəɘɛɜɝɚɞɤɨɪɯɵøœɶɔʉʊʌʏ";
+
+fn is_vowel(c: char) -> bool {
+    VOWELS.contains(c)
+}
+
+/// Length marks and combining diacritics stick to the sound before them
+fn is_modifier(c: char) -> bool {
+    c == 'ː' || c == 'ˑ' || ('\u{300}'..='\u{36f}').contains(&c)
+}
+
+/// The sounds of a transcription without its delimiters, syllable breaks,
+/// secondary stress or optional-sound brackets. The primary stress mark stays.
+fn sounds(ipa: &str) -> String {
+    ipa.chars()
+        .filter(|c| {
+            !matches!(c, '/' | '[' | ']' | '.' | 'ˌ' | '(' | ')' | '‿') && !c.is_whitespace()
+        })
+        .collect()
+}
+
+/// The part of a pronunciation two words have to share to rhyme: everything
+/// from the vowel of the last stressed syllable on. `/ˈkæt/` gives `æt`,
+/// `/əˈbaʊt/` gives `aʊt`.
+pub fn rhyme_key(ipa: &str) -> Option<String> {
+    let sounds = sounds(ipa);
+    let stressed = sounds.rsplit('ˈ').next().unwrap_or_default();
+    let start = stressed.find(is_vowel)?;
+    Some(stressed[start..].to_string())
+}
+
+/// How many syllables a pronunciation has: one per run of vowels, so
+/// diphthongs count once
+pub fn syllables(ipa: &str) -> usize {
+    let mut count = 0;
+    let mut in_vowel = false;
+    for c in sounds(ipa).chars() {
+        if is_modifier(c) {
+            continue;
+        }
+        let vowel = is_vowel(c);
+        if vowel && !in_vowel {
+            count += 1;
+        }
+        in_vowel = vowel;
+    }
+    count
+}
+
+/// Every headword in `dict` that rhymes with `def` in one of its
+/// pronunciations, grouped by syllable count and sorted. Entries without a
+/// pronunciation can't rhyme with anything.
+pub fn rhymes(dict: &Dictionary, def: &Definition) -> Result<BTreeMap<usize, Vec<String>>> {
+    let keys: HashSet<String> = def
+        .pronunciations
+        .iter()
+        .filter_map(|p| rhyme_key(&p.ipa))
+        .collect();
+    let word = def.word.to_lowercase();
+
+    let mut groups: BTreeMap<usize, Vec<String>> = BTreeMap::new();
+    if keys.is_empty() {
+        return Ok(groups);
+    }
+
+    for shard in dict.shards()? {
+        for candidate in dict.load_shard(&shard)?.into_values() {
+            if candidate.word.to_lowercase() == word {
+                continue;
+            }
+            let rhyming = candidate
+                .pronunciations
+                .iter()
+                .find(|p| rhyme_key(&p.ipa).is_some_and(|key| keys.contains(&key)));
+            if let Some(pronunciation) = rhyming {
+                groups
+                    .entry(syllables(&pronunciation.ipa))
+                    .or_default()
+                    .push(candidate.word);
+            }
+        }
+    }
+
+    for words in groups.values_mut() {
+        words.sort();
+    }
+    Ok(groups)
+}
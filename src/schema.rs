@@ -0,0 +1,261 @@
+use crate::period::Period;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::HashMap;
+
+/// The contents of a single shard file, keyed by headword
+pub type DictionaryFile = HashMap<String, Definition>;
+
+/// Labels marking a sense as no longer in everyday use, hidden by `--no-archaic`
+pub const ARCHAIC_LABELS: &[&str] = &["archaic", "obsolete", "dated"];
+
+/// Words ranked at or above this in the frequency list are marked common
+pub const COMMON_RANK: u32 = 5_000;
+
+/// Words ranked below this in the frequency list are marked rare
+pub const RARE_RANK: u32 = 50_000;
+
+/// The JSON Schema of a shard file, or of a single entry in one if `entry`
+/// is set, for checking data meant for dibble before it's imported
+pub fn json_schema(entry: bool) -> schemars::Schema {
+    let mut schema = if entry {
+        schemars::schema_for!(Definition)
+    } else {
+        schemars::schema_for!(DictionaryFile)
+    };
+    let title = if entry {
+        "Dibble entry"
+    } else {
+        "Dibble shard"
+    };
+    schema.insert("title".to_string(), title.into());
+    schema
+}
+
+/// The entry for one headword
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Definition {
+    /// The word being defined
+    pub word: String,
+    /// Array of Etymology objects, representing different meanings or origins of the word
+    pub etymologies: Vec<Etymology>,
+    /// Optional: How the word is said, in IPA
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pronunciations: Vec<Pronunciation>,
+    /// Optional: Rank of the word in a frequency list, 1 being the most common
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frequency: Option<u32>,
+}
+
+/// One way of saying a word
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct Pronunciation {
+    /// The pronunciation in IPA, e.g. "/həˈləʊ/". Empty when there's only a recording.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub ipa: String,
+    /// Optional: The accent it belongs to, e.g. "UK" or "US"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub accent: Option<String>,
+    /// Optional: A recording of it, either a URL or a path relative to the
+    /// dictionary root for one bundled with the dictionary
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audio: Option<String>,
+}
+
+/// Represents a particular etymology or origin of a word
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Etymology {
+    /// Array of Part of Speech objects within this etymology
+    #[serde(rename = "partsOfSpeech")]
+    pub parts_of_speech: Vec<PartOfSpeech>,
+    /// Optional: Where the word comes from, e.g. "From Old French..."
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin: Option<String>,
+    /// Optional: Name of the dictionary this etymology came from, when
+    /// entries from several dictionaries were merged
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+}
+
+/// Represents a specific part of speech for a word
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PartOfSpeech {
+    /// The part of speech (e.g., "Noun", "Verb", "Adjective")
+    #[serde(rename = "partOfSpeech")]
+    pub part_of_speech: String,
+    /// Array of Sense objects representing different meanings
+    pub senses: Vec<Sense>,
+}
+
+/// Represents a specific sense or meaning of a word
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Sense {
+    /// The specific sense or meaning
+    pub sense: String,
+    /// Optional: Time period or usage context
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date: Option<String>,
+    /// Optional: Usage labels like "archaic", "slang" or "botany"
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<String>,
+    /// Optional: Sentences using the word in this sense
+    #[serde(default)]
+    pub examples: Vec<String>,
+    /// Optional: Words meaning the same in this sense
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub synonyms: Vec<String>,
+    /// Optional: Words meaning the opposite in this sense
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub antonyms: Vec<String>,
+}
+
+impl Sense {
+    /// The related words to show under the sense, labelled: synonyms, then
+    /// antonyms, leaving out any there are none of
+    pub fn relations(&self) -> Vec<(&'static str, String)> {
+        [("Synonyms", &self.synonyms), ("Antonyms", &self.antonyms)]
+            .into_iter()
+            .filter(|(_, words)| !words.is_empty())
+            .map(|(label, words)| (label, words.join(", ")))
+            .collect()
+    }
+}
+
+impl Sense {
+    /// The years this sense was in use, if its date says
+    pub fn period(&self) -> Option<Period> {
+        Period::parse(self.date.as_deref()?)
+    }
+
+    /// Whether the sense carries `label`, either in its labels or in the
+    /// qualifier the sense text starts with, like "(obsolete, transitive)".
+    /// Case doesn't matter.
+    pub fn has_label(&self, label: &str) -> bool {
+        let qualifier = self
+            .sense
+            .strip_prefix('(')
+            .and_then(|rest| rest.split_once(')'))
+            .map_or("", |(qualifier, _)| qualifier);
+        self.labels
+            .iter()
+            .map(String::as_str)
+            .chain(qualifier.split(',').map(str::trim))
+            .any(|have| have.eq_ignore_ascii_case(label))
+    }
+
+    /// Whether the sense is labelled archaic, obsolete or dated
+    pub fn is_archaic(&self) -> bool {
+        ARCHAIC_LABELS.iter().any(|label| self.has_label(label))
+    }
+}
+
+impl Definition {
+    /// The marker shown next to the headword for very common and rare words.
+    /// Words without a rank get none.
+    pub fn commonness(&self) -> Option<&'static str> {
+        match self.frequency? {
+            rank if rank <= COMMON_RANK => Some("★ common"),
+            rank if rank > RARE_RANK => Some("rare"),
+            _ => None,
+        }
+    }
+
+    /// The first sense of the first part of speech of the first etymology
+    pub fn first_sense(&self) -> Option<&Sense> {
+        self.etymologies
+            .iter()
+            .flat_map(|etym| &etym.parts_of_speech)
+            .flat_map(|pos| &pos.senses)
+            .next()
+    }
+
+    /// The pronunciations as shown under the headword: `/ˈwɜːd/ (UK), /ˈwɝd/ (US)`
+    pub fn pronunciation_line(&self) -> Option<String> {
+        let parts: Vec<String> = self
+            .pronunciations
+            .iter()
+            .filter(|p| !p.ipa.is_empty())
+            .map(|p| match &p.accent {
+                Some(accent) => format!("{} ({})", p.ipa, accent),
+                None => p.ipa.clone(),
+            })
+            .collect();
+        (!parts.is_empty()).then(|| parts.join(", "))
+    }
+
+    /// The title shown above an etymology: its number when the entry has
+    /// several, and the dictionary it came from. `None` when there's nothing to say.
+    pub fn etymology_heading(&self, idx: usize) -> Option<String> {
+        let numbered = self.etymologies.len() > 1;
+        match (numbered, &self.etymologies.get(idx)?.source) {
+            (true, Some(source)) => Some(format!("Etymology {} ({})", idx + 1, source)),
+            (true, None) => Some(format!("Etymology {}", idx + 1)),
+            (false, Some(source)) => Some(format!("From {}", source)),
+            (false, None) => None,
+        }
+    }
+
+    /// Keeps only the parts of speech `keep` returns true for, dropping any
+    /// etymology left with none
+    pub fn retain_parts_of_speech(&mut self, keep: impl Fn(&PartOfSpeech) -> bool) {
+        for etymology in &mut self.etymologies {
+            etymology.parts_of_speech.retain(&keep);
+        }
+        self.etymologies
+            .retain(|etymology| !etymology.parts_of_speech.is_empty());
+    }
+
+    /// Keeps only the senses `keep` returns true for, dropping any part of
+    /// speech or etymology left with none
+    pub fn retain_senses(&mut self, keep: impl Fn(&Sense) -> bool) {
+        for etymology in &mut self.etymologies {
+            for pos in &mut etymology.parts_of_speech {
+                pos.senses.retain(&keep);
+            }
+        }
+        self.retain_parts_of_speech(|pos| !pos.senses.is_empty());
+    }
+
+    /// Keeps at most `max` senses under each part of speech
+    pub fn truncate_senses(&mut self, max: usize) {
+        for etymology in &mut self.etymologies {
+            for pos in &mut etymology.parts_of_speech {
+                pos.senses.truncate(max);
+            }
+        }
+    }
+
+    /// Cuts the entry down to its first sense, under the part of speech and
+    /// etymology it belongs to
+    pub fn keep_first_sense(&mut self) {
+        self.retain_parts_of_speech(|pos| !pos.senses.is_empty());
+        self.etymologies.truncate(1);
+        for etymology in &mut self.etymologies {
+            etymology.parts_of_speech.truncate(1);
+        }
+        self.truncate_senses(1);
+    }
+
+    /// Drops every synonym and antonym from the entry
+    pub fn strip_relations(&mut self) {
+        for etymology in &mut self.etymologies {
+            for pos in &mut etymology.parts_of_speech {
+                for sense in &mut pos.senses {
+                    sense.synonyms.clear();
+                    sense.antonyms.clear();
+                }
+            }
+        }
+    }
+
+    /// Drops every example sentence from the entry
+    pub fn strip_examples(&mut self) {
+        for etymology in &mut self.etymologies {
+            for pos in &mut etymology.parts_of_speech {
+                for sense in &mut pos.senses {
+                    sense.examples.clear();
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,40 @@
+use crate::index::anagram::AnagramIndex;
+
+/// The points for a letter in English Scrabble
+fn letter_score(c: char) -> Option<u32> {
+    Some(match c.to_ascii_lowercase() {
+        'a' | 'e' | 'i' | 'o' | 'u' | 'l' | 'n' | 's' | 't' | 'r' => 1,
+        'd' | 'g' => 2,
+        'b' | 'c' | 'm' | 'p' => 3,
+        'f' | 'h' | 'v' | 'w' | 'y' => 4,
+        'k' => 5,
+        'j' | 'x' => 8,
+        'q' | 'z' => 10,
+        _ => return None,
+    })
+}
+
+/// The face value of `word`, without any premium squares. `None` if it has
+/// anything but the letters a to z.
+pub fn score(word: &str) -> Option<u32> {
+    word.chars().map(letter_score).sum()
+}
+
+/// Whether `word` could go on the board at all: two or more plain letters and
+/// not capitalized, since proper nouns aren't allowed
+pub fn is_playable(word: &str) -> bool {
+    word.chars().count() >= 2 && word.chars().all(|c| c.is_ascii_lowercase())
+}
+
+/// The playable headwords that can be spelled from the tiles in `rack`, with
+/// their scores, highest first
+pub fn best_words(index: &AnagramIndex, rack: &str) -> Vec<(u32, String)> {
+    let mut words: Vec<(u32, String)> = index
+        .partial(rack)
+        .into_iter()
+        .filter(|word| is_playable(word))
+        .filter_map(|word| Some((score(&word)?, word)))
+        .collect();
+    words.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+    words
+}
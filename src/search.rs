@@ -0,0 +1,313 @@
+use crate::{Definition, Dictionary};
+use anyhow::Result;
+use regex::Regex;
+
+/// Lists every headword matching a glob pattern, where `?` matches one
+/// character and `*` matches any run of characters. Matching ignores case.
+pub fn glob(dict: &Dictionary, pattern: &str) -> Result<Vec<String>> {
+    if let Some(Ok(words)) = dict
+        .headword_index()
+        .map(|index| index.regex(&glob_regex(pattern), true))
+    {
+        return Ok(words);
+    }
+
+    let literal = literal_prefix(pattern);
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+
+    let mut words = Vec::new();
+    for shard in dict.prefix_shards(&literal)? {
+        for word in dict.shard_words(&shard)? {
+            let lower: Vec<char> = word.to_lowercase().chars().collect();
+            if glob_match(&pattern, &lower) {
+                words.push(word);
+            }
+        }
+    }
+
+    words.sort();
+    Ok(words)
+}
+
+/// A regex matching the same whole words as the glob `pattern`
+fn glob_regex(pattern: &str) -> String {
+    let mut re = String::from("^");
+    for c in pattern.chars() {
+        match c {
+            '*' => re.push_str(".*"),
+            '?' => re.push('.'),
+            c => re.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    re.push('$');
+    re
+}
+
+/// The part of a glob pattern before its first wildcard, used to narrow down
+/// which shards need to be read
+pub fn literal_prefix(pattern: &str) -> String {
+    pattern
+        .to_lowercase()
+        .chars()
+        .take_while(|c| !matches!(c, '*' | '?'))
+        .collect()
+}
+
+/// Every headword in the dictionary, unsorted. They come from the binary
+/// index when there is one, which is much faster than reading every shard.
+pub fn headwords(dict: &Dictionary) -> Result<Vec<String>> {
+    match dict.index() {
+        Some(index) => (0..index.len() as u32).map(|idx| index.key(idx)).collect(),
+        None => dict.iter_words()?.collect(),
+    }
+}
+
+/// Picks one of `words` at random and removes it, so drawing repeatedly never
+/// gives the same word twice
+pub fn take_random(words: &mut Vec<String>) -> Option<String> {
+    let idx = random_index(words.len())?;
+    Some(words.swap_remove(idx))
+}
+
+/// Picks one of `words` at random, leaving them as they are
+pub fn pick_random(words: &[String]) -> Option<&String> {
+    words.get(random_index(words.len())?)
+}
+
+/// A random number below `len`, or `None` if `len` is 0
+fn random_index(len: usize) -> Option<usize> {
+    if len == 0 {
+        return None;
+    }
+    // a real generator rather than hashing with a fresh RandomState, whose
+    // keys only step by one between calls on a thread, so picks in a row
+    // would follow each other and lean towards some indices from the modulo
+    Some(fastrand::usize(..len))
+}
+
+/// Lists every headword fitting a crossword pattern like `c_t__n`, where `_`
+/// (or `?`) is an unknown letter and the length is fixed. Matching ignores case.
+pub fn fill(dict: &Dictionary, pattern: &str) -> Result<Vec<String>> {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let fits = |word: &str| {
+        let lower: Vec<char> = word.to_lowercase().chars().collect();
+        lower.len() == pattern.len()
+            && pattern
+                .iter()
+                .zip(&lower)
+                .all(|(p, c)| matches!(p, '_' | '?') || p == c)
+    };
+
+    let mut words = headwords(dict)?;
+    words.retain(|word| fits(word));
+    words.sort();
+    Ok(words)
+}
+
+/// Lists every headword matching a regular expression. With a headword
+/// index the pattern runs over that; otherwise, or if it's too big to run
+/// there, every shard is walked. Flags like case insensitivity have to be
+/// in the pattern, as `(?i)`, to carry over to the index.
+pub fn grep(dict: &Dictionary, re: &Regex) -> Result<Vec<String>> {
+    if let Some(Ok(words)) = dict
+        .headword_index()
+        .map(|index| index.regex(re.as_str(), false))
+    {
+        return Ok(words);
+    }
+
+    let mut words = Vec::new();
+    for word in dict.iter_words()? {
+        let word = word?;
+        if re.is_match(&word) {
+            words.push(word);
+        }
+    }
+
+    words.sort();
+    Ok(words)
+}
+
+/// Lists the headwords from `from` up to but not including `to`, or to the
+/// end without one, in byte order
+pub fn range(dict: &Dictionary, from: &str, to: Option<&str>) -> Result<Vec<String>> {
+    if let Some(index) = dict.headword_index() {
+        return index.range(from, to);
+    }
+
+    let mut words = headwords(dict)?;
+    words.retain(|word| word.as_str() >= from && to.is_none_or(|to| word.as_str() < to));
+    words.sort();
+    words.dedup();
+    Ok(words)
+}
+
+/// The `top` most frequent headwords and their ranks, most frequent first.
+/// Words without a frequency rank are left out.
+pub fn most_frequent(dict: &Dictionary, top: usize) -> Result<Vec<(u32, String)>> {
+    let mut ranked = Vec::new();
+    for shard in dict.shards()? {
+        for def in dict.load_shard(&shard)?.into_values() {
+            if let Some(rank) = def.frequency {
+                ranked.push((rank, def.word));
+            }
+        }
+    }
+
+    ranked.sort();
+    ranked.truncate(top);
+    Ok(ranked)
+}
+
+/// The lowercased words of `text`, for matching phrases regardless of case
+/// and punctuation
+pub fn tokens(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric() && c != '\'')
+        .map(|token| token.trim_matches('\'').to_lowercase())
+        .filter(|token| !token.is_empty())
+        .collect()
+}
+
+/// Whether the words of `phrase` appear together, in order, in `text`. Both
+/// are lists of [`tokens`].
+pub fn contains_phrase(text: &[String], phrase: &[String]) -> bool {
+    !phrase.is_empty() && text.windows(phrase.len()).any(|window| window == phrase)
+}
+
+/// Finds the headwords with a sense mentioning `phrase`, like `acrophobia`
+/// for "fear of heights", by reading every shard. Each comes with its
+/// matching sense; the shortest senses, which are most likely to mean just
+/// the phrase, come first.
+pub fn reverse(dict: &Dictionary, phrase: &str) -> Result<Vec<(String, String)>> {
+    let phrase = tokens(phrase);
+    let mut found = Vec::new();
+    for shard in dict.shards()? {
+        for def in dict.load_shard(&shard)?.into_values() {
+            if let Some(sense) = matching_sense(&def, &phrase) {
+                found.push((def.word, sense));
+            }
+        }
+    }
+    rank(&mut found);
+    Ok(found)
+}
+
+/// The shortest sense of `def` mentioning `phrase`
+pub fn matching_sense(def: &Definition, phrase: &[String]) -> Option<String> {
+    def.etymologies
+        .iter()
+        .flat_map(|etym| &etym.parts_of_speech)
+        .flat_map(|pos| &pos.senses)
+        .filter(|sense| contains_phrase(&tokens(&sense.sense), phrase))
+        .min_by_key(|sense| sense.sense.chars().count())
+        .map(|sense| sense.sense.clone())
+}
+
+/// Puts reverse lookup results in order: shortest sense first, then by headword
+pub fn rank(found: &mut [(String, String)]) {
+    found.sort_by(|a, b| {
+        a.1.chars()
+            .count()
+            .cmp(&b.1.chars().count())
+            .then_with(|| a.0.cmp(&b.0))
+    });
+}
+
+/// Matches `text` against a glob `pattern` of `?` and `*` wildcards
+pub fn glob_match(pattern: &[char], text: &[char]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    // position of the last `*` seen, and the text position it was tried at
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, from)) => {
+                    p = star + 1;
+                    t = from + 1;
+                    backtrack = Some((star, from + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, text: &str) -> bool {
+        let pattern: Vec<char> = pattern.chars().collect();
+        let text: Vec<char> = text.chars().collect();
+        glob_match(&pattern, &text)
+    }
+
+    #[test]
+    fn glob_match_wildcards() {
+        assert!(matches("cat", "cat"));
+        assert!(!matches("cat", "cats"));
+        assert!(!matches("cats", "cat"));
+        assert!(matches("c?t", "cut"));
+        assert!(!matches("c?t", "ct"));
+        assert!(matches("c*", "c"));
+        assert!(matches("c*t", "cat"));
+        assert!(matches("c*t", "ct"));
+        assert!(matches("*", ""));
+        assert!(!matches("?", ""));
+        assert!(matches("", ""));
+        assert!(!matches("", "a"));
+        assert!(matches("*ing", "string"));
+        assert!(matches("c?t*", "catapult"));
+        assert!(matches("**a**", "banana"));
+        assert!(matches("é?é", "été"));
+    }
+
+    #[test]
+    fn glob_match_backtracks_past_early_matches() {
+        // the first `s` after the star isn't the one that matches
+        assert!(matches("*ses", "misses"));
+        assert!(!matches("*ss", "misses"));
+        assert!(matches("a*b*c", "abbbcbc"));
+        assert!(!matches("a*b*c", "abbbcb"));
+        assert!(matches("*a?a", "banana"));
+        assert!(!matches("*x*", "banana"));
+    }
+
+    #[test]
+    fn glob_regex_agrees_with_glob_match() {
+        let texts = [
+            "cat", "cut", "ct", "catapult", "banana", "a.b", "a+b", "(c)", "",
+        ];
+        for pattern in ["c?t", "c*", "*a*", "a.b", "a+b", "(*)", "*", "?", "**a?a"] {
+            let re = Regex::new(&glob_regex(pattern)).unwrap();
+            for text in texts {
+                assert_eq!(
+                    re.is_match(text),
+                    matches(pattern, text),
+                    "{} on {}",
+                    pattern,
+                    text
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn literal_prefix_stops_at_the_first_wildcard() {
+        assert_eq!(literal_prefix("Cat*"), "cat");
+        assert_eq!(literal_prefix("ca?s"), "ca");
+        assert_eq!(literal_prefix("*at"), "");
+        assert_eq!(literal_prefix("cat"), "cat");
+    }
+}
@@ -0,0 +1,314 @@
+use crate::{Dictionary, search, store::Store, suggest};
+use anyhow::Result;
+use regex::Regex;
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    thread,
+};
+
+/// The port DICT clients connect to unless told otherwise
+pub const DEFAULT_PORT: u16 = 2628;
+
+/// The name the local shards are served under
+const DATABASE: &str = "dibble";
+const DATABASE_DESCRIPTION: &str = "Dibble dictionary";
+
+/// Match strategies, with the description sent for `SHOW STRAT`
+const STRATEGIES: &[(&str, &str)] = &[
+    ("exact", "Match headwords exactly"),
+    ("prefix", "Match prefixes"),
+    ("glob", "Match a pattern with ? and * wildcards"),
+    ("re", "Match a regular expression"),
+    ("lev", "Match headwords within a small edit distance"),
+];
+
+/// Upper bound on the number of headwords returned for the `lev` strategy
+const LEV_LIMIT: usize = 20;
+
+static CONNECTIONS: AtomicU64 = AtomicU64::new(0);
+
+/// Serves the dictionary over the DICT protocol (RFC 2229) until killed, one
+/// thread per client. Entries and prefix matches come from `store`, so the
+/// user's own entries are served too; the other strategies search `dict`.
+pub fn run(dict: &Dictionary, store: Arc<dyn Store>, addr: impl ToSocketAddrs) -> Result<()> {
+    let listener = TcpListener::bind(addr)?;
+
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let dict = dict.clone();
+        let store = Arc::clone(&store);
+        thread::spawn(move || {
+            // a client hanging up mid-response only affects that client
+            let _ = Session::new(&dict, store.as_ref(), &stream).serve();
+        });
+    }
+
+    Ok(())
+}
+
+struct Session<'a> {
+    dict: &'a Dictionary,
+    store: &'a dyn Store,
+    stream: &'a TcpStream,
+}
+
+impl<'a> Session<'a> {
+    fn new(dict: &'a Dictionary, store: &'a dyn Store, stream: &'a TcpStream) -> Self {
+        Self {
+            dict,
+            store,
+            stream,
+        }
+    }
+
+    fn serve(&mut self) -> Result<()> {
+        let id = CONNECTIONS.fetch_add(1, Ordering::Relaxed);
+        self.status(
+            220,
+            &format!(
+                "dibble {} <> <{}.{}@dibble>",
+                env!("CARGO_PKG_VERSION"),
+                std::process::id(),
+                id
+            ),
+        )?;
+
+        for line in BufReader::new(self.stream).lines() {
+            let line = line?;
+            let Some(args) = parse_command(&line) else {
+                self.status(501, "syntax error, illegal parameters")?;
+                continue;
+            };
+            let Some(command) = args.first() else {
+                continue;
+            };
+
+            let args: Vec<&str> = args.iter().map(String::as_str).collect();
+            match (command.to_ascii_uppercase().as_str(), &args[1..]) {
+                ("DEFINE", [database, word]) => self.define(database, word)?,
+                ("MATCH", [database, strategy, word]) => self.find(database, strategy, word)?,
+                ("SHOW", [what]) if is_any(what, &["DB", "DATABASES"]) => self.show_databases()?,
+                ("SHOW", [what]) if is_any(what, &["STRAT", "STRATEGIES"]) => {
+                    self.show_strategies()?
+                }
+                ("SHOW", [what, database]) if is_any(what, &["INFO"]) => {
+                    self.show_info(database)?
+                }
+                ("SHOW", [what]) if is_any(what, &["SERVER"]) => {
+                    self.status(114, "server information follows")?;
+                    self.text(&format!(
+                        "dibble {}\nServing the local shards as '{}'",
+                        env!("CARGO_PKG_VERSION"),
+                        DATABASE
+                    ))?;
+                    self.status(250, "ok")?;
+                }
+                ("CLIENT", [_, ..]) | ("OPTION", [_, ..]) => self.status(250, "ok")?,
+                ("STATUS", []) => self.status(210, "up")?,
+                ("HELP", []) => {
+                    self.status(113, "help text follows")?;
+                    self.text(HELP)?;
+                    self.status(250, "ok")?;
+                }
+                ("QUIT", []) => {
+                    self.status(221, "bye")?;
+                    break;
+                }
+                ("DEFINE" | "MATCH" | "SHOW" | "CLIENT" | "OPTION" | "STATUS" | "HELP", _) => {
+                    self.status(501, "syntax error, illegal parameters")?
+                }
+                _ => self.status(500, "unknown command")?,
+            }
+        }
+
+        Ok(())
+    }
+
+    fn define(&mut self, database: &str, word: &str) -> Result<()> {
+        if !is_database(database) {
+            return self.status(550, "invalid database, use SHOW DB for list of databases");
+        }
+
+        let Some(def) = self.store.lookup(word)? else {
+            return self.status(552, "no match");
+        };
+
+        self.status(150, "1 definitions retrieved")?;
+        self.status(
+            151,
+            &format!(
+                "{} {} {}",
+                quote(&def.word),
+                DATABASE,
+                quote(DATABASE_DESCRIPTION)
+            ),
+        )?;
+        self.text(&def.to_text(true))?;
+        self.status(250, "ok")
+    }
+
+    fn find(&mut self, database: &str, strategy: &str, word: &str) -> Result<()> {
+        if !is_database(database) {
+            return self.status(550, "invalid database, use SHOW DB for list of databases");
+        }
+
+        let matches = match strategy.to_ascii_lowercase().as_str() {
+            "exact" => self
+                .store
+                .lookup(word)?
+                .map(|def| def.word)
+                .into_iter()
+                .collect(),
+            "prefix" | "." => self.store.prefix(word)?,
+            "glob" => search::glob(self.dict, word)?,
+            "re" => match Regex::new(word) {
+                Ok(re) => search::grep(self.dict, &re)?,
+                Err(_) => return self.status(501, "syntax error, illegal parameters"),
+            },
+            "lev" => suggest::suggestions(self.dict, word, LEV_LIMIT)?,
+            _ => return self.status(551, "invalid strategy, use SHOW STRAT for a list"),
+        };
+
+        if matches.is_empty() {
+            return self.status(552, "no match");
+        }
+
+        self.status(152, &format!("{} matches found", matches.len()))?;
+        let lines: Vec<String> = matches
+            .iter()
+            .map(|word| format!("{} {}", DATABASE, quote(word)))
+            .collect();
+        self.text(&lines.join("\n"))?;
+        self.status(250, "ok")
+    }
+
+    fn show_databases(&mut self) -> Result<()> {
+        self.status(110, "1 databases present")?;
+        self.text(&format!("{} {}", DATABASE, quote(DATABASE_DESCRIPTION)))?;
+        self.status(250, "ok")
+    }
+
+    fn show_strategies(&mut self) -> Result<()> {
+        self.status(111, &format!("{} strategies available", STRATEGIES.len()))?;
+        let lines: Vec<String> = STRATEGIES
+            .iter()
+            .map(|(name, description)| format!("{} {}", name, quote(description)))
+            .collect();
+        self.text(&lines.join("\n"))?;
+        self.status(250, "ok")
+    }
+
+    fn show_info(&mut self, database: &str) -> Result<()> {
+        if !is_database(database) {
+            return self.status(550, "invalid database, use SHOW DB for list of databases");
+        }
+
+        self.status(112, "database information follows")?;
+        let roots: Vec<String> = self
+            .dict
+            .roots()
+            .iter()
+            .map(|root| format!("  {}", root.display()))
+            .collect();
+        self.text(&format!(
+            "{}\n\nEntries are read from:\n{}",
+            DATABASE_DESCRIPTION,
+            roots.join("\n")
+        ))?;
+        self.status(250, "ok")
+    }
+
+    fn status(&mut self, code: u16, message: &str) -> Result<()> {
+        write!(self.stream, "{} {}\r\n", code, message)?;
+        Ok(())
+    }
+
+    /// Sends a block of text, dot-stuffed and terminated by a lone `.`
+    fn text(&mut self, text: &str) -> Result<()> {
+        let mut out = String::new();
+        for line in text.trim_end().lines() {
+            if line.starts_with('.') {
+                out.push('.');
+            }
+            out.push_str(line);
+            out.push_str("\r\n");
+        }
+        out.push_str(".\r\n");
+        self.stream.write_all(out.as_bytes())?;
+        Ok(())
+    }
+}
+
+const HELP: &str = "DEFINE database word         -- look up word in database
+MATCH database strategy word -- match word in database using strategy
+SHOW DB                      -- list all accessible databases
+SHOW STRAT                   -- list available matching strategies
+SHOW INFO database           -- provide information about the database
+SHOW SERVER                  -- provide site-specific information
+CLIENT info                  -- identify client to server
+STATUS                       -- display timing information
+HELP                         -- display this help information
+QUIT                         -- terminate connection";
+
+/// `*` and `!` ask for every database, or the first with a match; with only
+/// one database both mean the same thing
+fn is_database(name: &str) -> bool {
+    matches!(name, "*" | "!" | DATABASE)
+}
+
+fn is_any(word: &str, options: &[&str]) -> bool {
+    options
+        .iter()
+        .any(|option| word.eq_ignore_ascii_case(option))
+}
+
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Splits a command line into words, honouring single and double quotes and
+/// backslash escapes. Returns `None` for an unterminated quote.
+fn parse_command(line: &str) -> Option<Vec<String>> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut quote = None;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match (c, quote) {
+            ('\\', _) => {
+                current.push(chars.next()?);
+                in_word = true;
+            }
+            ('"' | '\'', None) => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (c, Some(q)) if c == q => quote = None,
+            (c, None) if c.is_whitespace() => {
+                if in_word {
+                    args.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            (c, _) => {
+                current.push(c);
+                in_word = true;
+            }
+        }
+    }
+
+    if quote.is_some() {
+        return None;
+    }
+    if in_word {
+        args.push(current);
+    }
+    Some(args)
+}
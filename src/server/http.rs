@@ -0,0 +1,218 @@
+use crate::{
+    Dictionary, dictionary::is_valid_word, error::DibbleError, search, store::Store, suggest,
+};
+use anyhow::Result;
+use serde_json::Value;
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    sync::{Arc, OnceLock},
+    thread,
+};
+
+/// Where `--http` listens unless given an address
+pub const DEFAULT_ADDR: &str = "127.0.0.1:8080";
+
+/// How many headwords `/search` returns unless asked for a `limit`
+const SEARCH_LIMIT: usize = 100;
+
+/// How many suggestions come with a word that isn't found
+const SUGGESTIONS: usize = 5;
+
+/// Serves a JSON API over HTTP until killed, one thread per client:
+///
+/// - `GET /define/{word}`: the entry for `word`
+/// - `GET /search?q=cat&limit=100`: headwords starting with `q`, or matching
+///   it as a glob pattern if it has `*` or `?` in it
+/// - `GET /random`: the entry for a headword picked at random
+///
+/// Entries and prefix searches come from `store`, so the user's own entries
+/// are served too; patterns, suggestions and random words come from `dict`.
+/// Errors are JSON objects like those `--format json` writes to stderr.
+pub fn run(dict: &Dictionary, store: Arc<dyn Store>, addr: impl ToSocketAddrs) -> Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    let server = Arc::new(Server {
+        dict: dict.clone(),
+        store,
+        headwords: OnceLock::new(),
+    });
+
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let server = Arc::clone(&server);
+        thread::spawn(move || {
+            // a client hanging up mid-response only affects that client
+            let _ = server.serve(&stream);
+        });
+    }
+
+    Ok(())
+}
+
+struct Server {
+    dict: Dictionary,
+    store: Arc<dyn Store>,
+    /// Every headword, for `/random`, listed the first time it's asked for
+    headwords: OnceLock<Vec<String>>,
+}
+
+impl Server {
+    /// Answers a single request and closes the connection
+    fn serve(&self, stream: &TcpStream) -> Result<()> {
+        let mut reader = BufReader::new(stream);
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line)?;
+        // the headers say nothing a GET here needs
+        let mut header = String::new();
+        while reader.read_line(&mut header)? > 0 && !header.trim_end().is_empty() {
+            header.clear();
+        }
+
+        let mut parts = request_line.split_whitespace();
+        let (status, body) = match (parts.next(), parts.next()) {
+            (Some("GET"), Some(target)) => match self.handle(target) {
+                Ok(Some(body)) => (200, body),
+                Ok(None) => (404, error("not_found", "No such endpoint")),
+                Err(e) => (status_for(&e), crate::error::to_json(&e)),
+            },
+            (Some(_), Some(_)) => (405, error("invalid_input", "Only GET is supported")),
+            _ => (400, error("invalid_input", "Malformed request")),
+        };
+        respond(stream, status, &body)
+    }
+
+    /// The response to a GET of `target`, or `None` if there's nothing there
+    fn handle(&self, target: &str) -> Result<Option<Value>> {
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        let param = |name: &str| {
+            query
+                .split('&')
+                .filter_map(|pair| pair.split_once('='))
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| percent_decode(value, true))
+        };
+
+        if let Some(word) = path.strip_prefix("/define/") {
+            return self.define(&percent_decode(word, false)).map(Some);
+        }
+        Ok(Some(match path {
+            "/search" => {
+                let Some(q) = param("q").filter(|q| !q.is_empty()) else {
+                    return Err(invalid("Give a prefix or pattern to search for with ?q="));
+                };
+                let limit = match param("limit") {
+                    Some(limit) => limit
+                        .parse()
+                        .map_err(|_| invalid("The limit must be a number"))?,
+                    None => SEARCH_LIMIT,
+                };
+                let mut words = if q.contains(['*', '?']) {
+                    search::glob(&self.dict, &q)?
+                } else {
+                    self.store.prefix(&q)?
+                };
+                words.truncate(limit);
+                serde_json::to_value(words)?
+            }
+            "/random" => {
+                let headwords = match self.headwords.get() {
+                    Some(words) => words,
+                    None => {
+                        let words = search::headwords(&self.dict)?;
+                        self.headwords.get_or_init(|| words)
+                    }
+                };
+                let Some(word) = search::pick_random(headwords) else {
+                    return Err(DibbleError::DictMissing("The dictionary is empty".into()).into());
+                };
+                self.define(word)?
+            }
+            _ => return Ok(None),
+        }))
+    }
+
+    fn define(&self, word: &str) -> Result<Value> {
+        if !is_valid_word(word) {
+            return Err(invalid(
+                "Words may only contain letters, hyphens, apostrophes and spaces",
+            ));
+        }
+        match self.store.lookup(word)? {
+            Some(def) => Ok(serde_json::to_value(def)?),
+            None => Err(DibbleError::WordNotFound {
+                word: word.to_string(),
+                suggestions: suggest::suggestions(&self.dict, word, SUGGESTIONS)?,
+            }
+            .into()),
+        }
+    }
+}
+
+fn invalid(message: &str) -> anyhow::Error {
+    DibbleError::InvalidInput(message.to_string()).into()
+}
+
+/// An error body shaped like [`crate::error::to_json`]'s
+fn error(kind: &str, message: &str) -> Value {
+    serde_json::json!({ "error": kind, "message": message })
+}
+
+/// The HTTP status for a failed request: the client's fault for bad input
+/// and missing words, the server's for anything else
+fn status_for(e: &anyhow::Error) -> u16 {
+    match e.downcast_ref::<DibbleError>() {
+        Some(DibbleError::InvalidInput(_)) => 400,
+        Some(DibbleError::WordNotFound { .. }) => 404,
+        _ => 500,
+    }
+}
+
+fn respond(mut stream: &TcpStream, status: u16, body: &Value) -> Result<()> {
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        _ => "Internal Server Error",
+    };
+    let body = serde_json::to_string(body)?;
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason,
+        body.len(),
+        body
+    )?;
+    stream.flush()?;
+    Ok(())
+}
+
+/// Decodes the `%XX` escapes in part of a URL, and `+` as a space in a query
+/// string. Anything that doesn't decode to UTF-8 is replaced.
+fn percent_decode(text: &str, query: bool) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut idx = 0;
+    while idx < bytes.len() {
+        let hex = bytes
+            .get(idx + 1..idx + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[idx], hex) {
+            (b'%', Some(byte)) => {
+                out.push(byte);
+                idx += 3;
+            }
+            (b'+', _) if query => {
+                out.push(b' ');
+                idx += 1;
+            }
+            (byte, _) => {
+                out.push(byte);
+                idx += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
@@ -0,0 +1,58 @@
+use crate::DictionaryFile;
+use std::{
+    collections::VecDeque,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// How many parsed shards a [`ShardCache`] keeps unless told otherwise
+pub const DEFAULT_CAPACITY: usize = 64;
+
+/// The most recently used parsed shards, so a process answering lookup after
+/// lookup doesn't parse the same shard again each time
+#[derive(Debug)]
+pub struct ShardCache {
+    capacity: usize,
+    /// Most recently used first
+    shards: VecDeque<(PathBuf, Arc<DictionaryFile>)>,
+}
+
+impl Default for ShardCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl ShardCache {
+    /// A cache holding at most `capacity` shards, dropping the least
+    /// recently used one to make room
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            shards: VecDeque::new(),
+        }
+    }
+
+    /// The parsed shard at `path`, if it's kept, marking it as just used
+    pub fn get(&mut self, path: &Path) -> Option<Arc<DictionaryFile>> {
+        let idx = self.shards.iter().position(|(shard, _)| shard == path)?;
+        let entry = self.shards.remove(idx)?;
+        let data = entry.1.clone();
+        self.shards.push_front(entry);
+        Some(data)
+    }
+
+    pub fn insert(&mut self, path: PathBuf, data: Arc<DictionaryFile>) {
+        self.shards.retain(|(shard, _)| *shard != path);
+        self.shards.push_front((path, data));
+        self.shards.truncate(self.capacity);
+    }
+
+    pub fn len(&self) -> usize {
+        self.shards.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shards.is_empty()
+    }
+}
@@ -0,0 +1,213 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+use unicode_normalization::UnicodeNormalization;
+
+/// How a shard tree files headwords into shards, recorded in its manifest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum ShardScheme {
+    /// By the first `letters` letters: `c/cat` for three, with words shorter
+    /// than that in a shard of their own, like `c/ca`
+    Prefix { letters: usize },
+    /// Spread evenly over `buckets` shards by a hash of the whole word, for
+    /// dictionaries big enough that some prefixes make huge shards. Prefix
+    /// searches have to read every shard.
+    Hash { buckets: u32 },
+}
+
+impl Default for ShardScheme {
+    /// The first two letters, `c/ca`, which every tree used before schemes
+    fn default() -> Self {
+        Self::Prefix { letters: 2 }
+    }
+}
+
+impl ShardScheme {
+    /// Parses a scheme like `prefix:3` or `hash:4096`
+    pub fn parse(spec: &str) -> Result<Self> {
+        let (kind, size) = spec
+            .split_once(':')
+            .with_context(|| format!("Expected prefix:LETTERS or hash:BUCKETS, got '{}'", spec))?;
+        let size: u32 = size
+            .trim()
+            .parse()
+            .ok()
+            .filter(|&size| size > 0)
+            .with_context(|| format!("Expected a positive number after {}:", kind))?;
+
+        match kind.trim() {
+            "prefix" => Ok(Self::Prefix {
+                letters: size as usize,
+            }),
+            "hash" => Ok(Self::Hash { buckets: size }),
+            other => anyhow::bail!("Unknown shard scheme '{}' (expected prefix or hash)", other),
+        }
+    }
+
+    /// Derives the shard `word` lives in, or `None` if it has no letters or
+    /// digits. Hyphens, apostrophes, spaces and other punctuation are
+    /// skipped, so under the default scheme `o'clock` lives in `o/oc`, and
+    /// so are accents that don't compose onto their letter.
+    pub fn path(&self, word: &str) -> Option<PathBuf> {
+        let key: Vec<char> = shard_key(word).collect();
+        let first = *key.first()?;
+
+        Some(match *self {
+            Self::Prefix { letters } => {
+                let prefix: String = key.iter().take(letters.max(1)).collect();
+                PathBuf::from(String::from(first)).join(prefix)
+            }
+            Self::Hash { buckets } => {
+                let bucket = fnv1a(&key) % u64::from(buckets.max(1));
+                PathBuf::from(format!("{:02x}", bucket >> 8)).join(format!("{:04x}", bucket))
+            }
+        })
+    }
+
+    /// Whether the shard at `shard` can hold words beginning with `stem`
+    pub fn may_hold_prefix(&self, shard: &Path, stem: &str) -> bool {
+        let Self::Prefix { letters } = *self else {
+            return true;
+        };
+        let key: String = shard_key(stem).take(letters.max(1)).collect();
+        let Some(first) = key.chars().next() else {
+            return true;
+        };
+
+        shard.starts_with(String::from(first))
+            && shard
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with(&key))
+    }
+}
+
+impl fmt::Display for ShardScheme {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Prefix { letters } => write!(f, "prefix:{}", letters),
+            Self::Hash { buckets } => write!(f, "hash:{}", buckets),
+        }
+    }
+}
+
+/// The lowercased letters and digits of `word` in NFC, which decide its shard
+pub(crate) fn shard_key(word: &str) -> impl Iterator<Item = char> {
+    word.nfc()
+        .flat_map(char::to_lowercase)
+        .filter(|c| c.is_alphanumeric())
+        .collect::<Vec<_>>()
+        .into_iter()
+}
+
+/// 64-bit FNV-1a over the UTF-8 of `key`, which never changes between builds
+/// or platforms the way the standard library's hashers may
+fn fnv1a(key: &[char]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    let mut buf = [0; 4];
+    for c in key {
+        for byte in c.encode_utf8(&mut buf).bytes() {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PREFIX_2: ShardScheme = ShardScheme::Prefix { letters: 2 };
+
+    #[test]
+    fn parses_schemes() {
+        assert_eq!(
+            ShardScheme::parse("prefix:3").unwrap(),
+            ShardScheme::Prefix { letters: 3 }
+        );
+        assert_eq!(
+            ShardScheme::parse(" hash : 4096").unwrap(),
+            ShardScheme::Hash { buckets: 4096 }
+        );
+        for bad in ["prefix", "prefix:0", "prefix:-1", "hash:many", "trie:2"] {
+            assert!(
+                ShardScheme::parse(bad).is_err(),
+                "{} should be refused",
+                bad
+            );
+        }
+        assert_eq!(ShardScheme::default(), PREFIX_2);
+        assert_eq!(ShardScheme::Hash { buckets: 16 }.to_string(), "hash:16");
+    }
+
+    #[test]
+    fn shard_key_is_the_lowercase_letters_and_digits_in_nfc() {
+        let key = |word: &str| shard_key(word).collect::<String>();
+        assert_eq!(key("O'Clock"), "oclock");
+        assert_eq!(key("mother-in-law"), "motherinlaw");
+        assert_eq!(key("cafe\u{301}"), "café");
+        assert_eq!(key("4x4"), "4x4");
+        assert_eq!(key("--"), "");
+    }
+
+    #[test]
+    fn prefix_paths() {
+        let path = |scheme: ShardScheme, word| scheme.path(word);
+        assert_eq!(path(PREFIX_2, "cat"), Some(PathBuf::from("c/ca")));
+        assert_eq!(path(PREFIX_2, "Cat"), Some(PathBuf::from("c/ca")));
+        assert_eq!(path(PREFIX_2, "o'clock"), Some(PathBuf::from("o/oc")));
+        assert_eq!(path(PREFIX_2, "a"), Some(PathBuf::from("a/a")));
+        assert_eq!(
+            path(PREFIX_2, "e\u{301}te\u{301}"),
+            Some(PathBuf::from("é/ét"))
+        );
+        assert_eq!(path(PREFIX_2, "''"), None);
+
+        let three = ShardScheme::Prefix { letters: 3 };
+        assert_eq!(path(three, "cattle"), Some(PathBuf::from("c/cat")));
+        assert_eq!(path(three, "ca"), Some(PathBuf::from("c/ca")));
+        // no letters at all would put every word in one folder's first shard
+        let zero = ShardScheme::Prefix { letters: 0 };
+        assert_eq!(path(zero, "cat"), Some(PathBuf::from("c/c")));
+    }
+
+    #[test]
+    fn hash_paths_are_stable_and_in_range() {
+        let scheme = ShardScheme::Hash { buckets: 4096 };
+        let path = scheme.path("cat").unwrap();
+        // spellings with the same key share a shard
+        assert_eq!(scheme.path("CAT").unwrap(), path);
+        assert_eq!(scheme.path("c-a-t").unwrap(), path);
+        // FNV-1a of "cat" is 0xf5e307190ce4a327, bucket 807; trees on disk
+        // depend on this never changing
+        assert_eq!(path, PathBuf::from("03/0327"));
+        assert_eq!(scheme.path("!?"), None);
+
+        for word in ["a", "zebra", "naïve", "9"] {
+            let path = scheme.path(word).unwrap();
+            let bucket = u64::from_str_radix(&path.file_name().unwrap().to_string_lossy(), 16);
+            assert!(
+                bucket.unwrap() < 4096,
+                "{} went to {}",
+                word,
+                path.display()
+            );
+        }
+    }
+
+    #[test]
+    fn may_hold_prefix() {
+        assert!(PREFIX_2.may_hold_prefix(Path::new("c/ca"), "cat"));
+        assert!(PREFIX_2.may_hold_prefix(Path::new("c/ca"), "Ca"));
+        assert!(PREFIX_2.may_hold_prefix(Path::new("c/ca"), "c"));
+        assert!(!PREFIX_2.may_hold_prefix(Path::new("c/co"), "cat"));
+        assert!(!PREFIX_2.may_hold_prefix(Path::new("d/ca"), "cat"));
+        // a stem without letters could be anywhere
+        assert!(PREFIX_2.may_hold_prefix(Path::new("c/co"), "-"));
+        assert!(ShardScheme::Hash { buckets: 16 }.may_hold_prefix(Path::new("00/0001"), "cat"));
+    }
+}
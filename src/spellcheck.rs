@@ -0,0 +1,53 @@
+use std::collections::HashMap;
+
+/// A word of a text and every line it's on
+#[derive(Debug, Clone)]
+pub struct Word {
+    pub word: String,
+    /// 1-based, ascending, each line once
+    pub lines: Vec<usize>,
+}
+
+/// The distinct words of `text`, in the order they first appear. Words keep
+/// their case, since a lookup tries the lowercase spelling anyway and a name
+/// may only be in the dictionary capitalized. Apostrophes and hyphens inside
+/// a word are kept, a possessive `'s` is dropped, and anything with a digit
+/// in it isn't a word.
+pub fn words(text: &str) -> Vec<Word> {
+    let mut found: Vec<Word> = Vec::new();
+    let mut seen: HashMap<&str, usize> = HashMap::new();
+
+    for (idx, line) in text.lines().enumerate() {
+        let tokens = line
+            .split(|c: char| !c.is_alphanumeric() && !matches!(c, '\'' | '’' | '-'))
+            .map(|token| token.trim_matches(['\'', '’', '-']))
+            .map(|token| {
+                token
+                    .strip_suffix("'s")
+                    .or_else(|| token.strip_suffix("’s"))
+                    .unwrap_or(token)
+            })
+            .filter(|token| !token.is_empty() && !token.chars().any(|c| c.is_numeric()));
+
+        for token in tokens {
+            let line = idx + 1;
+            match seen.get(token) {
+                Some(&at) => {
+                    let word = &mut found[at];
+                    if word.lines.last() != Some(&line) {
+                        word.lines.push(line);
+                    }
+                }
+                None => {
+                    seen.insert(token, found.len());
+                    found.push(Word {
+                        word: token.to_string(),
+                        lines: vec![line],
+                    });
+                }
+            }
+        }
+    }
+
+    found
+}
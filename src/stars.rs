@@ -0,0 +1,80 @@
+use crate::paths::project_dirs;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+const FILE_NAME: &str = "stars.json";
+
+/// A starred word
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Star {
+    /// Optional: Why it was starred, or anything else worth remembering
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+/// The starred words, kept in the user data dir
+#[derive(Debug, Default)]
+pub struct Stars {
+    path: PathBuf,
+    words: BTreeMap<String, Star>,
+}
+
+/// Where the starred words live: the user data dir
+pub fn default_path() -> Result<PathBuf> {
+    let dirs = project_dirs().context("Could not determine the user data directory")?;
+    Ok(dirs.data_dir().join(FILE_NAME))
+}
+
+impl Stars {
+    /// Loads the stars at the default path
+    pub fn open() -> Result<Self> {
+        Self::open_at(&default_path()?)
+    }
+
+    /// Loads the stars at `path`. A missing file has no stars; one that
+    /// doesn't parse is an error, so saving can't wipe it out.
+    pub fn open_at(path: &Path) -> Result<Self> {
+        let words = match fs::read_to_string(path) {
+            Ok(contents) => serde_json::from_str(&contents)
+                .with_context(|| format!("Invalid stars file {}", path.display()))?,
+            Err(_) => BTreeMap::new(),
+        };
+        Ok(Self {
+            path: path.to_path_buf(),
+            words,
+        })
+    }
+
+    /// The star on `word`, if it has one
+    pub fn get(&self, word: &str) -> Option<&Star> {
+        self.words.get(word)
+    }
+
+    /// Stars `word`, replacing the note if it was already starred
+    pub fn add(&mut self, word: &str, note: Option<String>) {
+        self.words.insert(word.to_string(), Star { note });
+    }
+
+    /// Unstars `word`. Returns whether it was starred.
+    pub fn remove(&mut self, word: &str) -> bool {
+        self.words.remove(word).is_some()
+    }
+
+    /// The starred words and their stars, alphabetically
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Star)> {
+        self.words.iter()
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(&self.words)?)
+            .with_context(|| format!("Failed to write {}", self.path.display()))
+    }
+}
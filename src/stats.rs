@@ -0,0 +1,138 @@
+use crate::{Dictionary, date, history::Visit};
+use anyhow::Result;
+use std::collections::{HashMap, HashSet};
+
+/// What the lookup history says about how dibble gets used
+#[derive(Debug, Default)]
+pub struct Usage {
+    /// Every lookup, found or not
+    pub lookups: usize,
+    /// Lookups that found nothing
+    pub misses: usize,
+    /// The distinct headwords that were found
+    pub words: HashSet<String>,
+    /// The most looked-up headwords and how often, most first
+    pub top_words: Vec<(usize, String)>,
+    /// The days with the most lookups and how many, most first
+    pub busiest_days: Vec<(usize, String)>,
+}
+
+impl Usage {
+    /// Tallies `visits`, keeping the `top` most looked-up words and busiest days
+    pub fn from_visits(visits: &[Visit], top: usize) -> Self {
+        let mut usage = Self {
+            lookups: visits.len(),
+            ..Self::default()
+        };
+        let mut words: HashMap<&str, usize> = HashMap::new();
+        let mut days: HashMap<u64, usize> = HashMap::new();
+        for visit in visits {
+            *days.entry(visit.time / 86_400).or_default() += 1;
+            if visit.missed {
+                usage.misses += 1;
+            } else {
+                *words.entry(&visit.word).or_default() += 1;
+            }
+        }
+
+        usage.words = words.keys().map(|word| word.to_string()).collect();
+        usage.top_words = most(
+            words.into_iter().map(|(word, n)| (n, word.to_string())),
+            top,
+        );
+        usage.busiest_days = most(
+            days.into_iter().map(|(day, n)| (n, date::format_day(day))),
+            top,
+        );
+        usage
+    }
+
+    /// The share of lookups that found nothing, from 0 to 1
+    pub fn miss_rate(&self) -> f64 {
+        if self.lookups == 0 {
+            return 0.0;
+        }
+        self.misses as f64 / self.lookups as f64
+    }
+}
+
+/// What a dictionary holds
+#[derive(Debug, Default)]
+pub struct Contents {
+    pub headwords: usize,
+    pub senses: usize,
+    /// Headwords with at least one example sentence
+    pub with_examples: usize,
+    /// The size of each shard's JSON in bytes, smallest first
+    pub shard_sizes: Vec<usize>,
+}
+
+impl Contents {
+    /// Reads every shard of `dict` and counts what's in it
+    pub fn collect(dict: &Dictionary) -> Result<Self> {
+        let mut contents = Self::default();
+        for shard in dict.shards()? {
+            let json = dict.read_shard(&shard)?;
+            contents.shard_sizes.push(json.len());
+
+            let entries: crate::DictionaryFile = serde_json::from_str(&json)?;
+            for def in entries.values() {
+                let senses = def
+                    .etymologies
+                    .iter()
+                    .flat_map(|etym| &etym.parts_of_speech)
+                    .flat_map(|pos| &pos.senses);
+                let mut examples = false;
+                for sense in senses {
+                    contents.senses += 1;
+                    examples |= !sense.examples.is_empty();
+                }
+                contents.headwords += 1;
+                contents.with_examples += usize::from(examples);
+            }
+        }
+        contents.shard_sizes.sort_unstable();
+        Ok(contents)
+    }
+
+    pub fn senses_per_word(&self) -> f64 {
+        self.senses as f64 / self.headwords.max(1) as f64
+    }
+
+    /// The share of headwords with an example, from 0 to 1
+    pub fn example_rate(&self) -> f64 {
+        self.with_examples as f64 / self.headwords.max(1) as f64
+    }
+
+    /// The shard size `fraction` of the way from the smallest to the largest,
+    /// so 0.5 is the median. 0 with no shards.
+    pub fn shard_size(&self, fraction: f64) -> usize {
+        let Some(last) = self.shard_sizes.len().checked_sub(1) else {
+            return 0;
+        };
+        self.shard_sizes[(last as f64 * fraction).round() as usize]
+    }
+}
+
+/// `bytes` in the largest unit that keeps it above 1, e.g. `3.4 MB`
+pub fn format_bytes(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1000.0 && unit < UNITS.len() - 1 {
+        size /= 1000.0;
+        unit += 1;
+    }
+    match unit {
+        0 => format!("{} B", bytes),
+        _ => format!("{:.1} {}", size, UNITS[unit]),
+    }
+}
+
+/// The `top` counts, highest first, ties broken alphabetically
+fn most(counts: impl Iterator<Item = (usize, String)>, top: usize) -> Vec<(usize, String)> {
+    let mut counts: Vec<(usize, String)> = counts.collect();
+    counts.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+    counts.truncate(top);
+    counts
+}
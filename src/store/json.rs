@@ -0,0 +1,18 @@
+use super::Store;
+use crate::{Definition, Dictionary};
+use anyhow::Result;
+
+/// The default store: the sharded JSON tree searched by [`Dictionary`]
+impl Store for Dictionary {
+    fn lookup(&self, word: &str) -> Result<Option<Definition>> {
+        Dictionary::lookup(self, word)
+    }
+
+    fn lookup_many(&self, words: &[String]) -> Result<Vec<Option<Definition>>> {
+        Dictionary::lookup_many(self, words)
+    }
+
+    fn prefix(&self, stem: &str) -> Result<Vec<String>> {
+        Dictionary::prefix(self, stem)
+    }
+}
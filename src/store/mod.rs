@@ -0,0 +1,21 @@
+use crate::Definition;
+use anyhow::Result;
+
+pub mod json;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+
+/// A source of dictionary entries, which servers share between the threads
+/// answering their clients
+pub trait Store: Send + Sync {
+    /// Looks up a single headword
+    fn lookup(&self, word: &str) -> Result<Option<Definition>>;
+
+    /// Looks up several headwords, returning results in the same order
+    fn lookup_many(&self, words: &[String]) -> Result<Vec<Option<Definition>>> {
+        words.iter().map(|word| self.lookup(word)).collect()
+    }
+
+    /// Lists every headword beginning with `stem`, sorted
+    fn prefix(&self, stem: &str) -> Result<Vec<String>>;
+}
@@ -0,0 +1,121 @@
+use super::Store;
+use crate::{
+    Definition, Dictionary,
+    dictionary::{case_variants, normalize},
+    paths::project_dirs,
+};
+use anyhow::{Context, Result};
+use rusqlite::{Connection, OpenFlags, OptionalExtension, params};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+/// All entries in one indexed SQLite file, so a lookup never parses a shard
+pub struct SqliteStore {
+    /// One query at a time, since a connection can't be used from two
+    /// threads at once
+    conn: Mutex<Connection>,
+}
+
+impl SqliteStore {
+    /// Where the database lives unless told otherwise: `dibble.db` in the user data dir
+    pub fn default_path() -> Result<PathBuf> {
+        let dirs = project_dirs().context("Could not determine the user data directory")?;
+        Ok(dirs.data_dir().join("dibble.db"))
+    }
+
+    /// Opens an existing database read-only
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .with_context(|| {
+                format!(
+                    "Failed to open {} (run `dibble build-db` to create it)",
+                    path.display()
+                )
+            })?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Copies every entry from the shard tree into a new database at `path`,
+    /// replacing any existing one. Returns the number of entries written.
+    pub fn build(dict: &Dictionary, path: &Path) -> Result<usize> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let partial = path.with_extension("db.partial");
+        let _ = fs::remove_file(&partial);
+
+        let mut conn = Connection::open(&partial)?;
+        conn.execute_batch(
+            "CREATE TABLE entries (
+                word TEXT PRIMARY KEY,
+                lower TEXT NOT NULL,
+                data TEXT NOT NULL
+            );
+            CREATE INDEX entries_lower ON entries (lower);",
+        )?;
+
+        let mut count = 0;
+        let tx = conn.transaction()?;
+        {
+            let mut insert = tx.prepare(
+                "INSERT OR REPLACE INTO entries (word, lower, data) VALUES (?1, ?2, ?3)",
+            )?;
+            for shard in dict.shards()? {
+                for (word, def) in dict.load_shard(&shard)? {
+                    insert.execute(params![
+                        word,
+                        word.to_lowercase(),
+                        serde_json::to_string(&def)?
+                    ])?;
+                    count += 1;
+                }
+            }
+        }
+        tx.commit()?;
+        drop(conn);
+
+        fs::rename(&partial, path)?;
+        Ok(count)
+    }
+}
+
+impl Store for SqliteStore {
+    fn lookup(&self, word: &str) -> Result<Option<Definition>> {
+        let conn = self.conn.lock().unwrap();
+        for variant in case_variants(&normalize(word)) {
+            let data: Option<String> = conn
+                .query_row(
+                    "SELECT data FROM entries WHERE word = ?1",
+                    params![variant],
+                    |row| row.get(0),
+                )
+                .optional()?;
+
+            if let Some(data) = data {
+                return Ok(Some(serde_json::from_str(&data)?));
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn prefix(&self, stem: &str) -> Result<Vec<String>> {
+        // a range scan over the lowercase index: everything from the stem up to
+        // the stem followed by the highest code point
+        let lower = stem.to_lowercase();
+        let upper = format!("{}\u{10FFFF}", lower);
+        let conn = self.conn.lock().unwrap();
+        let mut query = conn
+            .prepare("SELECT word FROM entries WHERE lower >= ?1 AND lower < ?2 ORDER BY word")?;
+
+        let words = query
+            .query_map(params![lower, upper], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+        Ok(words)
+    }
+}
@@ -0,0 +1,136 @@
+use crate::paths::project_dirs;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+const FILE_NAME: &str = "study.json";
+
+/// The lowest ease SM-2 lets a card fall to, so hard cards still come back
+/// less and less often
+const MIN_EASE: f64 = 1.3;
+
+/// A word being studied and when it's next due
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Card {
+    /// Days between the last review and the next
+    pub interval: u32,
+    /// Reviews in a row graded 3 or better
+    pub repetitions: u32,
+    /// How much the interval grows after a good review
+    pub ease: f64,
+    /// The day it's next due, in days since 1970-01-01
+    pub due: u64,
+}
+
+impl Card {
+    /// A card that's due on `today`
+    pub fn new(today: u64) -> Self {
+        Self {
+            interval: 0,
+            repetitions: 0,
+            ease: 2.5,
+            due: today,
+        }
+    }
+
+    /// Schedules the next review after one graded `quality`, from 0 (forgot
+    /// it completely) to 5 (perfect recall), following SuperMemo's SM-2
+    pub fn review(&mut self, quality: u8, today: u64) {
+        let quality = quality.min(5);
+        if quality >= 3 {
+            self.interval = match self.repetitions {
+                0 => 1,
+                1 => 6,
+                _ => (f64::from(self.interval) * self.ease).round() as u32,
+            };
+            self.repetitions += 1;
+        } else {
+            self.repetitions = 0;
+            self.interval = 1;
+        }
+        let miss = f64::from(5 - quality);
+        self.ease = (self.ease + 0.1 - miss * (0.08 + miss * 0.02)).max(MIN_EASE);
+        self.due = today + u64::from(self.interval);
+    }
+}
+
+/// The words being studied, kept in the user data dir
+#[derive(Debug, Default)]
+pub struct Deck {
+    path: PathBuf,
+    cards: BTreeMap<String, Card>,
+}
+
+/// Where the study deck lives: the user data dir
+pub fn default_path() -> Result<PathBuf> {
+    let dirs = project_dirs().context("Could not determine the user data directory")?;
+    Ok(dirs.data_dir().join(FILE_NAME))
+}
+
+impl Deck {
+    /// Loads the deck at the default path
+    pub fn open() -> Result<Self> {
+        Self::open_at(&default_path()?)
+    }
+
+    /// Loads the deck at `path`. A missing file is an empty deck; one that
+    /// doesn't parse is an error, so saving can't wipe out the schedule.
+    pub fn open_at(path: &Path) -> Result<Self> {
+        let cards = match fs::read_to_string(path) {
+            Ok(contents) => serde_json::from_str(&contents)
+                .with_context(|| format!("Invalid study deck {}", path.display()))?,
+            Err(_) => BTreeMap::new(),
+        };
+        Ok(Self {
+            path: path.to_path_buf(),
+            cards,
+        })
+    }
+
+    /// Adds `word` as a new card due on `today`. Returns false, leaving its
+    /// schedule alone, if it's already in the deck.
+    pub fn add(&mut self, word: &str, today: u64) -> bool {
+        if self.cards.contains_key(word) {
+            return false;
+        }
+        self.cards.insert(word.to_string(), Card::new(today));
+        true
+    }
+
+    /// Drops `word` from the deck. Returns whether it was there.
+    pub fn remove(&mut self, word: &str) -> bool {
+        self.cards.remove(word).is_some()
+    }
+
+    pub fn get_mut(&mut self, word: &str) -> Option<&mut Card> {
+        self.cards.get_mut(word)
+    }
+
+    /// The words due on or before `today`, most overdue first
+    pub fn due(&self, today: u64) -> Vec<String> {
+        let mut due: Vec<(&String, &Card)> = self
+            .cards
+            .iter()
+            .filter(|(_, card)| card.due <= today)
+            .collect();
+        due.sort_by_key(|(word, card)| (card.due, *word));
+        due.into_iter().map(|(word, _)| word.clone()).collect()
+    }
+
+    /// Every card, alphabetically
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Card)> {
+        self.cards.iter()
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(&self.cards)?)
+            .with_context(|| format!("Failed to write {}", self.path.display()))
+    }
+}
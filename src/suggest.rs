@@ -0,0 +1,77 @@
+use crate::{Dictionary, search, sharding::shard_key};
+use anyhow::Result;
+
+/// Finds up to `limit` headwords close to `word` by edit distance, closest
+/// first. With a headword index every headword is considered, those sharing
+/// the first letter of `word` ranking above the rest at the same distance;
+/// without one only the shards of that first letter are read.
+pub fn suggestions(dict: &Dictionary, word: &str, limit: usize) -> Result<Vec<String>> {
+    let Some(letter) = shard_key(word).next() else {
+        return Ok(Vec::new());
+    };
+
+    let word = word.to_lowercase();
+    let max_distance = (word.chars().count() / 3).max(2);
+    let words = match dict
+        .headword_index()
+        .map(|index| index.fuzzy(&word, max_distance as u32))
+    {
+        Some(Ok(words)) => words,
+        // long words make too big a query for the index
+        _ => {
+            let mut words = Vec::new();
+            for shard in dict.prefix_shards(&letter.to_string())? {
+                words.extend(
+                    dict.shard_words(&shard)?
+                        .into_iter()
+                        // hashed shards mix every letter
+                        .filter(|candidate| shard_key(candidate).next() == Some(letter)),
+                );
+            }
+            words
+        }
+    };
+
+    let mut candidates = Vec::new();
+    for candidate in words {
+        let distance = strsim::levenshtein(&word, &candidate.to_lowercase());
+        if distance > 0 && distance <= max_distance {
+            let other_letter = shard_key(&candidate).next() != Some(letter);
+            candidates.push((distance, other_letter, candidate));
+        }
+    }
+
+    candidates.sort();
+    candidates.dedup_by(|a, b| a.2 == b.2);
+
+    Ok(candidates
+        .into_iter()
+        .take(limit)
+        .map(|(_, _, word)| word)
+        .collect())
+}
+
+/// Every headword within `distance` edits of `word`, counting a change of
+/// case as an edit, closest first with its distance. The headword index
+/// answers this with a Levenshtein automaton; without one, or for a query
+/// too big for it, every headword is compared.
+pub fn fuzzy(dict: &Dictionary, word: &str, distance: usize) -> Result<Vec<(usize, String)>> {
+    let words = match dict
+        .headword_index()
+        .map(|index| index.fuzzy(word, distance as u32))
+    {
+        Some(Ok(words)) => words,
+        _ => search::headwords(dict)?,
+    };
+
+    let mut found: Vec<_> = words
+        .into_iter()
+        .filter_map(|candidate| {
+            let edits = strsim::levenshtein(word, &candidate);
+            (edits <= distance).then_some((edits, candidate))
+        })
+        .collect();
+    found.sort();
+    found.dedup();
+    Ok(found)
+}
@@ -0,0 +1,87 @@
+use crate::{Definition, paths::project_dirs};
+use anyhow::{Context, Result};
+use minijinja::{Environment, syntax::SyntaxConfig, value::Serde};
+use std::{fs, path::PathBuf};
+
+/// Where `--template` looks for templates: the `templates` folder in the user config dir
+pub fn dir() -> Result<PathBuf> {
+    let dirs = project_dirs().context("Could not determine the user config directory")?;
+    Ok(dirs.config_dir().join("templates"))
+}
+
+/// Lists the names of the saved templates, sorted
+pub fn names() -> Result<Vec<String>> {
+    let Ok(entries) = fs::read_dir(dir()?) else {
+        return Ok(Vec::new());
+    };
+
+    let mut names = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.is_file()
+            && let Some(stem) = path.file_stem()
+        {
+            names.push(stem.to_string_lossy().into_owned());
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+/// A saved output template, written in Jinja syntax. The entry is available
+/// as `word` and `etymologies`, with the same field names as the JSON output.
+#[derive(Debug)]
+pub struct Template {
+    env: Environment<'static>,
+}
+
+impl Template {
+    /// Loads the template called `name` (its file name, with or without the
+    /// extension) from the templates dir
+    pub fn load(name: &str) -> Result<Self> {
+        let dir = dir()?;
+        let exact = dir.join(name);
+        let path = if exact.is_file() {
+            exact
+        } else {
+            let found = fs::read_dir(&dir).ok().and_then(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                    .find(|path| path.is_file() && path.file_stem().is_some_and(|s| s == name))
+            });
+            match found {
+                Some(path) => path,
+                None => anyhow::bail!(
+                    "No template named '{}' in {}. Available: {}",
+                    name,
+                    dir.display(),
+                    names()?.join(", ")
+                ),
+            }
+        };
+
+        let source = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Self::parse(&source).with_context(|| format!("Invalid template {}", path.display()))
+    }
+
+    /// Compiles a template from its source
+    pub fn parse(source: &str) -> Result<Self> {
+        let mut env = Environment::new();
+        // block tags get their own lines without leaving blank ones behind
+        env.set_syntax(
+            SyntaxConfig::builder()
+                .trim_blocks(true)
+                .lstrip_blocks(true)
+                .keep_trailing_newline(true)
+                .build()?,
+        );
+        env.add_template_owned("entry", source.to_string())?;
+        Ok(Self { env })
+    }
+
+    /// Lays out an entry with the template
+    pub fn render(&self, def: &Definition) -> Result<String> {
+        Ok(self.env.get_template("entry")?.render(Serde(def))?)
+    }
+}
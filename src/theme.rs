@@ -0,0 +1,184 @@
+use crate::paths::project_dirs;
+use anstyle::{AnsiColor, Color, Effects, Style};
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::{fs, path::PathBuf};
+
+/// The themes that ship with dibble, selectable by name with `--theme`
+pub const BUILTIN: &[&str] = &["default", "light-terminal", "high-contrast", "monochrome"];
+
+/// The styles used for each part of an entry when printing it in color
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    pub word: Style,
+    pub etymology: Style,
+    pub origin: Style,
+    pub part_of_speech: Style,
+    pub number: Style,
+    pub date: Style,
+    pub example: Style,
+}
+
+/// A theme as written in a theme file: each field is a space separated list
+/// of effects and colors, e.g. `"bold bright_cyan"`. Missing fields keep the
+/// default theme's style.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThemeSpec {
+    pub word: Option<String>,
+    pub etymology: Option<String>,
+    pub origin: Option<String>,
+    pub part_of_speech: Option<String>,
+    pub number: Option<String>,
+    pub date: Option<String>,
+    pub example: Option<String>,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            word: Style::new().bold().fg_color(Some(AnsiColor::Cyan.into())),
+            etymology: Style::new().bold().fg_color(Some(AnsiColor::Yellow.into())),
+            origin: Style::new().italic(),
+            part_of_speech: Style::new().bold().fg_color(Some(AnsiColor::Green.into())),
+            number: Style::new().bold(),
+            date: Style::new().italic().dimmed(),
+            example: Style::new().dimmed(),
+        }
+    }
+}
+
+/// Where custom themes live: `<name>.toml` files in the `themes` folder of the user config dir
+pub fn dir() -> Result<PathBuf> {
+    let dirs = project_dirs().context("Could not determine the user config directory")?;
+    Ok(dirs.config_dir().join("themes"))
+}
+
+impl Theme {
+    /// Looks up a built-in theme, then a theme file in [`dir`]
+    pub fn load(name: &str) -> Result<Self> {
+        if let Some(theme) = Self::builtin(name) {
+            return Ok(theme);
+        }
+
+        let path = dir()?.join(name).with_extension("toml");
+        let Ok(contents) = fs::read_to_string(&path) else {
+            anyhow::bail!(
+                "No theme named '{}'. Built in: {}; custom themes go in {}",
+                name,
+                BUILTIN.join(", "),
+                dir()?.display()
+            );
+        };
+        toml::from_str(&contents)
+            .map_err(anyhow::Error::from)
+            .and_then(|spec| Self::from_spec(&spec))
+            .with_context(|| format!("Invalid theme {}", path.display()))
+    }
+
+    /// One of the [`BUILTIN`] themes
+    pub fn builtin(name: &str) -> Option<Self> {
+        let spec = match name {
+            "default" => return Some(Self::default()),
+            "light-terminal" => ThemeSpec {
+                word: Some("bold blue".into()),
+                etymology: Some("bold magenta".into()),
+                part_of_speech: Some("bold green".into()),
+                date: Some("italic bright_black".into()),
+                example: Some("bright_black".into()),
+                ..Default::default()
+            },
+            "high-contrast" => ThemeSpec {
+                word: Some("bold underline bright_cyan".into()),
+                etymology: Some("bold bright_yellow".into()),
+                origin: Some("italic bright_white".into()),
+                part_of_speech: Some("bold bright_green".into()),
+                number: Some("bold bright_white".into()),
+                date: Some("italic bright_white".into()),
+                example: Some("bright_white".into()),
+            },
+            "monochrome" => ThemeSpec {
+                word: Some("bold underline".into()),
+                etymology: Some("bold".into()),
+                origin: Some("italic".into()),
+                part_of_speech: Some("bold".into()),
+                number: Some("bold".into()),
+                date: Some("italic".into()),
+                example: Some("italic".into()),
+            },
+            _ => return None,
+        };
+        Self::from_spec(&spec).ok()
+    }
+
+    /// Builds a theme from a spec, falling back to the default for missing fields
+    pub fn from_spec(spec: &ThemeSpec) -> Result<Self> {
+        Self::default().with_spec(spec)
+    }
+
+    /// Overrides the styles `spec` sets, keeping the rest
+    pub fn with_spec(self, spec: &ThemeSpec) -> Result<Self> {
+        let style = |field: &Option<String>, fallback: Style| match field {
+            Some(spec) => parse_style(spec),
+            None => Ok(fallback),
+        };
+
+        Ok(Self {
+            word: style(&spec.word, self.word)?,
+            etymology: style(&spec.etymology, self.etymology)?,
+            origin: style(&spec.origin, self.origin)?,
+            part_of_speech: style(&spec.part_of_speech, self.part_of_speech)?,
+            number: style(&spec.number, self.number)?,
+            date: style(&spec.date, self.date)?,
+            example: style(&spec.example, self.example)?,
+        })
+    }
+}
+
+/// Parses a style like `"bold italic bright_red"`. An empty string is no styling.
+pub fn parse_style(spec: &str) -> Result<Style> {
+    let mut style = Style::new();
+
+    for part in spec.split_whitespace() {
+        let lower = part.to_lowercase();
+        let effect = match lower.as_str() {
+            "bold" => Some(Effects::BOLD),
+            "dim" => Some(Effects::DIMMED),
+            "italic" => Some(Effects::ITALIC),
+            "underline" => Some(Effects::UNDERLINE),
+            "reverse" => Some(Effects::INVERT),
+            "strikethrough" => Some(Effects::STRIKETHROUGH),
+            _ => None,
+        };
+        if let Some(effect) = effect {
+            style = style.effects(style.get_effects() | effect);
+            continue;
+        }
+
+        let color = parse_color(&lower).with_context(|| format!("Unknown style '{}'", part))?;
+        style = style.fg_color(Some(color));
+    }
+
+    Ok(style)
+}
+
+fn parse_color(name: &str) -> Option<Color> {
+    let (bright, name) = match name.strip_prefix("bright_") {
+        Some(name) => (true, name),
+        None => (false, name),
+    };
+
+    let color = match name {
+        "black" => AnsiColor::Black,
+        "red" => AnsiColor::Red,
+        "green" => AnsiColor::Green,
+        "yellow" => AnsiColor::Yellow,
+        "blue" => AnsiColor::Blue,
+        "magenta" => AnsiColor::Magenta,
+        "cyan" => AnsiColor::Cyan,
+        "white" => AnsiColor::White,
+        _ => return None,
+    };
+
+    Some(color.bright(bright).into())
+}
@@ -0,0 +1,198 @@
+use crate::{Definition, store::Store};
+use anyhow::Result;
+use ratatui::{
+    DefaultTerminal, Frame,
+    crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    layout::{Constraint, Layout},
+    style::{Modifier, Style, Stylize},
+    text::{Line, Span, Text},
+    widgets::{Block, List, ListState, Paragraph, Wrap},
+};
+
+/// Upper bound on the number of matches shown in the results list
+const MAX_RESULTS: usize = 500;
+
+/// Runs the interactive browser over `store` until the user quits. Give the
+/// dictionary under it a [shard cache](crate::Dictionary::with_shard_cache)
+/// so moving between entries doesn't parse their shards again.
+pub fn run(store: &dyn Store) -> Result<()> {
+    let mut app = App::new(store);
+    ratatui::run(|terminal| app.run(terminal))
+}
+
+struct App<'a> {
+    store: &'a dyn Store,
+    query: String,
+    results: Vec<String>,
+    selected: ListState,
+    scroll: u16,
+    quit: bool,
+}
+
+impl<'a> App<'a> {
+    fn new(store: &'a dyn Store) -> Self {
+        Self {
+            store,
+            query: String::new(),
+            results: Vec::new(),
+            selected: ListState::default(),
+            scroll: 0,
+            quit: false,
+        }
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<()> {
+        while !self.quit {
+            let definition = self.selected_definition()?;
+            terminal.draw(|frame| self.draw(frame, definition.as_ref()))?;
+            self.handle_event()?;
+        }
+        Ok(())
+    }
+
+    fn handle_event(&mut self) -> Result<()> {
+        let Event::Key(key) = event::read()? else {
+            return Ok(());
+        };
+        if key.kind != KeyEventKind::Press {
+            return Ok(());
+        }
+
+        match key.code {
+            KeyCode::Esc => self.quit = true,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => self.quit = true,
+            KeyCode::Char(c) => {
+                self.query.push(c);
+                self.refilter()?;
+            }
+            KeyCode::Backspace => {
+                self.query.pop();
+                self.refilter()?;
+            }
+            KeyCode::Down => self.select_next(),
+            KeyCode::Up => self.select_previous(),
+            KeyCode::PageDown => self.scroll = self.scroll.saturating_add(10),
+            KeyCode::PageUp => self.scroll = self.scroll.saturating_sub(10),
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn select_next(&mut self) {
+        self.selected.select_next();
+        self.scroll = 0;
+    }
+
+    fn select_previous(&mut self) {
+        self.selected.select_previous();
+        self.scroll = 0;
+    }
+
+    /// Recomputes the results list for the current query
+    fn refilter(&mut self) -> Result<()> {
+        self.results.clear();
+        self.scroll = 0;
+
+        if !self.query.is_empty() {
+            self.results = self.store.prefix(&self.query)?;
+            self.results.truncate(MAX_RESULTS);
+        }
+
+        self.selected.select(if self.results.is_empty() {
+            None
+        } else {
+            Some(0)
+        });
+        Ok(())
+    }
+
+    /// Looks up the entry for the highlighted result
+    fn selected_definition(&self) -> Result<Option<Definition>> {
+        match self
+            .selected
+            .selected()
+            .and_then(|idx| self.results.get(idx))
+        {
+            Some(word) => self.store.lookup(word),
+            None => Ok(None),
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame, definition: Option<&Definition>) {
+        let [search, body] =
+            Layout::vertical([Constraint::Length(3), Constraint::Min(0)]).areas(frame.area());
+        let [list, pane] =
+            Layout::horizontal([Constraint::Percentage(30), Constraint::Percentage(70)])
+                .areas(body);
+
+        frame.render_widget(
+            Paragraph::new(self.query.as_str())
+                .block(Block::bordered().title(" Search (Esc to quit) ")),
+            search,
+        );
+
+        let results = List::new(self.results.iter().map(String::as_str))
+            .block(Block::bordered().title(format!(" {} matches ", self.results.len())))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(results, list, &mut self.selected);
+
+        let text = definition.map(definition_text).unwrap_or_default();
+        frame.render_widget(
+            Paragraph::new(text)
+                .block(Block::bordered().title(" Definition "))
+                .wrap(Wrap { trim: false })
+                .scroll((self.scroll, 0)),
+            pane,
+        );
+    }
+}
+
+/// Renders an entry with the same layout and colors as [`Definition::print_colored`]
+fn definition_text(def: &Definition) -> Text<'static> {
+    let mut header = vec![def.word.clone().bold().cyan()];
+    if let Some(marker) = def.commonness() {
+        header.push(format!("  {}", marker).dim());
+    }
+    let mut lines = vec![Line::from(header)];
+    if let Some(line) = def.pronunciation_line() {
+        lines.push(Line::from(format!("  {}", line).dim()));
+    }
+
+    for (etym_idx, etymology) in def.etymologies.iter().enumerate() {
+        if let Some(heading) = def.etymology_heading(etym_idx) {
+            lines.push(Line::from(format!("{}:", heading).bold().yellow()));
+        }
+        if let Some(origin) = &etymology.origin {
+            lines.push(Line::from(format!("  {}", origin).italic()));
+        }
+
+        for pos in &etymology.parts_of_speech {
+            lines.push(Line::from(vec![
+                Span::raw("  "),
+                pos.part_of_speech.clone().bold().green(),
+            ]));
+
+            for (sense_idx, sense) in pos.senses.iter().enumerate() {
+                lines.push(Line::from(vec![
+                    Span::raw("    "),
+                    format!("{}.", sense_idx + 1).bold(),
+                    Span::raw(format!(" {}", sense.sense)),
+                ]));
+
+                if let Some(date) = &sense.date
+                    && !date.is_empty()
+                {
+                    lines.push(Line::from(format!("       [{}]", date).italic().dim()));
+                }
+
+                for example in &sense.examples {
+                    lines.push(Line::from(format!("       \"{}\"", example).dim()));
+                }
+            }
+            lines.push(Line::default());
+        }
+    }
+
+    Text::from(lines)
+}
@@ -0,0 +1,141 @@
+use crate::{Definition, Dictionary, dictionary::normalize};
+use anyhow::Result;
+use std::{collections::BTreeMap, path::PathBuf};
+
+/// Something wrong with a shard, and where
+#[derive(Debug, Clone)]
+pub struct Problem {
+    /// The shard, relative to the root and without extension
+    pub shard: PathBuf,
+    /// 1-based line and column in the shard's JSON
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+/// What [`validate`] looked at and found
+#[derive(Debug, Default)]
+pub struct Report {
+    pub shards: usize,
+    pub entries: usize,
+    pub problems: Vec<Problem>,
+}
+
+/// Parses every shard of `dict` and checks each entry: that it's filed
+/// under its own headword, in the shard that headword belongs in, and that
+/// it has at least one sense and no blank ones. A shard that doesn't parse
+/// is one problem at the spot the parser gave up.
+pub fn validate(dict: &Dictionary) -> Result<Report> {
+    let mut report = Report::default();
+
+    for shard in dict.shards()? {
+        report.shards += 1;
+        let contents = dict.read_shard(&shard)?;
+        let entries: BTreeMap<String, Definition> = match serde_json::from_str(&contents) {
+            Ok(entries) => entries,
+            Err(e) => {
+                // the message carries the position too, which is shown separately
+                let message = e.to_string();
+                let position = format!(" at line {} column {}", e.line(), e.column());
+                report.problems.push(Problem {
+                    shard,
+                    line: e.line(),
+                    column: e.column(),
+                    message: message.trim_end_matches(&position).to_string(),
+                });
+                continue;
+            }
+        };
+
+        for (key, def) in &entries {
+            report.entries += 1;
+            let mut messages = Vec::new();
+            if def.word != *key {
+                messages.push(format!("'{}' is filed under '{}'", def.word, key));
+            }
+            if normalize(key) != *key {
+                messages.push(format!("'{}' isn't in Unicode NFC", key));
+            }
+            match dict.shard_path(&normalize(key)) {
+                Some(home) if home != shard => {
+                    messages.push(format!("'{}' belongs in shard {}", key, home.display()))
+                }
+                None => messages.push(format!("'{}' has no letters to shard it by", key)),
+                _ => {}
+            }
+            for message in entry_problems(def) {
+                messages.push(format!("'{}' {}", key, message));
+            }
+
+            // finding the entry means scanning the shard, so only do it for a problem
+            if messages.is_empty() {
+                continue;
+            }
+            let (line, column) = key_position(&contents, key);
+            report
+                .problems
+                .extend(messages.into_iter().map(|message| Problem {
+                    shard: shard.clone(),
+                    line,
+                    column,
+                    message,
+                }));
+        }
+    }
+
+    Ok(report)
+}
+
+/// What's missing or blank in `def`
+fn entry_problems(def: &Definition) -> Vec<String> {
+    let mut problems = Vec::new();
+    if def.etymologies.is_empty() {
+        problems.push("has no etymologies".to_string());
+    }
+    for (e, etymology) in def.etymologies.iter().enumerate() {
+        if etymology.parts_of_speech.is_empty() {
+            problems.push(format!("has no parts of speech in etymology {}", e + 1));
+        }
+        for part in &etymology.parts_of_speech {
+            if part.part_of_speech.trim().is_empty() {
+                problems.push(format!("has a blank part of speech in etymology {}", e + 1));
+            }
+            if part.senses.is_empty() {
+                problems.push(format!(
+                    "has no senses for {} in etymology {}",
+                    part.part_of_speech,
+                    e + 1
+                ));
+            }
+            for (s, sense) in part.senses.iter().enumerate() {
+                if sense.sense.trim().is_empty() {
+                    problems.push(format!(
+                        "has a blank sense {} for {} in etymology {}",
+                        s + 1,
+                        part.part_of_speech,
+                        e + 1
+                    ));
+                }
+            }
+        }
+    }
+    problems
+}
+
+/// The line and column where the entry for `key` starts in a shard's JSON,
+/// or the start of the file if it can't be found
+fn key_position(contents: &str, key: &str) -> (usize, usize) {
+    let quoted = serde_json::to_string(key).unwrap_or_default();
+    // the key is the quoted headword followed by a colon; the same text as a
+    // value is followed by a comma or bracket instead
+    let offset = contents
+        .match_indices(&quoted)
+        .map(|(idx, _)| idx)
+        .find(|idx| contents[idx + quoted.len()..].trim_start().starts_with(':'))
+        .unwrap_or(0);
+
+    let before = &contents[..offset];
+    let line = before.matches('\n').count() + 1;
+    let column = before.len() - before.rfind('\n').map_or(0, |nl| nl + 1) + 1;
+    (line, column)
+}
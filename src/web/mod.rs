@@ -0,0 +1,88 @@
+use crate::{
+    Definition,
+    dictionary::{case_variants, normalize, parse_entries},
+    manifest::{self, Manifest},
+    sharding::ShardScheme,
+};
+use anyhow::Result;
+use std::{
+    cell::{Cell, RefCell},
+    collections::{HashMap, HashSet},
+    future::Future,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+/// Where a [`WebStore`] gets its shards from
+pub trait ShardLoader {
+    /// The JSON of the shard at `path`, relative to the dictionary root and
+    /// without extension, or `None` if there's no such shard
+    fn load(&self, path: &Path) -> impl Future<Output = Result<Option<String>>>;
+}
+
+/// Lookups in a shard tree that can only be read asynchronously, like one
+/// served to a browser next to a static page. Every shard fetched is kept for
+/// the life of the store, unparsed, and only the entries asked for are built.
+pub struct WebStore<L> {
+    loader: L,
+    /// Read from the tree's manifest on the first lookup
+    scheme: Cell<Option<ShardScheme>>,
+    shards: RefCell<HashMap<PathBuf, Option<Rc<str>>>>,
+}
+
+impl<L: ShardLoader> WebStore<L> {
+    pub fn new(loader: L) -> Self {
+        Self {
+            loader,
+            scheme: Cell::new(None),
+            shards: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Looks up a single headword, trying the same spellings as
+    /// [`Dictionary::lookup`](crate::Dictionary::lookup)
+    pub async fn lookup(&self, word: &str) -> Result<Option<Definition>> {
+        let word = normalize(word);
+        let Some(path) = self.scheme().await?.path(&word) else {
+            return Ok(None);
+        };
+        let Some(contents) = self.shard(&path).await? else {
+            return Ok(None);
+        };
+
+        let variants = case_variants(&word);
+        let wanted: HashSet<String> = variants.iter().cloned().collect();
+        let mut entries = parse_entries(&contents, &wanted)?;
+        Ok(variants.iter().find_map(|variant| entries.remove(variant)))
+    }
+
+    /// How the tree is sharded, from its manifest, or the default for a tree
+    /// without one
+    async fn scheme(&self) -> Result<ShardScheme> {
+        if let Some(scheme) = self.scheme.get() {
+            return Ok(scheme);
+        }
+        let name = Path::new(manifest::FILE_NAME).with_extension("");
+        let scheme = match self.loader.load(&name).await? {
+            Some(contents) => serde_json::from_str::<Manifest>(&contents)?.shard_scheme,
+            None => ShardScheme::default(),
+        };
+        self.scheme.set(Some(scheme));
+        Ok(scheme)
+    }
+
+    /// The JSON of the shard at `path`, loading it the first time
+    async fn shard(&self, path: &Path) -> Result<Option<Rc<str>>> {
+        if let Some(contents) = self.shards.borrow().get(path) {
+            return Ok(contents.clone());
+        }
+        let contents: Option<Rc<str>> = self.loader.load(path).await?.map(Into::into);
+        self.shards
+            .borrow_mut()
+            .insert(path.to_path_buf(), contents.clone());
+        Ok(contents)
+    }
+}
@@ -0,0 +1,110 @@
+use super::{ShardLoader, WebStore};
+use anyhow::{Context, Result};
+use js_sys::{Function, Promise, Reflect};
+use std::path::Path;
+use wasm_bindgen::{JsCast, JsValue, prelude::*};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::Response;
+
+/// Loads shards with `fetch` from a shard tree served at `base_url`, as plain
+/// `.json` files. Compressing them is left to the web server.
+pub struct FetchLoader {
+    base_url: String,
+}
+
+impl FetchLoader {
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+}
+
+impl ShardLoader for FetchLoader {
+    async fn load(&self, path: &Path) -> Result<Option<String>> {
+        let parts: Vec<_> = path.iter().map(|part| part.to_string_lossy()).collect();
+        let url = format!("{}/{}.json", self.base_url, parts.join("/"));
+
+        let response: Response = JsFuture::from(fetch(&url)?)
+            .await
+            .map_err(|e| js_error(e, &url))?
+            .dyn_into()
+            .map_err(|e| js_error(e, &url))?;
+        if response.status() == 404 {
+            return Ok(None);
+        }
+        if !response.ok() {
+            anyhow::bail!("Failed to fetch {}: HTTP {}", url, response.status());
+        }
+
+        let text = response.text().map_err(|e| js_error(e, &url))?;
+        let text = JsFuture::from(text).await.map_err(|e| js_error(e, &url))?;
+        Ok(Some(
+            text.as_string()
+                .with_context(|| format!("Failed to read {}", url))?,
+        ))
+    }
+}
+
+/// Calls the global `fetch`, which windows and workers both have
+fn fetch(url: &str) -> Result<Promise> {
+    let global = js_sys::global();
+    let fetch: Function = Reflect::get(&global, &JsValue::from_str("fetch"))
+        .ok()
+        .and_then(|fetch| fetch.dyn_into().ok())
+        .context("fetch isn't available here")?;
+    let promise = fetch
+        .call1(&global, &JsValue::from_str(url))
+        .map_err(|e| js_error(e, url))?;
+    promise.dyn_into().map_err(|e| js_error(e, url))
+}
+
+fn js_error(e: JsValue, url: &str) -> anyhow::Error {
+    anyhow::anyhow!("Failed to fetch {}: {:?}", url, e)
+}
+
+/// A dictionary for JavaScript, read from a shard tree on the web:
+///
+/// ```js
+/// const dict = new WebDictionary("https://example.com/dict");
+/// const html = await dict.lookupHtml("cat", true);
+/// ```
+#[wasm_bindgen]
+pub struct WebDictionary {
+    store: WebStore<FetchLoader>,
+}
+
+#[wasm_bindgen]
+impl WebDictionary {
+    /// A dictionary whose shards are under `base_url`, laid out as on disk
+    #[wasm_bindgen(constructor)]
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            store: WebStore::new(FetchLoader::new(base_url)),
+        }
+    }
+
+    /// The entry for `word` as JSON, shaped as in a shard, or `undefined`
+    pub async fn lookup(&self, word: String) -> Result<Option<String>, JsError> {
+        match self.store.lookup(&word).await.map_err(to_js)? {
+            Some(def) => Ok(Some(serde_json::to_string(&def)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// The entry for `word` as an HTML fragment, like `--format html`, or
+    /// `undefined`
+    #[wasm_bindgen(js_name = lookupHtml)]
+    pub async fn lookup_html(
+        &self,
+        word: String,
+        examples: bool,
+    ) -> Result<Option<String>, JsError> {
+        let def = self.store.lookup(&word).await.map_err(to_js)?;
+        Ok(def.map(|def| def.to_html(examples)))
+    }
+}
+
+fn to_js(e: anyhow::Error) -> JsError {
+    JsError::new(&format!("{:#}", e))
+}
@@ -0,0 +1,37 @@
+use anyhow::Result;
+
+/// Checks `date` looks like `YYYY-MM-DD`
+pub fn check_date(date: &str) -> Result<()> {
+    let parts: Vec<&str> = date.split('-').collect();
+    let valid = matches!(parts.as_slice(), [y, m, d]
+        if y.len() == 4 && m.len() == 2 && d.len() == 2
+            && parts.iter().all(|p| p.chars().all(|c| c.is_ascii_digit()))
+            && (1..=12).contains(&m.parse::<u32>().unwrap_or(0))
+            && (1..=31).contains(&d.parse::<u32>().unwrap_or(0)));
+    if !valid {
+        anyhow::bail!("Expected a date like 2024-03-01, got '{}'", date);
+    }
+    Ok(())
+}
+
+/// 64-bit FNV-1a, which unlike the std hashers is the same on every machine
+/// and every release
+fn fnv1a(parts: &[&[u8]]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for part in parts {
+        for byte in *part {
+            hash ^= u64::from(*byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    hash
+}
+
+/// The word of the day for `date`: the headword whose hash together with the
+/// date is lowest. Everyone with the same dictionary gets the same word, and
+/// adding a few words to it rarely changes the pick.
+pub fn pick<'a>(words: impl IntoIterator<Item = &'a String>, date: &str) -> Option<&'a String> {
+    words
+        .into_iter()
+        .min_by_key(|word| (fnv1a(&[date.as_bytes(), b"\0", word.as_bytes()]), *word))
+}